@@ -19,6 +19,12 @@ const LANGUAGE_PATTERNS = {
     files: ['Cargo.toml', 'Cargo.lock'],
     bridge: 'bridge_rust.rs',
     targetPath: (projectRoot) => path.join(projectRoot, 'src', 'local_kg_bridge.rs'),
+    // Submodules of the bridge (src/local_kg_bridge/*.rs)
+    modules: 'bridge_rust',
+    modulesPath: (projectRoot) => path.join(projectRoot, 'src', 'local_kg_bridge'),
+    // Helper binaries (src/bin/*.rs)
+    bins: 'bridge_rust_bin',
+    binsPath: (projectRoot) => path.join(projectRoot, 'src', 'bin'),
     priority: 1
   },
   python: {
@@ -190,6 +196,12 @@ export function installBridge(language, projectRoot = process.cwd()) {
   // Copy bridge file
   try {
     fs.copyFileSync(sourceBridge, targetPath);
+    if (config.modules) {
+      fs.cpSync(path.join(__dirname, config.modules), config.modulesPath(projectRoot), { recursive: true });
+    }
+    if (config.bins) {
+      fs.cpSync(path.join(__dirname, config.bins), config.binsPath(projectRoot), { recursive: true });
+    }
     console.log(`✅ Installed ${language} bridge: ${path.relative(projectRoot, targetPath)}`);
     return true;
   } catch (error) {
@@ -215,6 +227,11 @@ Add to Cargo.toml:
 tokio = { version = "1", features = ["process", "rt"] }
 serde = { version = "1", features = ["derive"] }
 serde_json = "1"
+tracing = "0.1"
+tracing-subscriber = { version = "0.3", default-features = false, features = ["registry", "std"] }
+log = { version = "0.4", features = ["std"] }
+rusqlite = { version = "0.32", features = ["bundled"] }
+uuid = { version = "1", features = ["v4"] }
 `,
     javascript: `
 Add to package.json:
@@ -1,91 +1,335 @@
 /*!
  * Rust Bridge for Nebula Local KG
  * 
- * Provides a Rust interface to interact with the Python-based Local Knowledge Graph.
+ * Provides a Rust interface to the Local Knowledge Graph. By default the bridge
+ * talks to the Universal Schema SQLite file directly; set `"backend"` in
+ * `.nebula/config.json` (or `NEBULA_KG_BACKEND`) to `python` to go through a
+ * persistent `local_kg/kg_worker.py` process, or to `memory` for a throwaway
+ * in-process store.
  * 
  * Usage:
  *   mod local_kg_bridge;
- *   use local_kg_bridge::LocalKGBridge;
+ *   use local_kg_bridge::{Category, LocalKGBridge, Severity};
  *   
  *   let kg = LocalKGBridge::new(None)?;
- *   kg.capture_error("E0308: mismatched types", "CompileError", "rust", "high").await?;
+ *   kg.capture_error_async("E0308: mismatched types", Category::CompileError, "rust", Severity::High).await?;
  */
 
-use std::process::Command;
 use std::fs;
 use std::env;
+use std::sync::{Arc, RwLock};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+mod backend;
+#[cfg(test)]
+mod backend_tests;
+mod capture;
+mod cargo_diagnostics;
+mod error;
+#[cfg(feature = "semantic-index")]
+mod hnsw;
+mod http;
+mod log_bridge;
+mod memory;
+mod migrations;
+mod panic_hook;
+mod python;
+mod resolution;
+mod rpc;
+mod schema;
+mod search;
+#[cfg(feature = "semantic-index")]
+mod semantic;
+mod signature;
+mod similarity;
+mod solution;
+mod sqlite;
+mod sync;
+mod taxonomy;
+mod test_failures;
+mod tracing_layer;
+mod worker;
+
+pub use backend::KgBackend;
+pub use capture::ErrorCapture;
+pub use cargo_diagnostics::{parse_cargo_output, Diagnostic, DiagnosticCollector, DiagnosticNote};
+pub use error::NebulaKgError;
+pub use http::{HttpTransport, DEFAULT_SYNC_TIMEOUT};
+pub use log_bridge::KgLogger;
+pub use memory::MemoryBackend;
+pub use migrations::{
+    detect_schema_version, migrate_db, pending_migrations, run_migrations, Migration,
+    LEGACY_SCHEMA_VERSION, MIGRATIONS,
+};
+pub use panic_hook::{install_panic_hook, PanicHook, DEFAULT_PANIC_CAPTURE_TIMEOUT};
+pub use python::PythonBackend;
+pub use resolution::{ErrorResolution, Fix, ResolutionStats};
+pub use schema::SCHEMA_VERSION;
+pub use search::{PatternSearch, SearchHit};
+#[cfg(feature = "semantic-index")]
+pub use semantic::{
+    Embedder, HashingEmbedder, SemanticIndex, SemanticMatch, DEFAULT_EMBEDDING_DIMENSIONS,
+};
+pub use signature::{normalize_signature, NormalizedSignature};
+pub use similarity::{
+    SimilarPattern, SimilarityMetric, SimilaritySearch, DEFAULT_SIMILARITY_THRESHOLD,
+};
+pub use solution::{Solution, SolutionFeedback};
+pub use sqlite::SqliteBackend;
+pub use sync::{
+    CentralSync, OutboxItem, OutboxStatus, OutboxUpdate, RetryPolicy, SyncRecord, SyncStatus,
+    SyncSummary, SyncTransport, SyncType, UnsyncedSolution, PATTERN_SUBMIT_PATH, SOLUTION_SYNC_PATH,
+};
+pub use taxonomy::{Category, Severity};
+pub use test_failures::{
+    parse_junit_xml, parse_libtest_json, parse_libtest_output, test_signature, LibtestJsonParser,
+    LibtestParser, PassedTest, TestFailure, TestReport,
+};
+pub use tracing_layer::{KgLayer, DEFAULT_DEDUP_WINDOW};
+pub use worker::{PythonWorkerBackend, DEFAULT_WORKER_TIMEOUT};
+
+/// Default location of the Universal Schema database
+const DEFAULT_DB_PATH: &str = "local_kg/universal_memory.sqlite";
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct NebulaConfig {
     pub language: Option<String>,
     pub framework: Option<String>,
     pub local_kg_db: Option<String>,
+    /// Central KG to sync to (`http://` only, see `HttpTransport`)
     pub central_kg_url: Option<String>,
     pub python_command: Option<String>,
+    /// Sync to the Central KG in the background after captures and solutions
     pub auto_sync: Option<bool>,
+    /// Minimum minutes between automatic syncs (default: no limit)
+    #[serde(default)]
+    pub sync_interval_minutes: Option<u64>,
+    /// Sent as `X-API-Key` to the Central KG
+    #[serde(default)]
+    pub central_kg_api_key: Option<String>,
+    /// Identifies this instance to the Central KG
+    #[serde(default)]
+    pub central_kg_instance_id: Option<String>,
+    /// Storage backend: "sqlite" (default), "python", "python-subprocess" or "memory"
+    pub backend: Option<String>,
+    /// Per-request timeout for the Python worker, in milliseconds
+    pub python_timeout_ms: Option<u64>,
+    /// Keep a `<db>.vectors` semantic index up to date (needs the
+    /// `semantic-index` feature)
+    #[serde(default)]
+    pub semantic_index: Option<bool>,
 }
 
-#[derive(Debug, Clone)]
-pub struct ErrorCapture {
+/// A row of the Universal Schema `patterns` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorPattern {
+    pub id: String,
     pub signature: String,
-    pub category: String,
-    pub language: String,
-    pub severity: String,
+    pub category: Option<Category>,
     pub description: Option<String>,
+    /// Best known solution (promoted when a solution is rated effective)
+    pub solution: Option<String>,
+    pub occurrence_count: i64,
+    pub last_seen_at: Option<String>,
+    /// Set when the error stopped occurring (e.g. its failing test passed);
+    /// cleared when it is captured again
+    #[serde(default)]
+    pub resolved_at: Option<String>,
+    /// Every solution recorded for the pattern, best first (see `Solution`)
+    #[serde(default)]
+    pub solutions: Vec<Solution>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ErrorPattern {
+/// Aggregate counts over the Universal Schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternSummary {
+    pub total_patterns: i64,
+    /// `error` events captured (every occurrence, not just distinct patterns)
+    pub total_errors: i64,
+    /// `solution` events recorded
+    pub total_solutions: i64,
+    pub patterns_with_solution: i64,
+    /// Most frequent patterns, highest occurrence count first
+    pub top_patterns: Vec<ErrorPattern>,
+}
+
+/// A row of the Universal Schema `events` stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KgEvent {
     pub id: String,
-    pub error_signature: String,
-    pub error_category: String,
-    pub language: String,
-    pub severity: String,
-    pub description: Option<String>,
-    pub occurrence_count: i32,
-    pub first_seen: String,
-    pub last_seen: String,
-    pub solution_count: i32,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub phase: Option<String>,
+    pub content: Option<String>,
+    pub metadata: serde_json::Value,
+    pub created_at: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct PatternSummary {
-    pub total_patterns: i32,
-    pub total_solutions: i32,
-    pub languages: serde_json::Value,
-    pub top_errors: Vec<serde_json::Value>,
+impl KgEvent {
+    /// `metadata.severity` of an `error` event, parsed leniently
+    pub fn severity(&self) -> Option<Severity> {
+        self.metadata["severity"].as_str().map(Severity::parse_lenient)
+    }
+
+    /// `metadata.category` of an `error` event
+    pub fn category(&self) -> Option<Category> {
+        self.metadata["category"].as_str().map(Category::parse_lenient)
+    }
+}
+
+/// Result of `LocalKGBridge::capture_with_similar`
+#[derive(Debug, Clone)]
+pub struct CaptureOutcome {
+    pub event_id: String,
+    /// Patterns seen before this capture that resemble it (the same signature
+    /// included, if it had occurred already), most similar first
+    pub similar: Vec<SimilarPattern>,
+}
+
+impl CaptureOutcome {
+    /// Similar patterns that have a known solution
+    pub fn known_solutions(&self) -> impl Iterator<Item = &SimilarPattern> {
+        self.similar.iter().filter(|s| s.pattern.solution.is_some())
+    }
 }
 
 pub struct LocalKGBridge {
     config: NebulaConfig,
     db_path: String,
-    python_cmd: String,
+    backend: Arc<dyn KgBackend>,
+    central: Option<Arc<CentralSync>>,
+    #[cfg(feature = "semantic-index")]
+    semantic: Option<Arc<SemanticIndex>>,
 }
 
 impl LocalKGBridge {
-    /// Create a new bridge instance
-    pub fn new(db_path: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+    /// Create a new bridge instance.
+    /// Fails with `NebulaKgError::SchemaMismatch` if the store's recorded
+    /// schema version isn't `SCHEMA_VERSION`.
+    pub fn new(db_path: Option<&str>) -> Result<Self, NebulaKgError> {
         let config = Self::load_config()?;
         let db_path = db_path
             .map(String::from)
             .or_else(|| config.local_kg_db.clone())
-            .unwrap_or_else(|| "local_kg/nebula_ide_local.db".to_string());
+            .unwrap_or_else(|| DEFAULT_DB_PATH.to_string());
+        let backend = Self::open_backend(&config, &db_path)?;
+
+        let bridge = Self {
+            central: Self::central_sync(&config),
+            config,
+            db_path,
+            backend,
+            #[cfg(feature = "semantic-index")]
+            semantic: None,
+        };
+        if bridge.config.semantic_index == Some(true) {
+            #[cfg(feature = "semantic-index")]
+            {
+                let index = SemanticIndex::open(
+                    SemanticIndex::default_path(&bridge.db_path),
+                    Arc::new(HashingEmbedder::default()),
+                )?;
+                return bridge.with_semantic_index(index);
+            }
+            #[cfg(not(feature = "semantic-index"))]
+            tracing::warn!("semantic_index is set but the bridge was built without the semantic-index feature");
+        }
+        Ok(bridge)
+    }
+
+    /// The backend `config` selects, over `db_path`, after checking its schema version
+    fn open_backend(config: &NebulaConfig, db_path: &str) -> Result<Arc<dyn KgBackend>, NebulaKgError> {
         let python_cmd = config.python_command.clone().unwrap_or_else(|| "python".to_string());
+        // The Python tooling reads the Universal Schema but never creates it
+        if matches!(config.backend.as_deref(), Some("python" | "python-subprocess")) {
+            migrate_db(db_path)?;
+        }
+
+        let backend: Arc<dyn KgBackend> = match config.backend.as_deref() {
+            None | Some("sqlite") => Arc::new(SqliteBackend::open(db_path)?),
+            Some("python") => {
+                let timeout = config
+                    .python_timeout_ms
+                    .map(std::time::Duration::from_millis)
+                    .unwrap_or(DEFAULT_WORKER_TIMEOUT);
+                Arc::new(PythonWorkerBackend::new(db_path, &python_cmd).with_timeout(timeout))
+            }
+            Some("python-subprocess") => Arc::new(PythonBackend::new(db_path, &python_cmd)),
+            Some("memory") => Arc::new(MemoryBackend::new()),
+            Some(other) => {
+                return Err(NebulaKgError::Config(format!("Unknown Local KG backend: {}", other)))
+            }
+        };
+        schema::require_version(backend.schema_version()?.as_deref())?;
+        Ok(backend)
+    }
+
+    /// Create a bridge over an explicit backend (e.g. `MemoryBackend` in tests)
+    pub fn with_backend(backend: Arc<dyn KgBackend>) -> Result<Self, NebulaKgError> {
+        let config = Self::load_config()?;
+        let db_path = config.local_kg_db.clone().unwrap_or_else(|| DEFAULT_DB_PATH.to_string());
+        schema::require_version(backend.schema_version()?.as_deref())?;
 
         Ok(Self {
+            central: Self::central_sync(&config),
             config,
             db_path,
-            python_cmd,
+            backend,
+            #[cfg(feature = "semantic-index")]
+            semantic: None,
         })
     }
 
+    /// Keep `index` up to date with every capture and solution from now on,
+    /// after embedding the patterns it doesn't have yet
+    #[cfg(feature = "semantic-index")]
+    pub fn with_semantic_index(mut self, index: SemanticIndex) -> Result<Self, NebulaKgError> {
+        let embedded = index.sync(self.backend.as_ref())?;
+        if embedded > 0 {
+            tracing::info!("Embedded {} pattern(s) into the semantic index", embedded);
+        }
+        let index = Arc::new(index);
+        self.backend = Arc::new(semantic::IndexedBackend::new(self.backend.clone(), index.clone()));
+        self.semantic = Some(index);
+        Ok(self)
+    }
+
+    /// Sync through `sync` instead of the client configured by `central_kg_url`
+    /// (e.g. one pointed at a mock server in tests)
+    pub fn with_central_sync(mut self, sync: CentralSync) -> Self {
+        self.central = Some(Arc::new(sync));
+        self
+    }
+
+    /// Client for the configured Central KG. A URL the built-in transport
+    /// can't use only disables sync, it doesn't stop the bridge from opening.
+    fn central_sync(config: &NebulaConfig) -> Option<Arc<CentralSync>> {
+        match CentralSync::from_config(config) {
+            Ok(sync) => sync.map(Arc::new),
+            Err(e) => {
+                tracing::warn!("Central KG sync disabled: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Configuration the bridge was created with
+    pub fn config(&self) -> &NebulaConfig {
+        &self.config
+    }
+
+    /// Database path used by file-backed backends
+    pub fn db_path(&self) -> &str {
+        &self.db_path
+    }
+
     /// Load configuration from .nebula/config.json or environment variables
-    fn load_config() -> Result<NebulaConfig, Box<dyn std::error::Error>> {
+    fn load_config() -> Result<NebulaConfig, NebulaKgError> {
         // Priority 1: .nebula/config.json
         let config_path = ".nebula/config.json";
         if let Ok(contents) = fs::read_to_string(config_path) {
-            return Ok(serde_json::from_str(&contents)?);
+            return serde_json::from_str(&contents)
+                .map_err(|e| NebulaKgError::Config(format!("{}: {}", config_path, e)));
         }
 
         // Priority 2: Environment variables
@@ -96,7 +340,21 @@ impl LocalKGBridge {
                 local_kg_db: env::var("NEBULA_LOCAL_KG_DB").ok(),
                 central_kg_url: env::var("NEBULA_CENTRAL_KG_URL").ok(),
                 python_command: env::var("PYTHON_CMD").ok(),
-                auto_sync: None,
+                auto_sync: env::var("NEBULA_AUTO_SYNC")
+                    .ok()
+                    .map(|v| matches!(v.as_str(), "1" | "true" | "yes")),
+                sync_interval_minutes: env::var("NEBULA_SYNC_INTERVAL_MINUTES")
+                    .ok()
+                    .and_then(|m| m.parse().ok()),
+                central_kg_api_key: env::var("NEBULA_CENTRAL_KG_API_KEY").ok(),
+                central_kg_instance_id: env::var("NEBULA_CENTRAL_KG_INSTANCE_ID").ok(),
+                backend: env::var("NEBULA_KG_BACKEND").ok(),
+                python_timeout_ms: env::var("NEBULA_PYTHON_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|ms| ms.parse().ok()),
+                semantic_index: env::var("NEBULA_SEMANTIC_INDEX")
+                    .ok()
+                    .map(|v| matches!(v.as_str(), "1" | "true" | "yes")),
             });
         }
 
@@ -104,44 +362,47 @@ impl LocalKGBridge {
         Ok(NebulaConfig {
             language: Some("rust".to_string()),
             framework: None,
-            local_kg_db: Some("local_kg/nebula_ide_local.db".to_string()),
+            local_kg_db: Some(DEFAULT_DB_PATH.to_string()),
             central_kg_url: None,
             python_command: Some("python".to_string()),
             auto_sync: Some(true),
+            sync_interval_minutes: None,
+            central_kg_api_key: None,
+            central_kg_instance_id: None,
+            backend: None,
+            python_timeout_ms: None,
+            semantic_index: None,
         })
     }
 
+    /// Record an error with all its details (see `ErrorCapture`'s builder).
+    /// Returns the event id.
+    pub async fn capture(&self, capture: ErrorCapture) -> Result<String, NebulaKgError> {
+        self.run_write(move |backend| backend.capture(&capture)).await
+    }
+
+    /// Look up similar earlier errors, then record this one. Use the outcome to
+    /// show "seen similar before" together with the known solution.
+    pub async fn capture_with_similar(&self, capture: ErrorCapture) -> Result<CaptureOutcome, NebulaKgError> {
+        self.run_write(move |backend| {
+            let similar = backend.find_similar(&SimilaritySearch::new(capture.signature.clone()))?;
+            let event_id = backend.capture(&capture)?;
+            Ok(CaptureOutcome { event_id, similar })
+        })
+        .await
+    }
+
     /// Capture an error pattern to the local KG (async, non-blocking)
     pub async fn capture_error_async(
         &self,
         signature: &str,
-        category: &str,
+        category: impl Into<Category>,
         language: &str,
-        severity: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let python_code = format!(
-            r#"
-import sys
-sys.path.insert(0, '.')
-from local_kg.local_kg import get_local_kg
-
-kg = get_local_kg('{}')
-pattern_id = kg.capture_error(
-    error_signature='{}',
-    error_category='{}',
-    language='{}',
-    severity='{}'
-)
-print(pattern_id)
-"#,
-            self.db_path,
-            Self::escape_python(signature),
-            Self::escape_python(category),
-            Self::escape_python(language),
-            Self::escape_python(severity)
-        );
-
-        self.run_python(&python_code).await
+        severity: Severity,
+    ) -> Result<String, NebulaKgError> {
+        let (signature, category, language) = (signature.to_string(), category.into(), language.to_string());
+        self.run_write(move |backend| backend.capture_error(&signature, category, &language, severity))
+            .await
     }
 
     /// Search for similar error patterns
@@ -149,26 +410,39 @@ print(pattern_id)
         &self,
         query: &str,
         limit: usize,
-    ) -> Result<Vec<ErrorPattern>, Box<dyn std::error::Error>> {
-        let python_code = format!(
-            r#"
-import sys
-import json
-sys.path.insert(0, '.')
-from local_kg.local_kg import get_local_kg
-
-kg = get_local_kg('{}')
-patterns = kg.search_patterns('{}', {})
-print(json.dumps(patterns, default=str))
-"#,
-            self.db_path,
-            Self::escape_python(query),
-            limit
-        );
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        let query = query.to_string();
+        self.run_blocking(move |backend| backend.search_patterns(&query, limit)).await
+    }
+
+    /// Full-text search (BM25-ranked, with highlighted snippets), see `PatternSearch`
+    pub async fn search(&self, search: PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.search(&search)).await
+    }
 
-        let result = self.run_python(&python_code).await?;
-        let patterns: Vec<ErrorPattern> = serde_json::from_str(&result)?;
-        Ok(patterns)
+    /// Fuzzy match against known patterns (see `SimilaritySearch`)
+    pub async fn find_similar(&self, search: SimilaritySearch) -> Result<Vec<SimilarPattern>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.find_similar(&search)).await
+    }
+
+    /// Patterns closest in meaning to `text` (e.g. a new error message), using
+    /// the semantic index. Fails with `Config` if no index is enabled.
+    #[cfg(feature = "semantic-index")]
+    pub async fn semantic_search(&self, text: &str, limit: usize) -> Result<Vec<SemanticMatch>, NebulaKgError> {
+        let index = self.semantic.clone().ok_or_else(|| {
+            NebulaKgError::Config("semantic index not enabled (set \"semantic_index\": true)".to_string())
+        })?;
+        let text = text.to_string();
+        self.run_blocking(move |backend| {
+            let mut matches = Vec::new();
+            for (_, signature, score) in index.nearest(&text, limit)? {
+                if let Some(pattern) = semantic::find_pattern(backend, &signature)? {
+                    matches.push(SemanticMatch { pattern, score });
+                }
+            }
+            Ok(matches)
+        })
+        .await
     }
 
     /// Add a solution to an existing pattern
@@ -177,117 +451,295 @@ print(json.dumps(patterns, default=str))
         pattern_id: &str,
         solution_text: &str,
         effectiveness: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let python_code = format!(
-            r#"
-import sys
-sys.path.insert(0, '.')
-from local_kg.local_kg import get_local_kg
-
-kg = get_local_kg('{}')
-solution_id = kg.add_solution(
-    pattern_id='{}',
-    solution_text='{}',
-    effectiveness='{}'
-)
-print(solution_id)
-"#,
-            self.db_path,
-            Self::escape_python(pattern_id),
-            Self::escape_python(solution_text),
-            Self::escape_python(effectiveness)
+    ) -> Result<String, NebulaKgError> {
+        let (pattern_id, solution_text, effectiveness) = (
+            pattern_id.to_string(),
+            solution_text.to_string(),
+            effectiveness.to_string(),
         );
+        self.run_write(move |backend| backend.add_solution(&pattern_id, &solution_text, &effectiveness))
+            .await
+    }
+
+    /// Record a structured solution for a pattern (by id or signature).
+    /// Returns the event id.
+    pub async fn record_solution(&self, pattern_id: &str, solution: Solution) -> Result<String, NebulaKgError> {
+        let pattern_id = pattern_id.to_string();
+        self.run_write(move |backend| backend.record_solution(&pattern_id, &solution))
+            .await
+    }
 
-        self.run_python(&python_code).await
+    /// Record whether a solution (by its `id`) helped when applied. Re-ranks
+    /// the pattern's solutions and may change which one is promoted.
+    pub async fn record_feedback(&self, solution_id: &str, feedback: SolutionFeedback) -> Result<String, NebulaKgError> {
+        let solution_id = solution_id.to_string();
+        self.run_blocking(move |backend| backend.record_feedback(&solution_id, &feedback))
+            .await
+    }
+
+    /// Link a captured error (the id `capture` returned) to the fix that
+    /// resolved it, recording the time to resolve. The pattern is marked
+    /// resolved once none of its occurrences are open.
+    pub async fn resolve_error(&self, error_event_id: &str, fix: Fix) -> Result<ErrorResolution, NebulaKgError> {
+        let error_event_id = error_event_id.to_string();
+        self.run_write(move |backend| backend.resolve_error(&error_event_id, &fix))
+            .await
+    }
+
+    /// Captured errors not yet resolved with `resolve_error`, newest first
+    pub async fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.open_errors(limit)).await
+    }
+
+    /// Open and resolved error counts and mean time to fix
+    pub async fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError> {
+        self.run_blocking(|backend| backend.resolution_stats()).await
+    }
+
+    /// Mark the pattern with `signature` resolved, recording a `resolution` event.
+    /// Returns the event id, or `None` if there was no unresolved pattern.
+    pub async fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: serde_json::Value,
+    ) -> Result<Option<String>, NebulaKgError> {
+        let (signature, content) = (signature.to_string(), content.to_string());
+        self.run_blocking(move |backend| backend.resolve_pattern(&signature, &content, &context))
+            .await
     }
 
     /// Get summary statistics
-    pub async fn get_summary(&self) -> Result<PatternSummary, Box<dyn std::error::Error>> {
-        let python_code = format!(
-            r#"
-import sys
-import json
-sys.path.insert(0, '.')
-from local_kg.local_kg import get_local_kg
-
-kg = get_local_kg('{}')
-summary = kg.get_pattern_summary()
-print(json.dumps(summary, default=str))
-"#,
-            self.db_path
-        );
+    pub async fn get_summary(&self) -> Result<PatternSummary, NebulaKgError> {
+        self.run_blocking(|backend| backend.get_summary()).await
+    }
+
+    /// Append an arbitrary event (milestone, decision, ...) to the event stream
+    pub async fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: serde_json::Value,
+    ) -> Result<String, NebulaKgError> {
+        let (event_type, phase, content) = (event_type.to_string(), phase.to_string(), content.to_string());
+        self.run_blocking(move |backend| backend.record_event(&event_type, &phase, &content, &metadata))
+            .await
+    }
+
+    /// Get the most recent events, newest first
+    pub async fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.recent_events(limit)).await
+    }
+
+    /// Record compiler diagnostics (see `parse_cargo_output`). Returns the event ids.
+    pub async fn capture_diagnostics(&self, diagnostics: &[Diagnostic]) -> Result<Vec<String>, NebulaKgError> {
+        let captures: Vec<ErrorCapture> = diagnostics.iter().map(Diagnostic::to_capture).collect();
+        self.run_write(move |backend| captures.iter().map(|c| backend.capture(c)).collect())
+            .await
+    }
+
+    /// Record failed tests (see `parse_libtest_output`). Returns the event ids.
+    pub async fn capture_test_failures(&self, failures: &[TestFailure]) -> Result<Vec<String>, NebulaKgError> {
+        let captures: Vec<ErrorCapture> = failures.iter().map(TestFailure::to_capture).collect();
+        self.run_write(move |backend| captures.iter().map(|c| backend.capture(c)).collect())
+            .await
+    }
+
+    /// Resolve the patterns of previously failing tests that passed in `report`.
+    /// Returns the signatures of the patterns that were resolved.
+    pub async fn resolve_passed_tests(&self, report: &TestReport) -> Result<Vec<String>, NebulaKgError> {
+        let passed: Vec<PassedTest> = report.resolvable().cloned().collect();
+        self.run_blocking(move |backend| {
+            let mut resolved = Vec::new();
+            for test in passed {
+                let signature = test.signature();
+                let context = serde_json::json!({ "test_name": test.name, "crate": test.crate_name });
+                if backend
+                    .resolve_pattern(&signature, &format!("test {} passed", test.name), &context)?
+                    .is_some()
+                {
+                    resolved.push(signature);
+                }
+            }
+            Ok(resolved)
+        })
+        .await
+    }
+
+    /// Push unsynced patterns and solutions to the Central KG (see `CentralSync`).
+    /// Fails with `Config` if no `central_kg_url` is set.
+    pub async fn sync_to_central(&self) -> Result<SyncSummary, NebulaKgError> {
+        let central = self.central.clone().ok_or_else(|| {
+            NebulaKgError::Config("Central KG sync not configured (set \"central_kg_url\")".to_string())
+        })?;
+        self.run_blocking(move |backend| central.sync(backend)).await
+    }
+
+    /// Most recent sync attempts, newest first
+    pub async fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.sync_history(limit)).await
+    }
+
+    /// Items queued for the Central KG, optionally only those with `status`, newest first
+    pub async fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        self.run_blocking(move |backend| backend.sync_outbox(status, limit)).await
+    }
 
-        let result = self.run_python(&python_code).await?;
-        let summary: PatternSummary = serde_json::from_str(&result)?;
-        Ok(summary)
+    /// Start a background sync if `auto_sync` is on (called after writes)
+    fn auto_sync(&self) {
+        if self.config.auto_sync != Some(true) {
+            return;
+        }
+        if let Some(central) = &self.central {
+            central.schedule(self.backend.clone());
+        }
     }
 
     /// Fire-and-forget error capture (spawns task, doesn't wait)
     pub fn capture_error_fire_and_forget(
         &self,
         signature: String,
-        category: String,
+        category: impl Into<Category>,
         language: String,
-        severity: String,
+        severity: Severity,
     ) {
-        let db_path = self.db_path.clone();
-        let python_cmd = self.python_cmd.clone();
-
-        tokio::spawn(async move {
-            let bridge = LocalKGBridge {
-                config: NebulaConfig {
-                    language: Some(language.clone()),
-                    framework: None,
-                    local_kg_db: Some(db_path.clone()),
-                    central_kg_url: None,
-                    python_command: Some(python_cmd.clone()),
-                    auto_sync: None,
-                },
-                db_path,
-                python_cmd,
-            };
-
-            if let Err(e) = bridge.capture_error_async(&signature, &category, &language, &severity).await {
-                tracing::warn!("Failed to capture error to Local KG: {}", e);
-            }
-        });
+        self.capture_fire_and_forget(
+            ErrorCapture::new(signature, category)
+                .with_language(language)
+                .with_severity(severity),
+        );
     }
 
-    /// Escape string for Python code
-    fn escape_python(s: &str) -> String {
-        s.replace('\\', "\\\\")
-            .replace('\'', "\\'")
-            .replace('\n', "\\n")
+    /// Fire-and-forget capture of a full `ErrorCapture`.
+    ///
+    /// Runs on tokio's blocking pool when called inside a runtime, otherwise on
+    /// a short-lived thread, so it is safe to call from any context.
+    pub fn capture_fire_and_forget(&self, capture: ErrorCapture) {
+        spawn_capture(self.backend.clone(), capture);
     }
 
-    /// Run Python code and return stdout
-    async fn run_python(&self, code: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let output = tokio::process::Command::new(&self.python_cmd)
-            .arg("-c")
-            .arg(code)
-            .current_dir(".")
-            .output()
-            .await?;
+    /// `run_blocking` for operations that add something worth syncing
+    async fn run_write<T, F>(&self, op: F) -> Result<T, NebulaKgError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn KgBackend) -> Result<T, NebulaKgError> + Send + 'static,
+    {
+        let result = self.run_blocking(op).await?;
+        self.auto_sync();
+        Ok(result)
+    }
+
+    /// Run a backend operation on the blocking pool
+    async fn run_blocking<T, F>(&self, op: F) -> Result<T, NebulaKgError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn KgBackend) -> Result<T, NebulaKgError> + Send + 'static,
+    {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || op(backend.as_ref()))
+            .await
+            .map_err(|e| NebulaKgError::BackendUnavailable(format!("Local KG task failed: {}", e)))?
+    }
+}
 
-        if output.status.success() {
-            Ok(String::from_utf8(output.stdout)?.trim().to_string())
-        } else {
-            let stderr = String::from_utf8(output.stderr)?;
-            Err(format!("Python process failed: {}", stderr).into())
+/// Write a capture in the background, logging (not returning) failures
+fn spawn_capture(backend: Arc<dyn KgBackend>, capture: ErrorCapture) {
+    let task = move || {
+        if let Err(e) = backend.capture(&capture) {
+            tracing::warn!("Failed to capture error to Local KG: {}", e);
+        }
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(task);
+        }
+        Err(_) => {
+            if let Err(e) = std::thread::Builder::new()
+                .name("local-kg-capture".to_string())
+                .spawn(task)
+            {
+                tracing::warn!("Failed to capture error to Local KG: {}", e);
+            }
         }
     }
 }
 
-/// Global singleton instance
-static mut GLOBAL_INSTANCE: Option<LocalKGBridge> = None;
+/// Module path of the bridge root (e.g. `my_app::local_kg_bridge`). Events and
+/// log records from here are never captured, so a failing backend can't loop.
+fn bridge_module_path() -> &'static str {
+    module_path!()
+}
+
+/// Global singleton instance (`None` until first use or after `reset_local_kg`)
+static GLOBAL_INSTANCE: RwLock<Option<Arc<LocalKGBridge>>> = RwLock::new(None);
+
+/// Get or create global singleton instance.
+///
+/// `db_path` is only used on first initialization; asking for a different path
+/// afterwards returns `NebulaKgError::DbPathMismatch` instead of silently
+/// handing back the existing instance.
+pub fn get_local_kg(db_path: Option<&str>) -> Result<Arc<LocalKGBridge>, NebulaKgError> {
+    if let Some(existing) = GLOBAL_INSTANCE.read()?.as_ref() {
+        return check_db_path(existing, db_path);
+    }
+    init_local_kg(db_path)
+}
+
+/// Explicitly initialize the global instance. Idempotent for the same db path.
+pub fn init_local_kg(db_path: Option<&str>) -> Result<Arc<LocalKGBridge>, NebulaKgError> {
+    let mut global = GLOBAL_INSTANCE.write()?;
+    // Another thread may have initialized it while we waited for the lock
+    if let Some(existing) = global.as_ref() {
+        return check_db_path(existing, db_path);
+    }
+
+    let bridge = Arc::new(LocalKGBridge::new(db_path)?);
+    *global = Some(bridge.clone());
+    Ok(bridge)
+}
 
-/// Get or create global singleton instance
-pub fn get_local_kg(db_path: Option<&str>) -> Result<&'static LocalKGBridge, Box<dyn std::error::Error>> {
-    unsafe {
-        if GLOBAL_INSTANCE.is_none() {
-            GLOBAL_INSTANCE = Some(LocalKGBridge::new(db_path)?);
+/// Install a pre-built bridge (e.g. over `MemoryBackend`) as the global instance.
+/// Fails with `AlreadyInitialized` (same db path) or `DbPathMismatch` if one
+/// is already installed.
+pub fn set_local_kg(bridge: LocalKGBridge) -> Result<Arc<LocalKGBridge>, NebulaKgError> {
+    let mut global = GLOBAL_INSTANCE.write()?;
+    if let Some(existing) = global.as_ref() {
+        if existing.db_path() == bridge.db_path() {
+            return Err(NebulaKgError::AlreadyInitialized {
+                db_path: existing.db_path().to_string(),
+            });
         }
-        Ok(GLOBAL_INSTANCE.as_ref().unwrap())
+        return Err(NebulaKgError::DbPathMismatch {
+            initialized: existing.db_path().to_string(),
+            requested: bridge.db_path().to_string(),
+        });
     }
+
+    let bridge = Arc::new(bridge);
+    *global = Some(bridge.clone());
+    Ok(bridge)
 }
 
+/// Drop the global instance so the next `get_local_kg` re-initializes (tests)
+pub fn reset_local_kg() {
+    let mut global = GLOBAL_INSTANCE.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *global = None;
+}
+
+fn check_db_path(
+    existing: &Arc<LocalKGBridge>,
+    requested: Option<&str>,
+) -> Result<Arc<LocalKGBridge>, NebulaKgError> {
+    match requested {
+        Some(path) if path != existing.db_path() => Err(NebulaKgError::DbPathMismatch {
+            initialized: existing.db_path().to_string(),
+            requested: path.to_string(),
+        }),
+        _ => Ok(existing.clone()),
+    }
+}
@@ -0,0 +1,163 @@
+//! Storage backends for the Local KG bridge.
+//!
+//! `LocalKGBridge` delegates every operation to a `KgBackend`. Backends are
+//! synchronous; the bridge runs them on tokio's blocking pool so a slow
+//! backend (e.g. the Python subprocess) never stalls the async runtime.
+
+use serde_json::Value;
+
+use super::similarity::MAX_SIMILARITY_CANDIDATES;
+use super::solution::parse_effectiveness;
+use super::{
+    Category, ErrorCapture, ErrorPattern, ErrorResolution, Fix, KgEvent, NebulaKgError, OutboxItem,
+    OutboxStatus, OutboxUpdate, PatternSearch, PatternSummary, ResolutionStats, SearchHit,
+    Severity, SimilarPattern, SimilaritySearch, Solution, SolutionFeedback, SyncRecord, SyncType,
+    UnsyncedSolution,
+};
+
+/// Operations every Local KG storage backend must support
+pub trait KgBackend: Send + Sync {
+    /// Record an error event and update its pattern. Returns the event id.
+    fn capture(&self, capture: &ErrorCapture) -> Result<String, NebulaKgError>;
+
+    /// Shorthand for `capture` without description or context
+    fn capture_error(
+        &self,
+        signature: &str,
+        category: Category,
+        language: &str,
+        severity: Severity,
+    ) -> Result<String, NebulaKgError> {
+        self.capture(
+            &ErrorCapture::new(signature, category)
+                .with_language(language)
+                .with_severity(severity),
+        )
+    }
+
+    /// Find patterns matching `query`, most frequent first
+    fn search_patterns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError>;
+
+    /// Full-text search over signatures, descriptions and solutions, best match first
+    fn search(&self, search: &PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError>;
+
+    /// Patterns whose normalized signature resembles `search.signature`, most
+    /// similar first. Scores the most frequent `MAX_SIMILARITY_CANDIDATES` patterns.
+    fn find_similar(
+        &self,
+        search: &SimilaritySearch,
+    ) -> Result<Vec<SimilarPattern>, NebulaKgError> {
+        let candidates = self.search_patterns("", MAX_SIMILARITY_CANDIDATES)?;
+        Ok(search.rank(candidates))
+    }
+
+    /// Record a solution for a pattern (by id or signature) and re-derive the
+    /// pattern's promoted solution (see `Solution`). Returns the event id.
+    fn record_solution(
+        &self,
+        pattern_id: &str,
+        solution: &Solution,
+    ) -> Result<String, NebulaKgError>;
+
+    /// Shorthand for `record_solution` with plain text and a 1-5 rating
+    fn add_solution(
+        &self,
+        pattern_id: &str,
+        solution_text: &str,
+        effectiveness: &str,
+    ) -> Result<String, NebulaKgError> {
+        self.record_solution(
+            pattern_id,
+            &Solution::from_text(solution_text, parse_effectiveness(effectiveness)),
+        )
+    }
+
+    /// Record whether a solution (by its event id) helped and re-rank the
+    /// pattern's solutions. Returns the event id.
+    fn record_feedback(
+        &self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError>;
+
+    /// Link the error event `error_event_id` (as returned by `capture`) to the
+    /// fix that resolved it (see `resolution`)
+    fn resolve_error(
+        &self,
+        error_event_id: &str,
+        fix: &Fix,
+    ) -> Result<ErrorResolution, NebulaKgError>;
+
+    /// Error events not yet linked to a fix, newest first
+    fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError>;
+
+    /// Open and resolved error counts and mean time to fix
+    fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError>;
+
+    /// Mark the pattern with `signature` resolved and record a `resolution` event.
+    /// Returns the event id, or `None` if no unresolved pattern has that signature.
+    /// Capturing the error again reopens the pattern.
+    fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: &Value,
+    ) -> Result<Option<String>, NebulaKgError>;
+
+    /// Aggregate statistics over patterns and solutions
+    fn get_summary(&self) -> Result<PatternSummary, NebulaKgError>;
+
+    /// Append a raw event (milestone, decision, ...) to the event stream. Returns the event id.
+    fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: &Value,
+    ) -> Result<String, NebulaKgError>;
+
+    /// Most recent events, newest first
+    fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError>;
+
+    /// Patterns without a successful `pattern` sync record that aren't in the
+    /// sync outbox yet, most frequent first
+    fn unsynced_patterns(&self, limit: usize) -> Result<Vec<ErrorPattern>, NebulaKgError>;
+
+    /// Solutions without a successful `solution` sync record that aren't in
+    /// the sync outbox yet, oldest first
+    fn unsynced_solutions(&self, limit: usize) -> Result<Vec<UnsyncedSolution>, NebulaKgError>;
+
+    /// Add items to the sync outbox, skipping any whose type and local id are
+    /// already queued. Returns how many were added.
+    fn enqueue_sync(&self, items: &[OutboxItem]) -> Result<usize, NebulaKgError>;
+
+    /// `pending` outbox items of `sync_type` that are due, oldest first
+    fn due_sync_items(
+        &self,
+        sync_type: SyncType,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError>;
+
+    /// Record one attempt at each item (unknown ids are ignored)
+    fn update_sync_items(&self, updates: &[OutboxUpdate]) -> Result<(), NebulaKgError>;
+
+    /// Outbox items, optionally only those with `status`, newest first
+    fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError>;
+
+    /// Append to the sync history (`synced_at` is set to now)
+    fn record_sync(&self, records: &[SyncRecord]) -> Result<(), NebulaKgError>;
+
+    /// Most recent sync history entries, newest first
+    fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError>;
+
+    /// Universal Schema version recorded by the store (`None` if unversioned)
+    fn schema_version(&self) -> Result<Option<String>, NebulaKgError>;
+}
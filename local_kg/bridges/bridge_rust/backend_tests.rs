@@ -0,0 +1,486 @@
+//! Tests run against every backend.
+//!
+//! The parity suite drives `MemoryBackend` and `SqliteBackend` through the
+//! same script and compares what they report, minus generated ids and
+//! timestamps. Search scores are backend-specific (see `SearchHit::score`),
+//! so only the ranking is compared. The hostile-input test round-trips
+//! awkward strings through SQLite and both Python backends; the Python half
+//! needs `python3` and the `local_kg` package in the working directory.
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use super::schema::require_version;
+use super::{
+    Category, ErrorCapture, Fix, KgBackend, LocalKGBridge, MemoryBackend, NebulaConfig,
+    NebulaKgError, OutboxItem, OutboxStatus, OutboxUpdate, PatternSearch, PythonBackend,
+    PythonWorkerBackend, Severity, Solution, SolutionFeedback, SqliteBackend, SyncType,
+    SCHEMA_VERSION,
+};
+
+/// Strings that break naive quoting in SQL, JSON or Python source
+const HOSTILE: &[&str] = &[
+    "quote ' and \"double\" and `back`",
+    "carriage\rreturn and\nnewline",
+    "nul\0byte",
+    "line\u{2028}separator\u{2029}paragraph",
+    "'''triple''' \"\"\"quotes\"\"\"",
+    "'; import os; os.system('echo pwned'); '",
+    "\\ backslash \\x00 \\u0000 {braces} %s %(name)s",
+    "ünïcødé 🦀 \u{feff}",
+];
+
+/// A database file under the temp dir, removed on drop
+pub(super) struct TempDb(PathBuf);
+
+impl TempDb {
+    pub(super) fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("nebula-kg-{}-{}.db", name, uuid::Uuid::new_v4())))
+    }
+
+    pub(super) fn path(&self) -> &str {
+        self.0.to_str().expect("temp dir path is UTF-8")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm", "-journal", ".vectors"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path(), suffix));
+        }
+    }
+}
+
+fn capture(kg: &dyn KgBackend, signature: &str, category: Category, description: &str) -> String {
+    kg.capture(
+        &ErrorCapture::new(signature, category)
+            .with_language("rust")
+            .with_severity(Severity::High)
+            .with_description(description),
+    )
+    .unwrap()
+}
+
+fn signatures(kg: &dyn KgBackend, query: &str) -> Vec<String> {
+    let mut found: Vec<String> = kg
+        .search_patterns(query, 50)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.signature)
+        .collect();
+    // Both backends order by occurrence count only; ties come back in any order
+    found.sort();
+    found
+}
+
+/// What `kg` reports after each step of the script, without ids or timestamps
+fn run_script(kg: &dyn KgBackend) -> Vec<(&'static str, Value)> {
+    let mut transcript = vec![("schema_version", json!(kg.schema_version().unwrap()))];
+
+    let moved = capture(
+        kg,
+        "E0382: borrow of moved value",
+        Category::BorrowCheck,
+        "value moved into closure",
+    );
+    capture(
+        kg,
+        "E0382: borrow of moved value",
+        Category::BorrowCheck,
+        "value moved into closure",
+    );
+    capture(
+        kg,
+        "E0308: mismatched types",
+        Category::CompileError,
+        "expected u32, found String",
+    );
+    capture(
+        kg,
+        "E0_08 literal",
+        Category::Custom("lint".into()),
+        "underscore",
+    );
+    capture(kg, "disk 100% full", Category::Runtime, "no space left");
+
+    for query in ["E0382", "E0_08", "100%", "_", "borrow", ""] {
+        transcript.push(("search_patterns", json!([query, signatures(kg, query)])));
+    }
+    let pattern = &kg.search_patterns("E0382", 1).unwrap()[0];
+    transcript.push((
+        "pattern",
+        json!([
+            pattern.category,
+            pattern.description,
+            pattern.occurrence_count
+        ]),
+    ));
+
+    let solution = Solution::new("Clone before moving", "call .clone() first")
+        .with_step("clone the value")
+        .with_effectiveness(4);
+    kg.record_solution("E0382: borrow of moved value", &solution)
+        .unwrap();
+    kg.add_solution("E0382: borrow of moved value", "use a reference", "2")
+        .unwrap();
+    let pattern = &kg.search_patterns("E0382", 1).unwrap()[0];
+    let solution_id = pattern.solutions[0].id.clone();
+    kg.record_feedback(&solution_id, &SolutionFeedback::helpful())
+        .unwrap();
+    let pattern = &kg.search_patterns("E0382", 1).unwrap()[0];
+    transcript.push((
+        "solutions",
+        json!([
+            pattern.solution,
+            pattern
+                .solutions
+                .iter()
+                .map(|s| json!([
+                    s.title,
+                    s.description,
+                    s.resolution_steps,
+                    s.effectiveness,
+                    s.helpful_count,
+                    s.unhelpful_count
+                ]))
+                .collect::<Vec<_>>()
+        ]),
+    ));
+
+    for search in [
+        PatternSearch::new("borrow moved"),
+        PatternSearch::new("mism").with_prefix(true),
+        PatternSearch::new("clone mismatched").with_match_any(true),
+        PatternSearch::new("moved").with_category(Category::CompileError),
+        PatternSearch::new(""),
+    ] {
+        let hits = kg.search(&search).unwrap();
+        transcript.push((
+            "search",
+            json!([
+                search.text,
+                hits.iter()
+                    .map(|hit| json!([hit.pattern.signature, hit.score > 0.0]))
+                    .collect::<Vec<_>>()
+            ]),
+        ));
+    }
+
+    let resolution = kg
+        .resolve_error(&moved, &Fix::existing(&solution_id))
+        .unwrap();
+    transcript.push((
+        "resolve_error",
+        json!([
+            resolution.error_event_id == moved,
+            resolution.signature,
+            resolution.solution_id == solution_id
+        ]),
+    ));
+    let stats = kg.resolution_stats().unwrap();
+    transcript.push((
+        "resolution_stats",
+        json!([stats.open_errors, stats.resolved_errors]),
+    ));
+    let open = kg.open_errors(10).unwrap();
+    transcript.push(("open_errors", json!(open.len())));
+
+    let resolved = kg
+        .resolve_pattern("E0308: mismatched types", "fixed", &json!({}))
+        .unwrap();
+    let again = kg
+        .resolve_pattern("E0308: mismatched types", "fixed", &json!({}))
+        .unwrap();
+    transcript.push((
+        "resolve_pattern",
+        json!([resolved.is_some(), again.is_some()]),
+    ));
+    let hits = kg
+        .search(&PatternSearch::new("mismatched").with_include_resolved(false))
+        .unwrap();
+    transcript.push(("search unresolved", json!(hits.len())));
+
+    let summary = kg.get_summary().unwrap();
+    transcript.push((
+        "summary",
+        json!([
+            summary.total_patterns,
+            summary.total_errors,
+            summary.total_solutions,
+            summary.patterns_with_solution,
+            summary.top_patterns.first().map(|p| p.signature.clone())
+        ]),
+    ));
+
+    kg.record_event("milestone", "build", "parity", &json!({"step": 1}))
+        .unwrap();
+    let events = kg.recent_events(1).unwrap();
+    transcript.push((
+        "recent_events",
+        json!([
+            events[0].event_type,
+            events[0].phase,
+            events[0].content,
+            events[0].metadata
+        ]),
+    ));
+
+    let unsynced = kg.unsynced_patterns(50).unwrap();
+    transcript.push(("unsynced_patterns", json!(unsynced.len())));
+    let item = OutboxItem::new(SyncType::Pattern, &unsynced[0].id, json!({"n": 1}));
+    let added = kg.enqueue_sync(&[item.clone(), item.clone()]).unwrap();
+    let due = kg.due_sync_items(SyncType::Pattern, 10).unwrap();
+    transcript.push(("enqueue_sync", json!([added, due.len()])));
+    kg.update_sync_items(&[OutboxUpdate {
+        id: due[0].id.clone(),
+        status: OutboxStatus::Synced,
+        central_id: Some("central-1".into()),
+        error: None,
+        retry_in_secs: 0,
+    }])
+    .unwrap();
+    let synced = kg.sync_outbox(Some(OutboxStatus::Synced), 10).unwrap();
+    transcript.push((
+        "sync_outbox",
+        json!([
+            synced.len(),
+            synced[0].central_id,
+            synced[0].attempts,
+            kg.unsynced_patterns(50).unwrap().len()
+        ]),
+    ));
+
+    transcript
+}
+
+#[test]
+fn memory_and_sqlite_backends_agree() {
+    let db = TempDb::new("parity");
+    let sqlite = SqliteBackend::open(db.path()).unwrap();
+    let memory = MemoryBackend::new();
+
+    let expected = run_script(&memory);
+    let actual = run_script(&sqlite);
+    for ((step, memory), (_, sqlite)) in expected.iter().zip(&actual) {
+        assert_eq!(memory, sqlite, "backends disagree at {}", step);
+    }
+    assert_eq!(expected.len(), actual.len());
+}
+
+fn round_trips_hostile_strings(kg: &dyn KgBackend, backend: &str) {
+    for (i, hostile) in HOSTILE.iter().enumerate() {
+        let signature = format!("{} #{}", hostile, i);
+        capture(kg, &signature, Category::Custom((*hostile).into()), hostile);
+
+        let stored = kg
+            .search_patterns("", 100)
+            .unwrap()
+            .into_iter()
+            .find(|p| p.signature.as_bytes() == signature.as_bytes())
+            .unwrap_or_else(|| panic!("{}: {:?} not stored verbatim", backend, signature));
+        assert_eq!(
+            stored.description.as_deref().map(str::as_bytes),
+            Some(hostile.as_bytes()),
+            "{}: description of {:?}",
+            backend,
+            hostile
+        );
+        assert_eq!(stored.category, Some(Category::Custom((*hostile).into())));
+
+        kg.record_solution(&stored.id, &Solution::new(*hostile, *hostile))
+            .unwrap();
+        let pattern = kg
+            .search_patterns("", 100)
+            .unwrap()
+            .into_iter()
+            .find(|p| p.id == stored.id)
+            .unwrap();
+        let solution = &pattern.solutions[0];
+        assert_eq!(solution.title.as_bytes(), hostile.as_bytes(), "{}", backend);
+        assert_eq!(
+            solution.description.as_bytes(),
+            hostile.as_bytes(),
+            "{}",
+            backend
+        );
+
+        kg.record_event("milestone", hostile, hostile, &json!({ "note": hostile }))
+            .unwrap();
+        let event = &kg.recent_events(1).unwrap()[0];
+        assert_eq!(event.content.as_deref(), Some(*hostile), "{}", backend);
+        assert_eq!(event.phase.as_deref(), Some(*hostile), "{}", backend);
+        assert_eq!(event.metadata["note"], json!(hostile), "{}", backend);
+    }
+    // Nothing was executed and nothing else was written
+    assert_eq!(
+        kg.get_summary().unwrap().total_patterns,
+        HOSTILE.len() as i64
+    );
+}
+
+/// Whether the Python Local KG can be started from the working directory
+fn python_available() -> bool {
+    let found = std::path::Path::new("local_kg/kg_worker.py").exists()
+        && std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success());
+    if !found {
+        eprintln!("skipping Python backends: python3 or local_kg/ not available");
+    }
+    found
+}
+
+#[test]
+fn hostile_strings_round_trip_verbatim() {
+    let db = TempDb::new("hostile-sqlite");
+    round_trips_hostile_strings(&SqliteBackend::open(db.path()).unwrap(), "sqlite");
+
+    if !python_available() {
+        return;
+    }
+    // The Python Local KG expects a database the native backend has migrated
+    let db = TempDb::new("hostile-python");
+    SqliteBackend::open(db.path()).unwrap();
+    round_trips_hostile_strings(&PythonBackend::new(db.path(), "python3"), "python");
+
+    let db = TempDb::new("hostile-worker");
+    SqliteBackend::open(db.path()).unwrap();
+    round_trips_hostile_strings(
+        &PythonWorkerBackend::new(db.path(), "python3"),
+        "python worker",
+    );
+}
+
+#[test]
+fn fresh_stores_report_the_current_schema_version() {
+    let db = TempDb::new("version");
+    let sqlite = SqliteBackend::open(db.path()).unwrap();
+    assert_eq!(
+        sqlite.schema_version().unwrap().as_deref(),
+        Some(SCHEMA_VERSION)
+    );
+    assert!(require_version(None).is_err());
+
+    if python_available() {
+        let python = PythonBackend::new(db.path(), "python3");
+        assert_eq!(
+            python.schema_version().unwrap().as_deref(),
+            Some(SCHEMA_VERSION)
+        );
+    }
+}
+
+#[test]
+fn python_backends_open_a_fresh_database() {
+    if !python_available() {
+        return;
+    }
+    for backend in ["python", "python-subprocess"] {
+        let db = TempDb::new("fresh-python");
+        let config: NebulaConfig =
+            serde_json::from_value(json!({ "backend": backend, "python_command": "python3" }))
+                .unwrap();
+        let kg = LocalKGBridge::open_backend(&config, db.path()).unwrap();
+        assert_eq!(
+            kg.schema_version().unwrap().as_deref(),
+            Some(SCHEMA_VERSION),
+            "{}",
+            backend
+        );
+        capture(
+            &*kg,
+            "E0308: mismatched types",
+            Category::CompileError,
+            "fresh",
+        );
+        assert_eq!(signatures(&*kg, ""), vec!["E0308: mismatched types"]);
+    }
+}
+
+#[test]
+fn python_search_ranks_like_sqlite() {
+    if !python_available() {
+        return;
+    }
+    let db = TempDb::new("search");
+    let sqlite = SqliteBackend::open(db.path()).unwrap();
+    run_script(&sqlite);
+    let python = PythonWorkerBackend::new(db.path(), "python3");
+
+    for search in [
+        PatternSearch::new("borrow moved"),
+        PatternSearch::new("clone mismatched").with_match_any(true),
+        PatternSearch::new("e0")
+            .with_prefix(true)
+            .with_highlight("<", ">"),
+    ] {
+        let summarize = |kg: &dyn KgBackend| -> Vec<Value> {
+            kg.search(&search)
+                .unwrap()
+                .into_iter()
+                // The two SQLite builds may differ in the last bits of a score
+                .map(|hit| {
+                    json!([
+                        hit.pattern.signature,
+                        (hit.score * 1e6).round(),
+                        hit.snippet
+                    ])
+                })
+                .collect()
+        };
+        assert_eq!(summarize(&sqlite), summarize(&python), "{}", search.text);
+    }
+}
+
+fn rejects_solution_of_another_pattern(kg: &dyn KgBackend, backend: &str) {
+    let error = capture(
+        kg,
+        "E0382: borrow of moved value",
+        Category::BorrowCheck,
+        "moved",
+    );
+    capture(
+        kg,
+        "E0308: mismatched types",
+        Category::CompileError,
+        "types",
+    );
+    let other = kg
+        .add_solution("E0308: mismatched types", "convert the value", "4")
+        .unwrap();
+
+    let result = kg.resolve_error(&error, &Fix::existing(&other));
+    assert!(
+        matches!(result, Err(NebulaKgError::InvalidValue(_))),
+        "{}: {:?}",
+        backend,
+        result
+    );
+    // Nothing was written: no feedback, the error is still open
+    let pattern = &kg.search_patterns("E0308", 1).unwrap()[0];
+    assert_eq!(pattern.solutions[0].helpful_count, 0, "{}", backend);
+    assert_eq!(
+        kg.resolution_stats().unwrap().resolved_errors,
+        0,
+        "{}",
+        backend
+    );
+}
+
+#[test]
+fn resolve_error_rejects_a_solution_of_another_pattern() {
+    rejects_solution_of_another_pattern(&MemoryBackend::new(), "memory");
+    let db = TempDb::new("mismatch-sqlite");
+    rejects_solution_of_another_pattern(&SqliteBackend::open(db.path()).unwrap(), "sqlite");
+
+    if python_available() {
+        let db = TempDb::new("mismatch-python");
+        SqliteBackend::open(db.path()).unwrap();
+        rejects_solution_of_another_pattern(
+            &PythonWorkerBackend::new(db.path(), "python3"),
+            "python worker",
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! `ErrorCapture`: one error occurrence, built up field by field.
+//!
+//! Carries everything the protocol's `logError` records (level, phase,
+//! constellation, file path, line number, error code, stack trace, context)
+//! in addition to the pattern fields. Backends store the extra fields in the
+//! event's `metadata` under the same keys as `project-memory.js`.
+//!
+//! ```ignore
+//! let capture = ErrorCapture::new("E0308: mismatched types", Category::CompileError)
+//!     .with_severity(Severity::High)
+//!     .with_phase("BUILD")
+//!     .with_location("src/main.rs", 42)
+//!     .with_error_code("E0308")
+//!     .with_technologies(["tokio", "serde"]);
+//! kg.capture(capture).await?;
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use super::{Category, Severity};
+
+/// One error occurrence to record in the Local KG
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorCapture {
+    pub signature: String,
+    pub category: Category,
+    pub language: String,
+    pub severity: Severity,
+    /// Human-readable message (stored as the event content)
+    pub description: Option<String>,
+    /// Extra structured detail stored in the event's `metadata.context`
+    pub context: Option<Value>,
+    /// Log level as reported by the source (`ERROR`, `WARNING`, ...)
+    pub level: Option<String>,
+    /// Protocol phase / constellation the error happened in
+    pub phase: Option<String>,
+    pub constellation: Option<String>,
+    pub file_path: Option<String>,
+    pub line_number: Option<u32>,
+    /// Tool-specific code such as `E0308` or `clippy::needless_return`
+    pub error_code: Option<String>,
+    pub stack_trace: Option<String>,
+    /// Crates / tools involved (e.g. `tokio`, `sqlx`)
+    pub technologies: Vec<String>,
+}
+
+impl ErrorCapture {
+    /// A Rust error with `medium` severity and no further detail
+    pub fn new(signature: impl Into<String>, category: impl Into<Category>) -> Self {
+        Self {
+            signature: signature.into(),
+            category: category.into(),
+            language: "rust".to_string(),
+            severity: Severity::Medium,
+            description: None,
+            context: None,
+            level: None,
+            phase: None,
+            constellation: None,
+            file_path: None,
+            line_number: None,
+            error_code: None,
+            stack_trace: None,
+            technologies: Vec::new(),
+        }
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_context(mut self, context: Value) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn with_level(mut self, level: impl Into<String>) -> Self {
+        self.level = Some(level.into());
+        self
+    }
+
+    pub fn with_phase(mut self, phase: impl Into<String>) -> Self {
+        self.phase = Some(phase.into());
+        self
+    }
+
+    pub fn with_constellation(mut self, constellation: impl Into<String>) -> Self {
+        self.constellation = Some(constellation.into());
+        self
+    }
+
+    pub fn with_file_path(mut self, file_path: impl Into<String>) -> Self {
+        self.file_path = Some(file_path.into());
+        self
+    }
+
+    pub fn with_line_number(mut self, line_number: u32) -> Self {
+        self.line_number = Some(line_number);
+        self
+    }
+
+    /// Shorthand for `with_file_path` + `with_line_number`
+    pub fn with_location(self, file_path: impl Into<String>, line_number: u32) -> Self {
+        self.with_file_path(file_path).with_line_number(line_number)
+    }
+
+    pub fn with_error_code(mut self, error_code: impl Into<String>) -> Self {
+        self.error_code = Some(error_code.into());
+        self
+    }
+
+    pub fn with_stack_trace(mut self, stack_trace: impl Into<String>) -> Self {
+        self.stack_trace = Some(stack_trace.into());
+        self
+    }
+
+    pub fn with_technology(mut self, technology: impl Into<String>) -> Self {
+        self.technologies.push(technology.into());
+        self
+    }
+
+    pub fn with_technologies<I, T>(mut self, technologies: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.technologies
+            .extend(technologies.into_iter().map(Into::into));
+        self
+    }
+
+    /// Value of the event's `phase` column: the constellation, else the phase
+    /// (as `logError` does), else `UNKNOWN`
+    pub(super) fn event_phase(&self) -> &str {
+        self.constellation
+            .as_deref()
+            .or(self.phase.as_deref())
+            .unwrap_or("UNKNOWN")
+    }
+
+    /// Value of the event's `content` column
+    pub(super) fn event_content(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("Error: {}", self.category))
+    }
+
+    /// The event's `metadata` JSON. Optional fields are only written when set.
+    pub(super) fn event_metadata(&self) -> Value {
+        let mut meta = Map::new();
+        meta.insert("severity".to_string(), json!(self.severity));
+        meta.insert("category".to_string(), json!(self.category));
+        meta.insert("language".to_string(), json!(self.language));
+        meta.insert("context".to_string(), json!(self.context));
+        meta.insert("signature".to_string(), json!(self.signature));
+
+        let optional = [
+            ("level", self.level.as_ref().map(|v| json!(v))),
+            ("phase", self.phase.as_ref().map(|v| json!(v))),
+            (
+                "constellation",
+                self.constellation.as_ref().map(|v| json!(v)),
+            ),
+            ("file_path", self.file_path.as_ref().map(|v| json!(v))),
+            ("line_number", self.line_number.map(|v| json!(v))),
+            ("error_code", self.error_code.as_ref().map(|v| json!(v))),
+            ("stack_trace", self.stack_trace.as_ref().map(|v| json!(v))),
+            (
+                "technologies",
+                Some(json!(self.technologies)).filter(|_| !self.technologies.is_empty()),
+            ),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                meta.insert(key.to_string(), value);
+            }
+        }
+        Value::Object(meta)
+    }
+}
@@ -0,0 +1,460 @@
+//! Structured captures from `cargo --message-format=json`.
+//!
+//! Cargo prints one JSON object per line. `compiler-message` lines carry a
+//! rustc diagnostic with its error code, level, spans, rendered text and child
+//! notes; everything else (artifacts, build-script output, plain text) is
+//! ignored. `DiagnosticCollector` keeps the unique diagnostics of one build,
+//! since cargo reports the same diagnostic once per target that compiles a file.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::signature::{normalize_signature, NormalizedSignature};
+use super::{Category, ErrorCapture, Severity};
+
+/// Error codes reported by the borrow checker
+const BORROW_CHECK_CODES: &[&str] = &[
+    "E0382", "E0384", "E0499", "E0502", "E0503", "E0505", "E0506", "E0507", "E0508", "E0509",
+    "E0594", "E0596", "E0597", "E0713", "E0716",
+];
+
+/// Error codes about lifetimes
+const LIFETIME_CODES: &[&str] = &[
+    "E0106", "E0261", "E0262", "E0263", "E0478", "E0491", "E0495", "E0621", "E0623", "E0700",
+];
+
+/// level, code, message, file, line, column
+type DedupKey = (
+    String,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<u32>,
+    Option<u32>,
+);
+
+/// One compiler diagnostic (error, warning or lint)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// `error`, `warning`, ... as reported by rustc
+    pub level: String,
+    pub message: String,
+    /// `E0308`, `unused_variables`, `clippy::needless_return`, ...
+    pub code: Option<String>,
+    /// Location of the primary span (`None` for errors without one, such as
+    /// linker failures or "aborting due to ...")
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Label attached to the primary span (e.g. "expected `u32`, found `String`")
+    pub label: Option<String>,
+    /// The message as rustc would print it, with any ANSI colors cargo was
+    /// asked for (`to_capture` strips them)
+    pub rendered: Option<String>,
+    /// Attached `note:` / `help:` sub-diagnostics
+    pub children: Vec<DiagnosticNote>,
+    /// Cargo package and target that produced the diagnostic
+    pub package_id: Option<String>,
+    pub target: Option<String>,
+}
+
+/// A `note:` / `help:` attached to a diagnostic
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticNote {
+    pub level: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Suggested replacement text, if rustc offered one
+    pub suggestion: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    package_id: Option<String>,
+    target: Option<CargoTarget>,
+    message: Option<RustcDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u32,
+    column_start: u32,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+}
+
+impl Diagnostic {
+    /// Parse one line of cargo JSON output. Returns `None` for non-JSON
+    /// lines and messages other than `compiler-message`.
+    pub fn from_cargo_line(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return None;
+        }
+        let message: CargoMessage = serde_json::from_str(line).ok()?;
+        if message.reason != "compiler-message" {
+            return None;
+        }
+        let diagnostic = message.message?;
+        let primary = diagnostic.spans.iter().find(|s| s.is_primary);
+
+        Some(Self {
+            level: diagnostic.level.clone(),
+            message: diagnostic.message.clone(),
+            code: diagnostic.code.as_ref().map(|c| c.code.clone()),
+            file: primary.map(|s| s.file_name.clone()),
+            line: primary.map(|s| s.line_start),
+            column: primary.map(|s| s.column_start),
+            label: primary.and_then(|s| s.label.clone()),
+            rendered: diagnostic.rendered.clone(),
+            children: diagnostic
+                .children
+                .iter()
+                .map(|child| {
+                    let span = child
+                        .spans
+                        .iter()
+                        .find(|s| s.is_primary)
+                        .or_else(|| child.spans.first());
+                    DiagnosticNote {
+                        level: child.level.clone(),
+                        message: child.message.clone(),
+                        file: span.map(|s| s.file_name.clone()),
+                        line: span.map(|s| s.line_start),
+                        suggestion: span.and_then(|s| s.suggested_replacement.clone()),
+                    }
+                })
+                .collect(),
+            package_id: message.package_id,
+            target: message.target.map(|t| t.name),
+        })
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == "error" || self.level.starts_with("error:")
+    }
+
+    /// Canonical signature shared by every occurrence of this kind of
+    /// diagnostic, e.g. ``E0382: borrow of moved value: `_` ``
+    pub fn signature(&self) -> String {
+        self.normalized().canonical
+    }
+
+    fn normalized(&self) -> NormalizedSignature {
+        match &self.code {
+            Some(code) => normalize_signature(&format!("{}: {}", code, self.message)),
+            None => normalize_signature(&self.message),
+        }
+    }
+
+    /// Local KG category for this diagnostic's code
+    pub fn category(&self) -> Category {
+        match self.code.as_deref() {
+            Some(code) if code.starts_with("clippy::") => Category::Clippy,
+            Some(code) if BORROW_CHECK_CODES.contains(&code) => Category::BorrowCheck,
+            Some(code) if LIFETIME_CODES.contains(&code) => Category::Lifetime,
+            _ => Category::CompileError,
+        }
+    }
+
+    /// Build the capture recorded for this diagnostic
+    pub fn to_capture(&self) -> ErrorCapture {
+        let severity = match (self.is_error(), self.category()) {
+            (true, _) => Severity::High,
+            (false, Category::Clippy) => Severity::Low,
+            (false, _) => Severity::Medium,
+        };
+        let children: Vec<Value> = self
+            .children
+            .iter()
+            .map(|child| {
+                json!({
+                    "level": child.level,
+                    "message": child.message,
+                    "file": child.file,
+                    "line": child.line,
+                    "suggestion": child.suggestion,
+                })
+            })
+            .collect();
+
+        let normalized = self.normalized();
+
+        ErrorCapture {
+            level: Some(self.level.clone()),
+            file_path: self.file.clone(),
+            line_number: self.line,
+            error_code: self.code.clone(),
+            ..ErrorCapture::new(normalized.canonical, self.category())
+        }
+        .with_severity(severity)
+        .with_description(match &self.rendered {
+            Some(rendered) => strip_ansi(rendered),
+            None => self.message.clone(),
+        })
+        .with_context(json!({
+            "source": "cargo",
+            "fingerprint": normalized.fingerprint,
+            "column": self.column,
+            "label": self.label,
+            "children": children,
+            "package_id": self.package_id,
+            "target": self.target,
+        }))
+    }
+
+    /// Identity used to drop repeats within one build
+    fn dedup_key(&self) -> DedupKey {
+        (
+            self.level.clone(),
+            self.code.clone(),
+            self.message.clone(),
+            self.file.clone(),
+            self.line,
+            self.column,
+        )
+    }
+}
+
+/// Collects the unique diagnostics of a single build
+#[derive(Default)]
+pub struct DiagnosticCollector {
+    seen: HashSet<DedupKey>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one line of cargo output. Returns the diagnostic if it is new for this build.
+    pub fn push_line(&mut self, line: &str) -> Option<&Diagnostic> {
+        let diagnostic = Diagnostic::from_cargo_line(line)?;
+        if !self.seen.insert(diagnostic.dedup_key()) {
+            return None;
+        }
+        self.diagnostics.push(diagnostic);
+        self.diagnostics.last()
+    }
+
+    /// Unique diagnostics seen so far, in output order
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+/// `text` without ANSI escape sequences (colors and the like), so they don't
+/// end up in stored descriptions and the search index
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters and intermediates up to a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC (e.g. hyperlinks): up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // nF (e.g. ESC ( B): intermediates up to a final byte in 0..~
+            Some(c) if (' '..='/').contains(&c) => {
+                for c in chars.by_ref() {
+                    if ('0'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Two-character sequences
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// Parse a complete cargo JSON output into its unique diagnostics
+pub fn parse_cargo_output(output: &str) -> Vec<Diagnostic> {
+    let mut collector = DiagnosticCollector::new();
+    for line in output.lines() {
+        collector.push_line(line);
+    }
+    collector.into_diagnostics()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cargo clippy --all-targets --message-format=json-diagnostic-rendered-ansi`
+    /// on a crate whose lib has two errors, one of them reported again for
+    /// the test target
+    const CARGO_JSON: &str = r#"   Compiling demo v0.1.0 (/work/demo)
+{"reason":"compiler-artifact","package_id":"path+file:///work/demo#0.1.0","target":{"kind":["lib"],"crate_types":["lib"],"name":"dep","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"0"},"features":[],"filenames":["/work/demo/target/debug/libdep.rlib"],"executable":null,"fresh":true}
+{"reason":"compiler-message","package_id":"path+file:///work/demo#0.1.0","manifest_path":"/work/demo/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"demo","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"message":{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":"Expected type did not match the received type.\n"},"level":"error","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":21,"column_end":25,"is_primary":true,"text":[{"text":"    let s: String = \"hi\";","highlight_start":21,"highlight_end":25}],"label":"expected `String`, found `&str`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null},{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":12,"column_end":18,"is_primary":false,"text":[{"text":"    let s: String = \"hi\";","highlight_start":12,"highlight_end":18}],"label":"expected due to this","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"try using a conversion method","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":25,"column_end":25,"is_primary":true,"text":[{"text":"    let s: String = \"hi\";","highlight_start":25,"highlight_end":25}],"label":null,"suggested_replacement":".to_string()","suggestion_applicability":"MaybeIncorrect","expansion":null}],"children":[],"rendered":null}],"rendered":"\u001b[0m\u001b[1m\u001b[38;5;9merror[E0308]\u001b[0m\u001b[0m\u001b[1m: mismatched types\u001b[0m\n\u001b[0m \u001b[0m\u001b[0m\u001b[1m\u001b[38;5;12m--> \u001b[0m\u001b[0msrc/lib.rs:3:21\u001b[0m\n"}}
+{"reason":"compiler-message","package_id":"path+file:///work/demo#0.1.0","manifest_path":"/work/demo/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"demo","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"message":{"$message_type":"diagnostic","message":"borrow of moved value: `v`","code":{"code":"E0382","explanation":"A variable was used after its contents have been moved elsewhere.\n"},"level":"error","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":9,"line_end":9,"column_start":20,"column_end":21,"is_primary":true,"text":[{"text":"    println!(\"{:?}\", v);","highlight_start":20,"highlight_end":21}],"label":"value borrowed here after move","suggested_replacement":null,"suggestion_applicability":null,"expansion":null},{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":8,"line_end":8,"column_start":10,"column_end":11,"is_primary":false,"text":[{"text":"    drop(v);","highlight_start":10,"highlight_end":11}],"label":"value moved here","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"move occurs because `v` has type `Vec<i32>`, which does not implement the `Copy` trait","code":null,"level":"note","spans":[],"children":[],"rendered":null}],"rendered":"error[E0382]: borrow of moved value: `v`\n --> src/lib.rs:9:20\n"}}
+{"reason":"compiler-message","package_id":"path+file:///work/demo#0.1.0","manifest_path":"/work/demo/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"demo","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"message":{"$message_type":"diagnostic","message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":14,"line_end":14,"column_start":5,"column_end":14,"is_primary":true,"text":[{"text":"    return x;","highlight_start":5,"highlight_end":14}],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#needless_return","code":null,"level":"help","spans":[],"children":[],"rendered":null},{"message":"remove `return`","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":14,"line_end":14,"column_start":5,"column_end":14,"is_primary":true,"text":[{"text":"    return x;","highlight_start":5,"highlight_end":14}],"label":null,"suggested_replacement":"x","suggestion_applicability":"MaybeIncorrect","expansion":null}],"children":[],"rendered":null}],"rendered":"warning: unneeded `return` statement\n  --> src/lib.rs:14:5\n"}}
+{"reason":"compiler-message","package_id":"path+file:///work/demo#0.1.0","manifest_path":"/work/demo/Cargo.toml","target":{"kind":["test"],"crate_types":["test"],"name":"demo","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":"Expected type did not match the received type.\n"},"level":"error","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":21,"column_end":25,"is_primary":true,"text":[{"text":"    let s: String = \"hi\";","highlight_start":21,"highlight_end":25}],"label":"expected `String`, found `&str`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null},{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":12,"column_end":18,"is_primary":false,"text":[{"text":"    let s: String = \"hi\";","highlight_start":12,"highlight_end":18}],"label":"expected due to this","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"try using a conversion method","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","byte_start":100,"byte_end":105,"line_start":3,"line_end":3,"column_start":25,"column_end":25,"is_primary":true,"text":[{"text":"    let s: String = \"hi\";","highlight_start":25,"highlight_end":25}],"label":null,"suggested_replacement":".to_string()","suggestion_applicability":"MaybeIncorrect","expansion":null}],"children":[],"rendered":null}],"rendered":"\u001b[0m\u001b[1m\u001b[38;5;9merror[E0308]\u001b[0m\u001b[0m\u001b[1m: mismatched types\u001b[0m\n\u001b[0m \u001b[0m\u001b[0m\u001b[1m\u001b[38;5;12m--> \u001b[0m\u001b[0msrc/lib.rs:3:21\u001b[0m\n"}}
+{"reason":"compiler-message","package_id":"path+file:///work/demo#0.1.0","manifest_path":"/work/demo/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"demo","src_path":"/work/demo/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"message":{"$message_type":"diagnostic","message":"aborting due to 2 previous errors","code":null,"level":"error","spans":[],"children":[],"rendered":"\u001b[0m\u001b[1m\u001b[38;5;9merror\u001b[0m\u001b[0m\u001b[1m: aborting due to 2 previous errors\u001b[0m\n\n"}}
+{"reason": "build-finished", "success": false}
+"#;
+
+    #[test]
+    fn parses_compiler_messages() {
+        let diagnostics = parse_cargo_output(CARGO_JSON);
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code.as_deref()).collect();
+        // The repeated E0308 is kept once, artifacts and plain text are skipped
+        assert_eq!(
+            codes,
+            vec![
+                Some("E0308"),
+                Some("E0382"),
+                Some("clippy::needless_return"),
+                None
+            ]
+        );
+
+        let mismatch = &diagnostics[0];
+        assert_eq!(mismatch.level, "error");
+        assert_eq!(mismatch.message, "mismatched types");
+        assert_eq!(
+            (mismatch.file.as_deref(), mismatch.line, mismatch.column),
+            (Some("src/lib.rs"), Some(3), Some(21))
+        );
+        assert_eq!(
+            mismatch.label.as_deref(),
+            Some("expected `String`, found `&str`")
+        );
+        assert_eq!(
+            mismatch.children,
+            vec![DiagnosticNote {
+                level: "help".to_string(),
+                message: "try using a conversion method".to_string(),
+                file: Some("src/lib.rs".to_string()),
+                line: Some(3),
+                suggestion: Some(".to_string()".to_string()),
+            }]
+        );
+        assert_eq!(mismatch.target.as_deref(), Some("demo"));
+        assert_eq!(mismatch.category(), Category::CompileError);
+
+        let moved = &diagnostics[1];
+        assert_eq!(moved.signature(), "E0382: borrow of moved value: `_`");
+        assert_eq!(moved.category(), Category::BorrowCheck);
+        assert_eq!(moved.children[0].level, "note");
+        assert_eq!(moved.children[0].file, None);
+
+        let lint = &diagnostics[2];
+        assert!(!lint.is_error());
+        assert_eq!(lint.category(), Category::Clippy);
+        assert_eq!(lint.children[1].suggestion.as_deref(), Some("x"));
+        assert_eq!(lint.to_capture().severity, Severity::Low);
+    }
+
+    #[test]
+    fn keeps_errors_without_a_primary_span() {
+        let diagnostics = parse_cargo_output(CARGO_JSON);
+        let abort = diagnostics.last().unwrap();
+        assert!(abort.is_error());
+        assert_eq!(abort.message, "aborting due to 2 previous errors");
+        assert_eq!(
+            (abort.file.as_deref(), abort.line, abort.column),
+            (None, None, None)
+        );
+        assert_eq!(abort.signature(), "aborting due to N previous errors");
+
+        let capture = abort.to_capture();
+        assert_eq!(capture.file_path, None);
+        assert_eq!(capture.severity, Severity::High);
+    }
+
+    #[test]
+    fn captures_plain_rendered_text() {
+        let diagnostics = parse_cargo_output(CARGO_JSON);
+        let mismatch = &diagnostics[0];
+        // Colors are kept for the terminal but not stored
+        assert!(mismatch.rendered.as_deref().unwrap().contains('\u{1b}'));
+        let capture = mismatch.to_capture();
+        assert_eq!(
+            capture.description.as_deref(),
+            Some("error[E0308]: mismatched types\n --> src/lib.rs:3:21\n")
+        );
+        assert_eq!(capture.error_code.as_deref(), Some("E0308"));
+        let context = capture.context.unwrap();
+        assert_eq!(context["label"], "expected `String`, found `&str`");
+        assert_eq!(context["children"][0]["suggestion"], ".to_string()");
+    }
+
+    #[test]
+    fn strips_ansi_sequences() {
+        assert_eq!(strip_ansi("\u{1b}[1;38;5;9merror\u{1b}[0m: x"), "error: x");
+        assert_eq!(
+            strip_ansi("\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{1b}\\ ok"),
+            "link ok"
+        );
+        assert_eq!(strip_ansi("\u{1b}(Bplain ünïcødé\u{1b}7"), "plain ünïcødé");
+        assert_eq!(strip_ansi("no escapes"), "no escapes");
+    }
+
+    #[test]
+    fn collector_reports_each_diagnostic_once() {
+        let mut collector = DiagnosticCollector::new();
+        let mut new = 0;
+        for line in CARGO_JSON.lines() {
+            if collector.push_line(line).is_some() {
+                new += 1;
+            }
+        }
+        assert_eq!(new, 4);
+        assert_eq!(collector.diagnostics().len(), 4);
+    }
+}
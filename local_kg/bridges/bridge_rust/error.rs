@@ -0,0 +1,138 @@
+//! Error type for the Local KG bridge.
+
+use std::fmt;
+use std::sync::PoisonError;
+
+/// Everything that can go wrong talking to the Local KG.
+///
+/// `Send + Sync`, so it crosses `tokio::spawn` boundaries, and matchable so
+/// callers can choose to retry (`is_transient`), degrade or surface it.
+#[derive(Debug)]
+pub enum NebulaKgError {
+    /// `.nebula/config.json` or environment configuration is invalid
+    Config(String),
+    /// The selected backend can't be reached (worker crashed/timed out, lock poisoned, ...)
+    BackendUnavailable(String),
+    /// The Python process exited unsuccessfully
+    PythonProcess {
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// The Python worker answered with a JSON-RPC error
+    Worker { code: i64, message: String },
+    /// A response or stored JSON value couldn't be decoded
+    Json(serde_json::Error),
+    /// The database schema doesn't match what the bridge expects
+    SchemaMismatch { expected: String, found: String },
+    /// The requested pattern/event/solution does not exist
+    NotFound(String),
+    /// A value (severity, category, ...) isn't one the bridge recognises, or
+    /// doesn't fit the call (e.g. resolving an error with another pattern's solution)
+    InvalidValue(String),
+    /// The global instance was already initialized with a different database
+    DbPathMismatch {
+        initialized: String,
+        requested: String,
+    },
+    /// `set_local_kg` was called after the global instance was initialized
+    /// with the same database
+    AlreadyInitialized { db_path: String },
+    /// The Central KG answered a sync request with an HTTP error
+    Central { status: u16, message: String },
+    /// SQLite error from the native backend
+    Database(rusqlite::Error),
+    /// I/O error (spawning processes, creating directories, ...)
+    Io(std::io::Error),
+}
+
+impl NebulaKgError {
+    /// Whether retrying the same call later may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NebulaKgError::BackendUnavailable(_) | NebulaKgError::Io(_) => true,
+            NebulaKgError::Central { status, .. } => *status == 429 || *status >= 500,
+            NebulaKgError::Database(rusqlite::Error::SqliteFailure(e, _)) => matches!(
+                e.code,
+                rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for NebulaKgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NebulaKgError::Config(msg) => write!(f, "Invalid Local KG configuration: {}", msg),
+            NebulaKgError::BackendUnavailable(msg) => {
+                write!(f, "Local KG backend unavailable: {}", msg)
+            }
+            NebulaKgError::PythonProcess { exit_code, stderr } => match exit_code {
+                Some(code) => write!(f, "Python process failed (exit {}): {}", code, stderr),
+                None => write!(f, "Python process failed (killed): {}", stderr),
+            },
+            NebulaKgError::Worker { code, message } => {
+                write!(f, "Python worker error {}: {}", code, message)
+            }
+            NebulaKgError::Json(e) => write!(f, "Failed to decode Local KG JSON: {}", e),
+            NebulaKgError::SchemaMismatch { expected, found } => write!(
+                f,
+                "Local KG schema mismatch: expected {}, found {}",
+                expected, found
+            ),
+            NebulaKgError::NotFound(what) => write!(f, "Not found: {}", what),
+            NebulaKgError::InvalidValue(msg) => write!(f, "Invalid value: {}", msg),
+            NebulaKgError::DbPathMismatch {
+                initialized,
+                requested,
+            } => write!(
+                f,
+                "Global Local KG already initialized with {} (requested {})",
+                initialized, requested
+            ),
+            NebulaKgError::AlreadyInitialized { db_path } => {
+                write!(f, "Global Local KG already initialized with {}", db_path)
+            }
+            NebulaKgError::Central { status, message } => {
+                write!(f, "Central KG returned HTTP {}: {}", status, message)
+            }
+            NebulaKgError::Database(e) => write!(f, "Local KG database error: {}", e),
+            NebulaKgError::Io(e) => write!(f, "Local KG I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NebulaKgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NebulaKgError::Json(e) => Some(e),
+            NebulaKgError::Database(e) => Some(e),
+            NebulaKgError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for NebulaKgError {
+    fn from(e: serde_json::Error) -> Self {
+        NebulaKgError::Json(e)
+    }
+}
+
+impl From<rusqlite::Error> for NebulaKgError {
+    fn from(e: rusqlite::Error) -> Self {
+        NebulaKgError::Database(e)
+    }
+}
+
+impl From<std::io::Error> for NebulaKgError {
+    fn from(e: std::io::Error) -> Self {
+        NebulaKgError::Io(e)
+    }
+}
+
+impl<T> From<PoisonError<T>> for NebulaKgError {
+    fn from(_: PoisonError<T>) -> Self {
+        NebulaKgError::BackendUnavailable("Local KG lock poisoned".to_string())
+    }
+}
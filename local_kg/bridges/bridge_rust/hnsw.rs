@@ -0,0 +1,316 @@
+//! Minimal HNSW (hierarchical navigable small world) graph for cosine search.
+//!
+//! Vectors must be L2-normalized; distance is `1 - dot`. Nodes are never
+//! removed, only marked deleted (skipped in results but still traversed), so a
+//! changed pattern is re-inserted and its old node retired. Node levels come
+//! from a caller-supplied seed, so rebuilding the graph from the same vectors in
+//! the same order gives the same graph.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+/// Neighbours kept per node on upper layers (twice that on layer 0)
+const M: usize = 16;
+/// Candidate list size while inserting
+const EF_CONSTRUCTION: usize = 64;
+/// Minimum candidate list size while searching
+const EF_SEARCH: usize = 48;
+
+struct Node {
+    vector: Vec<f32>,
+    /// Neighbour ids per layer, `0..=level`
+    neighbors: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+/// Distance paired with a node id, ordered by distance
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+#[derive(Default)]
+pub(super) struct Hnsw {
+    nodes: Vec<Node>,
+    entry_point: Option<usize>,
+}
+
+impl Hnsw {
+    /// Add `vector`; `seed` (e.g. a hash of the item key) picks the node's level.
+    /// Returns the node id.
+    pub(super) fn insert(&mut self, vector: Vec<f32>, seed: u64) -> usize {
+        let level = level_for(seed);
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+
+        let Some(entry) = self.entry_point else {
+            self.entry_point = Some(id);
+            return id;
+        };
+        let top = self.level(entry);
+        let query = self.nodes[id].vector.clone();
+
+        let mut nearest = entry;
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy(&query, nearest, layer);
+        }
+        let mut entries = vec![nearest];
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entries, EF_CONSTRUCTION, layer);
+            let max_links = if layer == 0 { 2 * M } else { M };
+            let neighbors: Vec<usize> = found.iter().take(max_links).map(|c| c.id).collect();
+            for &neighbor in &neighbors {
+                self.nodes[neighbor].neighbors[layer].push(id);
+                self.prune(neighbor, layer, max_links);
+            }
+            self.nodes[id].neighbors[layer] = neighbors;
+            entries = found.into_iter().map(|c| c.id).collect();
+        }
+
+        if level > top {
+            self.entry_point = Some(id);
+        }
+        id
+    }
+
+    pub(super) fn vector(&self, id: usize) -> &[f32] {
+        &self.nodes[id].vector
+    }
+
+    /// Exclude a node from results
+    pub(super) fn mark_deleted(&mut self, id: usize) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.deleted = true;
+        }
+    }
+
+    /// Up to `k` live nodes closest to `query`, nearest first, as (id, cosine similarity)
+    pub(super) fn search(&self, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let Some(entry) = self.entry_point else {
+            return Vec::new();
+        };
+        let mut nearest = entry;
+        for layer in (1..=self.level(entry)).rev() {
+            nearest = self.greedy(query, nearest, layer);
+        }
+        self.search_layer(query, &[nearest], EF_SEARCH.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.id].deleted)
+            .take(k)
+            .map(|c| (c.id, 1.0 - c.distance))
+            .collect()
+    }
+
+    fn level(&self, id: usize) -> usize {
+        self.nodes[id].neighbors.len() - 1
+    }
+
+    fn distance(&self, query: &[f32], id: usize) -> f32 {
+        1.0 - dot(query, &self.nodes[id].vector)
+    }
+
+    /// Walk towards `query` on one layer until no neighbour is closer
+    fn greedy(&self, query: &[f32], start: usize, layer: usize) -> usize {
+        let mut current = start;
+        let mut best = self.distance(query, current);
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.nodes[current].neighbors[layer] {
+                let distance = self.distance(query, neighbor);
+                if distance < best {
+                    best = distance;
+                    current = neighbor;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Beam search on one layer; returns up to `ef` candidates, nearest first
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[usize],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut to_visit: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut found: BinaryHeap<Candidate> = BinaryHeap::new();
+        for &id in entries {
+            let candidate = Candidate {
+                distance: self.distance(query, id),
+                id,
+            };
+            to_visit.push(Reverse(candidate));
+            found.push(candidate);
+        }
+
+        while let Some(Reverse(current)) = to_visit.pop() {
+            if found.len() >= ef
+                && found
+                    .peek()
+                    .is_some_and(|worst| current.distance > worst.distance)
+            {
+                break;
+            }
+            for &neighbor in &self.nodes[current.id].neighbors[layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(query, neighbor),
+                    id: neighbor,
+                };
+                if found.len() < ef || found.peek().is_some_and(|worst| candidate < *worst) {
+                    to_visit.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found.into_sorted_vec()
+    }
+
+    /// Keep only the `max_links` closest neighbours of `id` on `layer`
+    fn prune(&mut self, id: usize, layer: usize, max_links: usize) {
+        if self.nodes[id].neighbors[layer].len() <= max_links {
+            return;
+        }
+        let vector = self.nodes[id].vector.clone();
+        let mut scored: Vec<Candidate> = self.nodes[id].neighbors[layer]
+            .iter()
+            .map(|&neighbor| Candidate {
+                distance: self.distance(&vector, neighbor),
+                id: neighbor,
+            })
+            .collect();
+        scored.sort();
+        self.nodes[id].neighbors[layer] =
+            scored.into_iter().take(max_links).map(|c| c.id).collect();
+    }
+}
+
+/// Level with the usual exponential distribution (`mL = 1 / ln M`)
+fn level_for(seed: u64) -> usize {
+    // splitmix64 finalizer, then map to (0, 1]
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    let level = -uniform.ln() / (M as f64).ln();
+    (level as usize).min(16)
+}
+
+pub(super) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic unit vectors (xorshift64)
+    fn vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..count)
+            .map(|_| {
+                let vector: Vec<f32> = (0..dimensions)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+                    })
+                    .collect();
+                let norm = dot(&vector, &vector).sqrt();
+                vector.into_iter().map(|x| x / norm).collect()
+            })
+            .collect()
+    }
+
+    fn build(vectors: &[Vec<f32>]) -> Hnsw {
+        let mut graph = Hnsw::default();
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(graph.insert(vector.clone(), i as u64), i);
+        }
+        graph
+    }
+
+    #[test]
+    fn finds_the_nearest_vectors() {
+        let vectors = vectors(500, 24);
+        let graph = build(&vectors);
+
+        for (i, vector) in vectors.iter().enumerate().step_by(7) {
+            let found = graph.search(vector, 5);
+            assert_eq!(found.len(), 5);
+            assert_eq!(found[0].0, i);
+            assert!((found[0].1 - 1.0).abs() < 1e-5);
+            assert!(found.windows(2).all(|w| w[0].1 >= w[1].1));
+
+            // Same top 5 as a brute-force scan
+            let mut exact: Vec<(usize, f32)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(j, other)| (j, dot(vector, other)))
+                .collect();
+            exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let exact: Vec<usize> = exact.iter().take(5).map(|(j, _)| *j).collect();
+            let found: Vec<usize> = found.iter().map(|(j, _)| *j).collect();
+            assert_eq!(found, exact);
+        }
+    }
+
+    #[test]
+    fn skips_deleted_nodes() {
+        let vectors = vectors(50, 8);
+        let mut graph = build(&vectors);
+        assert!(Hnsw::default().search(&vectors[0], 3).is_empty());
+
+        graph.mark_deleted(10);
+        let found = graph.search(&vectors[10], 3);
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().all(|(id, _)| *id != 10));
+
+        // A re-inserted vector replaces the retired node
+        let id = graph.insert(vectors[10].clone(), 10);
+        assert_eq!(graph.search(&vectors[10], 1)[0].0, id);
+        assert_eq!(graph.vector(id), vectors[10].as_slice());
+    }
+
+    #[test]
+    fn levels_are_deterministic() {
+        assert_eq!(level_for(42), level_for(42));
+        let ground_level = (0..10_000).filter(|&seed| level_for(seed) == 0).count();
+        // P(level 0) = 1 - 1/M
+        assert!((9_200..9_600).contains(&ground_level), "{}", ground_level);
+    }
+}
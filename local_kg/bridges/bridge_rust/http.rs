@@ -0,0 +1,265 @@
+//! Minimal blocking HTTP/1.1 client for talking to the Central KG.
+//!
+//! Only what sync needs: plain `http://` URLs, JSON bodies, one request per
+//! connection (`Connection: close`), `Content-Length` or chunked responses.
+//! TLS is out of scope; put the Central KG behind a local proxy, or plug in
+//! another client through `SyncTransport`.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use super::sync::SyncTransport;
+use super::NebulaKgError;
+
+/// Default time to connect, and to wait for each read or write
+pub const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest error body kept in `NebulaKgError::Central`
+const MAX_ERROR_BODY: usize = 500;
+
+/// `SyncTransport` over plain HTTP using only the standard library
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    host: String,
+    port: u16,
+    /// Path prefix from the URL, without a trailing slash
+    base_path: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    /// Transport for `url` (e.g. `http://localhost:8080`). Fails with `Config`
+    /// for anything but an `http://` URL.
+    pub fn new(url: &str) -> Result<Self, NebulaKgError> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            NebulaKgError::Config(format!(
+                "Central KG URL must start with http:// (got {})",
+                url
+            ))
+        })?;
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        // `[::1]:8080` keeps its colons inside the brackets
+        let port_sep = match authority.rfind(']') {
+            Some(bracket) => authority[bracket..].find(':').map(|i| bracket + i),
+            None => authority.rfind(':'),
+        };
+        let (host, port) = match port_sep {
+            Some(i) => {
+                let port = authority[i + 1..].parse().map_err(|_| {
+                    NebulaKgError::Config(format!("Invalid port in Central KG URL {}", url))
+                })?;
+                (&authority[..i], port)
+            }
+            None => (authority, 80),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(NebulaKgError::Config(format!(
+                "Central KG URL has no host: {}",
+                url
+            )));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            base_path: path.trim_end_matches('/').to_string(),
+            api_key: None,
+            timeout: DEFAULT_SYNC_TIMEOUT,
+        })
+    }
+
+    /// Send `X-API-Key` with every request. Fails with `Config` if the key
+    /// contains control characters.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Result<Self, NebulaKgError> {
+        let api_key = api_key.into();
+        header_value("Central KG API key", &api_key)?;
+        self.api_key = Some(api_key);
+        Ok(self)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> Result<TcpStream, NebulaKgError> {
+        let mut last_error = None;
+        for addr in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{} did not resolve", self.host),
+                )
+            })
+            .into())
+    }
+
+    fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == 80 {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+}
+
+impl SyncTransport for HttpTransport {
+    fn post_json(
+        &self,
+        path: &str,
+        body: &Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<Value, NebulaKgError> {
+        let body = serde_json::to_vec(body)?;
+        let mut request = format!(
+            "POST {}{} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Accept: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.base_path,
+            path,
+            self.host_header(),
+            body.len()
+        );
+        if let Some(api_key) = &self.api_key {
+            request.push_str(&format!("X-API-Key: {}\r\n", api_key));
+        }
+        if let Some(key) = idempotency_key {
+            let key = header_value("Idempotency key", key)?;
+            request.push_str(&format!("Idempotency-Key: {}\r\n", key));
+        }
+        request.push_str("\r\n");
+
+        let mut stream = self.connect()?;
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(&body)?;
+        stream.flush()?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        if response.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Central KG closed the connection without answering",
+            )
+            .into());
+        }
+        let (status, body) = parse_response(&response)?;
+
+        if !(200..300).contains(&status) {
+            let mut message = String::from_utf8_lossy(&body).trim().to_string();
+            if message.len() > MAX_ERROR_BODY {
+                let cut = (0..=MAX_ERROR_BODY)
+                    .rev()
+                    .find(|&i| message.is_char_boundary(i))
+                    .unwrap_or(0);
+                message.truncate(cut);
+                message.push('…');
+            }
+            return Err(NebulaKgError::Central { status, message });
+        }
+        if body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Value::Null);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// `value` if it can be sent as a header value: a CR, LF or other control
+/// character would end the header early and let the rest inject headers
+fn header_value<'a>(name: &str, value: &'a str) -> Result<&'a str, NebulaKgError> {
+    if value.chars().any(char::is_control) {
+        return Err(NebulaKgError::Config(format!(
+            "{} contains control characters",
+            name
+        )));
+    }
+    Ok(value)
+}
+
+fn malformed(what: &str) -> NebulaKgError {
+    NebulaKgError::BackendUnavailable(format!("Malformed Central KG response: {}", what))
+}
+
+/// Status code and (de-chunked) body of a complete HTTP/1.1 response
+fn parse_response(response: &[u8]) -> Result<(u16, Vec<u8>), NebulaKgError> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| malformed("no end of headers"))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let body = &response[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| malformed("bad status line"))?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.to_ascii_lowercase().contains("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+
+    let body = if chunked {
+        dechunk(body)?
+    } else if let Some(length) = content_length {
+        body.get(..length)
+            .ok_or_else(|| malformed("body shorter than Content-Length"))?
+            .to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok((status, body))
+}
+
+/// Decode a `Transfer-Encoding: chunked` body (trailers are ignored)
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>, NebulaKgError> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| malformed("truncated chunk size"))?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16)
+            .map_err(|_| malformed("bad chunk size"))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = body
+            .get(..size)
+            .ok_or_else(|| malformed("truncated chunk"))?;
+        decoded.extend_from_slice(chunk);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+}
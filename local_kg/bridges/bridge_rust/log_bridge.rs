@@ -0,0 +1,161 @@
+//! `log::Log` wrapper that records error-level records in the Local KG.
+//!
+//! Every record is passed to the wrapped logger unchanged. `Error`-level
+//! records are additionally captured (message, target, module path, file,
+//! line) through the bridge's fire-and-forget path, so logging never waits on
+//! the database.
+//!
+//! ```ignore
+//! let kg = get_local_kg(None)?;
+//! KgLogger::new(&kg, Box::new(env_logger::Builder::from_default_env().build()))
+//!     .init(log::LevelFilter::Info)?;
+//! ```
+
+use std::sync::Arc;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_json::json;
+
+use super::backend::KgBackend;
+use super::{bridge_module_path, spawn_capture, Category, ErrorCapture, LocalKGBridge, Severity};
+
+/// Logger that forwards to `inner` and captures `Error` records
+pub struct KgLogger {
+    inner: Box<dyn Log>,
+    backend: Arc<dyn KgBackend>,
+}
+
+impl KgLogger {
+    pub fn new(bridge: &LocalKGBridge, inner: Box<dyn Log>) -> Self {
+        Self {
+            inner,
+            backend: bridge.backend.clone(),
+        }
+    }
+
+    /// Install as the global logger with the given maximum level
+    pub fn init(self, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    fn capture(&self, record: &Record<'_>) {
+        let message = record.args().to_string();
+        spawn_capture(
+            self.backend.clone(),
+            ErrorCapture {
+                level: Some(record.level().as_str().to_string()),
+                file_path: record.file().map(str::to_string),
+                line_number: record.line(),
+                ..ErrorCapture::new(
+                    format!("{}: {}", record.target(), message),
+                    Category::Runtime,
+                )
+            }
+            .with_severity(Severity::High)
+            .with_description(message)
+            .with_context(json!({
+                "target": record.target(),
+                "module_path": record.module_path(),
+            })),
+        );
+    }
+}
+
+impl Log for KgLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() == log::Level::Error || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() == log::Level::Error && !record.target().starts_with(bridge_module_path())
+        {
+            self.capture(record);
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use log::Level;
+
+    use super::super::memory::MemoryBackend;
+    use super::*;
+
+    /// Counts the records forwarded to it
+    struct CountingLogger(Arc<AtomicUsize>);
+
+    impl Log for CountingLogger {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.level() <= Level::Info
+        }
+
+        fn log(&self, _record: &Record<'_>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn log(logger: &KgLogger, level: Level, target: &str, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .module_path(Some(target))
+                .file(Some("src/db.rs"))
+                .line(Some(17))
+                .args(format_args!("{}", message))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn captures_error_records_and_forwards_everything() {
+        let backend = Arc::new(MemoryBackend::new());
+        let kg = LocalKGBridge::with_backend(backend.clone()).unwrap();
+        let forwarded = Arc::new(AtomicUsize::new(0));
+        let logger = KgLogger::new(&kg, Box::new(CountingLogger(forwarded.clone())));
+
+        assert!(logger.enabled(&Metadata::builder().level(Level::Error).build()));
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Debug).build()));
+
+        log(&logger, Level::Warn, "app::db", "slow query");
+        log(&logger, Level::Info, "app::db", "connected");
+        // What the bridge itself logs when a capture fails
+        let own_target = format!("{}::sqlite", bridge_module_path());
+        log(&logger, Level::Error, &own_target, "database is locked");
+        log(&logger, Level::Error, "app::db", "connection reset");
+        assert_eq!(forwarded.load(Ordering::SeqCst), 4);
+
+        // Captures are written in the background
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let events = loop {
+            let events = backend.recent_events(50).unwrap();
+            if !events.is_empty() || Instant::now() > deadline {
+                break events;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.content.as_deref(), Some("connection reset"));
+        assert_eq!(event.severity(), Some(Severity::High));
+        assert_eq!(event.category(), Some(Category::Runtime));
+        assert_eq!(event.metadata["level"], "ERROR");
+        assert_eq!(event.metadata["signature"], "app::db: connection reset");
+        assert_eq!(event.metadata["file_path"], "src/db.rs");
+        assert_eq!(event.metadata["line_number"], 17);
+        assert_eq!(event.metadata["context"]["target"], "app::db");
+    }
+}
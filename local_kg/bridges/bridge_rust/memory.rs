@@ -0,0 +1,682 @@
+//! In-memory backend.
+//!
+//! Holds patterns and events in process memory with the same semantics as the
+//! SQLite backend. Intended for test suites that must not touch disk.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use super::backend::KgBackend;
+use super::resolution::check_same_pattern;
+use super::solution::{promoted_solution, solutions_from_events};
+use super::{
+    Category, ErrorCapture, ErrorPattern, ErrorResolution, Fix, KgEvent, NebulaKgError, OutboxItem,
+    OutboxStatus, OutboxUpdate, PatternSearch, PatternSummary, ResolutionStats, SearchHit,
+    Solution, SolutionFeedback, SyncRecord, SyncStatus, SyncType, UnsyncedSolution, SCHEMA_VERSION,
+};
+
+#[derive(Default)]
+struct MemoryState {
+    patterns: Vec<ErrorPattern>,
+    events: Vec<KgEvent>,
+    sync_history: Vec<SyncRecord>,
+    sync_outbox: Vec<OutboxItem>,
+}
+
+/// Backend that keeps everything in memory (lost when dropped)
+#[derive(Default)]
+pub struct MemoryBackend {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MemoryState {
+    fn push_event(
+        &mut self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: Value,
+    ) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.events.push(KgEvent {
+            id: id.clone(),
+            event_type: event_type.to_string(),
+            phase: Some(phase.to_string()),
+            content: Some(content.to_string()),
+            metadata,
+            created_at: timestamp_now(),
+        });
+        id
+    }
+
+    /// Promoted solution plus every `solution` event for the pattern, as indexed
+    /// by the SQLite backend
+    fn solution_text(&self, pattern: &ErrorPattern) -> String {
+        let mut parts: Vec<&str> = pattern.solution.iter().map(String::as_str).collect();
+        parts.extend(
+            self.events
+                .iter()
+                .filter(|e| {
+                    e.event_type == "solution"
+                        && e.metadata["target_signature"] == pattern.signature.as_str()
+                        && e.content != pattern.solution
+                })
+                .filter_map(|e| e.content.as_deref()),
+        );
+        parts.join(" ")
+    }
+
+    /// Ranked solutions (with their feedback) for the pattern with `signature`
+    fn solutions_for(&self, signature: &str) -> Vec<Solution> {
+        solutions_from_events(
+            self.events
+                .iter()
+                .filter(|e| e.metadata["target_signature"] == signature),
+        )
+        .remove(signature)
+        .unwrap_or_default()
+    }
+
+    /// `pattern` with its `solutions` filled in
+    fn with_solutions(&self, pattern: &ErrorPattern) -> ErrorPattern {
+        ErrorPattern {
+            solutions: self.solutions_for(&pattern.signature),
+            ..pattern.clone()
+        }
+    }
+
+    /// Re-derive the promoted `solution` of the pattern at `index`
+    fn promote_best(&mut self, index: usize) {
+        let ranked = self.solutions_for(&self.patterns[index].signature);
+        let pattern = &mut self.patterns[index];
+        pattern.solution = promoted_solution(pattern.solution.as_deref(), &ranked);
+    }
+
+    /// Record a `solution` event for the pattern at `index` and re-rank
+    fn insert_solution(&mut self, index: usize, solution: &Solution) -> String {
+        let signature = self.patterns[index].signature.clone();
+        let meta = solution.event_metadata(&signature);
+        let event_id = self.push_event("solution", "UNKNOWN", &solution.description, meta);
+        self.promote_best(index);
+        event_id
+    }
+
+    /// Signature of the pattern the solution event `solution_id` was recorded for
+    fn solution_signature(&self, solution_id: &str) -> Result<String, NebulaKgError> {
+        self.events
+            .iter()
+            .find(|e| e.id == solution_id && e.event_type == "solution")
+            .and_then(|e| e.metadata["target_signature"].as_str())
+            .map(String::from)
+            .ok_or_else(|| NebulaKgError::NotFound(format!("solution {}", solution_id)))
+    }
+
+    /// Record a `solution_feedback` event and re-rank
+    fn insert_feedback(
+        &mut self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError> {
+        let signature = self.solution_signature(solution_id)?;
+
+        let meta = feedback.event_metadata(solution_id, &signature);
+        let event_id = self.push_event(
+            "solution_feedback",
+            "UNKNOWN",
+            &feedback.event_content(),
+            meta,
+        );
+        if let Some(index) = self.patterns.iter().position(|p| p.signature == signature) {
+            self.promote_best(index);
+        }
+        Ok(event_id)
+    }
+
+    /// Local ids of `sync_type` with a successful sync record or in the outbox
+    fn synced_or_queued_ids(&self, sync_type: SyncType) -> HashSet<&str> {
+        let synced = self
+            .sync_history
+            .iter()
+            .filter(|r| r.sync_type == sync_type && r.status == SyncStatus::Success)
+            .map(|r| r.local_id.as_str());
+        let queued = self
+            .sync_outbox
+            .iter()
+            .filter(|item| item.sync_type == sync_type)
+            .map(|item| item.local_id.as_str());
+        synced.chain(queued).collect()
+    }
+
+    /// Resolutions written by `resolve_error`, oldest first
+    fn error_resolutions(&self) -> impl Iterator<Item = ErrorResolution> + '_ {
+        self.events
+            .iter()
+            .filter(|e| e.event_type == "resolution")
+            .filter_map(ErrorResolution::from_event)
+    }
+
+    /// `error` events that no `resolve_error` resolution points at, oldest first
+    fn open_errors(&self) -> impl Iterator<Item = &KgEvent> {
+        let resolved: HashSet<String> =
+            self.error_resolutions().map(|r| r.error_event_id).collect();
+        self.events
+            .iter()
+            .filter(move |e| e.event_type == "error" && !resolved.contains(&e.id))
+    }
+}
+
+impl KgBackend for MemoryBackend {
+    fn capture(&self, capture: &ErrorCapture) -> Result<String, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        let meta = capture.event_metadata();
+        let content = capture.event_content();
+        let event_id = state.push_event("error", capture.event_phase(), &content, meta);
+
+        let now = timestamp_now();
+        match state
+            .patterns
+            .iter_mut()
+            .find(|p| p.signature == capture.signature)
+        {
+            Some(pattern) => {
+                pattern.occurrence_count += 1;
+                pattern.last_seen_at = Some(now);
+                pattern.resolved_at = None;
+            }
+            None => state.patterns.push(ErrorPattern {
+                id: uuid::Uuid::new_v4().to_string(),
+                signature: capture.signature.clone(),
+                category: Some(capture.category.clone()),
+                description: capture.description.clone(),
+                solution: None,
+                occurrence_count: 1,
+                last_seen_at: Some(now),
+                resolved_at: None,
+                solutions: Vec::new(),
+            }),
+        }
+
+        Ok(event_id)
+    }
+
+    fn search_patterns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        let state = self.state.lock()?;
+        // Plain substring match, like the SQLite backend's escaped LIKE
+        let query = query.to_lowercase();
+
+        let mut matches: Vec<&ErrorPattern> = state
+            .patterns
+            .iter()
+            .filter(|p| {
+                p.signature.to_lowercase().contains(&query)
+                    || p.category
+                        .as_ref()
+                        .map_or("", Category::as_str)
+                        .to_lowercase()
+                        .contains(&query)
+                    || p.description
+                        .as_deref()
+                        .unwrap_or("")
+                        .to_lowercase()
+                        .contains(&query)
+            })
+            .collect();
+        matches.sort_by_key(|p| std::cmp::Reverse(p.occurrence_count));
+
+        Ok(matches
+            .into_iter()
+            .take(limit)
+            .map(|p| state.with_solutions(p))
+            .collect())
+    }
+
+    fn search(&self, search: &PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError> {
+        let state = self.state.lock()?;
+        let mut hits: Vec<SearchHit> = state
+            .patterns
+            .iter()
+            .filter_map(|pattern| search.score(pattern, &state.solution_text(pattern)))
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(search.limit);
+        for hit in &mut hits {
+            hit.pattern = state.with_solutions(&hit.pattern);
+        }
+        Ok(hits)
+    }
+
+    fn record_solution(
+        &self,
+        pattern_id: &str,
+        solution: &Solution,
+    ) -> Result<String, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        let index = state
+            .patterns
+            .iter()
+            .position(|p| p.id == pattern_id || p.signature == pattern_id)
+            .ok_or_else(|| NebulaKgError::NotFound(format!("pattern {}", pattern_id)))?;
+
+        Ok(state.insert_solution(index, solution))
+    }
+
+    fn record_feedback(
+        &self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        state.insert_feedback(solution_id, feedback)
+    }
+
+    /// Resolving an occurrence twice returns the first resolution
+    fn resolve_error(
+        &self,
+        error_event_id: &str,
+        fix: &Fix,
+    ) -> Result<ErrorResolution, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        if let Some(resolution) = state
+            .error_resolutions()
+            .find(|r| r.error_event_id == error_event_id)
+        {
+            return Ok(resolution);
+        }
+
+        let (signature, captured_at) = state
+            .events
+            .iter()
+            .find(|e| e.id == error_event_id && e.event_type == "error")
+            .and_then(|e| Some((e.metadata["signature"].as_str()?, e.created_at.clone())))
+            .map(|(signature, captured_at)| (signature.to_string(), captured_at))
+            .ok_or_else(|| NebulaKgError::NotFound(format!("error event {}", error_event_id)))?;
+        let resolved_at = timestamp_now();
+        let minutes = match (parse_timestamp(&captured_at), parse_timestamp(&resolved_at)) {
+            (Some(from), Some(to)) => u32::try_from((to - from).max(0) / 60).unwrap_or(u32::MAX),
+            _ => 0,
+        };
+
+        let solution_id = match fix {
+            Fix::New(solution) => {
+                let index = state
+                    .patterns
+                    .iter()
+                    .position(|p| p.signature == signature)
+                    .ok_or_else(|| NebulaKgError::NotFound(format!("pattern {}", signature)))?;
+                let mut solution = solution.clone();
+                solution.time_to_resolve_minutes.get_or_insert(minutes);
+                state.insert_solution(index, &solution)
+            }
+            Fix::Existing(solution_id) => {
+                check_same_pattern(
+                    solution_id,
+                    &state.solution_signature(solution_id)?,
+                    &signature,
+                )?;
+                let feedback = SolutionFeedback::helpful().with_resolution_time(minutes);
+                state.insert_feedback(solution_id, &feedback)?;
+                solution_id.clone()
+            }
+        };
+
+        let meta = json!({
+            "error_event_id": error_event_id,
+            "solution_id": solution_id,
+            "target_signature": signature,
+            "time_to_resolve_minutes": minutes,
+        });
+        let event_id = state.push_event(
+            "resolution",
+            "UNKNOWN",
+            &format!("Resolved in {} min", minutes),
+            meta,
+        );
+
+        // The pattern is resolved once none of its occurrences are open
+        let still_open = state
+            .open_errors()
+            .any(|e| e.metadata["signature"] == signature.as_str());
+        if !still_open {
+            if let Some(pattern) = state
+                .patterns
+                .iter_mut()
+                .find(|p| p.signature == signature && p.resolved_at.is_none())
+            {
+                pattern.resolved_at = Some(resolved_at.clone());
+            }
+        }
+
+        Ok(ErrorResolution {
+            event_id,
+            error_event_id: error_event_id.to_string(),
+            signature,
+            solution_id,
+            time_to_resolve_minutes: minutes,
+            resolved_at,
+        })
+    }
+
+    fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        let state = self.state.lock()?;
+        let open: Vec<&KgEvent> = state.open_errors().collect();
+        Ok(open.into_iter().rev().take(limit).cloned().collect())
+    }
+
+    fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError> {
+        let state = self.state.lock()?;
+        let minutes: Vec<u32> = state
+            .error_resolutions()
+            .map(|r| r.time_to_resolve_minutes)
+            .collect();
+        Ok(ResolutionStats {
+            open_errors: state.open_errors().count() as i64,
+            resolved_errors: minutes.len() as i64,
+            mean_time_to_fix_minutes: (!minutes.is_empty())
+                .then(|| minutes.iter().map(|&m| f64::from(m)).sum::<f64>() / minutes.len() as f64),
+        })
+    }
+
+    fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: &Value,
+    ) -> Result<Option<String>, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        let Some(pattern) = state
+            .patterns
+            .iter_mut()
+            .find(|p| p.signature == signature && p.resolved_at.is_none())
+        else {
+            return Ok(None);
+        };
+        pattern.resolved_at = Some(timestamp_now());
+
+        let meta = json!({
+            "target_signature": signature,
+            "context": context,
+        });
+        Ok(Some(state.push_event(
+            "resolution",
+            "UNKNOWN",
+            content,
+            meta,
+        )))
+    }
+
+    fn get_summary(&self) -> Result<PatternSummary, NebulaKgError> {
+        let state = self.state.lock()?;
+        let count_events = |event_type: &str| {
+            state
+                .events
+                .iter()
+                .filter(|e| e.event_type == event_type)
+                .count() as i64
+        };
+
+        let mut top_patterns: Vec<&ErrorPattern> = state.patterns.iter().collect();
+        top_patterns.sort_by_key(|p| std::cmp::Reverse(p.occurrence_count));
+        top_patterns.truncate(10);
+        let top_patterns = top_patterns
+            .into_iter()
+            .map(|p| state.with_solutions(p))
+            .collect();
+
+        Ok(PatternSummary {
+            total_patterns: state.patterns.len() as i64,
+            total_errors: count_events("error"),
+            total_solutions: count_events("solution"),
+            patterns_with_solution: state
+                .patterns
+                .iter()
+                .filter(|p| p.solution.is_some())
+                .count() as i64,
+            top_patterns,
+        })
+    }
+
+    fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: &Value,
+    ) -> Result<String, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        Ok(state.push_event(event_type, phase, content, metadata.clone()))
+    }
+
+    fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        let state = self.state.lock()?;
+        Ok(state.events.iter().rev().take(limit).cloned().collect())
+    }
+
+    fn unsynced_patterns(&self, limit: usize) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        let state = self.state.lock()?;
+        let synced = state.synced_or_queued_ids(SyncType::Pattern);
+        let mut patterns: Vec<&ErrorPattern> = state
+            .patterns
+            .iter()
+            .filter(|p| !synced.contains(p.id.as_str()))
+            .collect();
+        patterns.sort_by_key(|p| std::cmp::Reverse(p.occurrence_count));
+
+        Ok(patterns
+            .into_iter()
+            .take(limit)
+            .map(|p| state.with_solutions(p))
+            .collect())
+    }
+
+    fn unsynced_solutions(&self, limit: usize) -> Result<Vec<UnsyncedSolution>, NebulaKgError> {
+        let state = self.state.lock()?;
+        let synced = state.synced_or_queued_ids(SyncType::Solution);
+        let pending = state
+            .events
+            .iter()
+            .filter(|e| e.event_type == "solution" && !synced.contains(e.id.as_str()))
+            .filter_map(|e| Some((e.id.as_str(), e.metadata["target_signature"].as_str()?)))
+            .take(limit);
+
+        let mut unsynced = Vec::new();
+        for (id, signature) in pending {
+            let Some(solution) = state
+                .solutions_for(signature)
+                .into_iter()
+                .find(|s| s.id == id)
+            else {
+                continue;
+            };
+            let central_pattern_id = state
+                .patterns
+                .iter()
+                .find(|p| p.signature == signature)
+                .and_then(|pattern| {
+                    state.sync_history.iter().rev().find(|r| {
+                        r.sync_type == SyncType::Pattern
+                            && r.status == SyncStatus::Success
+                            && r.local_id == pattern.id
+                    })
+                })
+                .and_then(|r| r.central_id.clone());
+            unsynced.push(UnsyncedSolution {
+                solution,
+                signature: signature.to_string(),
+                central_pattern_id,
+            });
+        }
+        Ok(unsynced)
+    }
+
+    fn record_sync(&self, records: &[SyncRecord]) -> Result<(), NebulaKgError> {
+        let mut state = self.state.lock()?;
+        let now = timestamp_now();
+        state
+            .sync_history
+            .extend(records.iter().map(|r| SyncRecord {
+                synced_at: Some(now.clone()),
+                ..r.clone()
+            }));
+        Ok(())
+    }
+
+    fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError> {
+        let state = self.state.lock()?;
+        Ok(state
+            .sync_history
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn enqueue_sync(&self, items: &[OutboxItem]) -> Result<usize, NebulaKgError> {
+        let mut state = self.state.lock()?;
+        let now = timestamp_now();
+        let mut added = 0;
+        for item in items {
+            let queued = state.sync_outbox.iter().any(|queued| {
+                (queued.sync_type == item.sync_type && queued.local_id == item.local_id)
+                    || queued.idempotency_key == item.idempotency_key
+            });
+            if !queued {
+                state.sync_outbox.push(OutboxItem {
+                    next_attempt_at: Some(now.clone()),
+                    created_at: Some(now.clone()),
+                    ..item.clone()
+                });
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    fn due_sync_items(
+        &self,
+        sync_type: SyncType,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        let state = self.state.lock()?;
+        let now = timestamp_now();
+        Ok(state
+            .sync_outbox
+            .iter()
+            .filter(|item| {
+                item.sync_type == sync_type
+                    && item.status == OutboxStatus::Pending
+                    && item
+                        .next_attempt_at
+                        .as_deref()
+                        .is_none_or(|at| at <= now.as_str())
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn update_sync_items(&self, updates: &[OutboxUpdate]) -> Result<(), NebulaKgError> {
+        let mut state = self.state.lock()?;
+        for update in updates {
+            let Some(item) = state.sync_outbox.iter_mut().find(|i| i.id == update.id) else {
+                continue;
+            };
+            item.status = update.status;
+            item.attempts += 1;
+            if update.central_id.is_some() {
+                item.central_id = update.central_id.clone();
+            }
+            item.last_error = update.error.clone();
+            item.next_attempt_at = Some(timestamp_in(update.retry_in_secs));
+        }
+        Ok(())
+    }
+
+    fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        let state = self.state.lock()?;
+        Ok(state
+            .sync_outbox
+            .iter()
+            .rev()
+            .filter(|item| status.is_none_or(|s| item.status == s))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn schema_version(&self) -> Result<Option<String>, NebulaKgError> {
+        Ok(Some(SCHEMA_VERSION.to_string()))
+    }
+}
+
+/// Current UTC time in SQLite's `CURRENT_TIMESTAMP` format (`YYYY-MM-DD HH:MM:SS`)
+fn timestamp_now() -> String {
+    timestamp_in(0)
+}
+
+/// UTC time `secs` seconds from now, in the same format as `timestamp_now`
+fn timestamp_in(secs: u64) -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs() + secs) as i64)
+        .unwrap_or(0);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60
+    )
+}
+
+/// Seconds since the Unix epoch for a `timestamp_now` string
+fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let (date, time) = timestamp.split_once(' ')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    // Days-from-civil, the inverse of the conversion above
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
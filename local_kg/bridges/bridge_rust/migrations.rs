@@ -0,0 +1,744 @@
+//! Ordered schema migrations for Local KG database files.
+//!
+//! Each migration brings a database up to its `version` and is recorded in the
+//! `schema_migrations` table once applied, so running the migrator again only
+//! applies what is missing. The first migration also converts a legacy
+//! `local_kg/schema.sql` database (`local_patterns` / `local_solutions`) into
+//! Universal Schema `patterns` + `events`; the legacy tables are left in place.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::{json, Value};
+
+use super::schema::{
+    self, SCHEMA_VERSION, SYNC_HISTORY_SCHEMA, SYNC_OUTBOX_SCHEMA, UNIVERSAL_SCHEMA,
+};
+use super::{Category, NebulaKgError, Severity};
+
+/// Bookkeeping table listing every applied migration
+const MIGRATIONS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+/// Version reported for a database that only has the legacy tables
+pub const LEGACY_SCHEMA_VERSION: &str = "legacy";
+
+/// One step in the migration chain
+pub struct Migration {
+    /// Schema version the database is at after this migration
+    pub version: &'static str,
+    pub description: &'static str,
+    apply: fn(&Transaction<'_>) -> Result<(), NebulaKgError>,
+}
+
+/// All migrations, oldest first. The last one must match `SCHEMA_VERSION`.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: "1.0",
+        description: "Create Universal Schema tables and import legacy local_patterns",
+        apply: create_universal_schema,
+    },
+    Migration {
+        version: "1.1",
+        description: "Track schema_version in project_info",
+        apply: add_schema_version_column,
+    },
+    Migration {
+        version: "1.2",
+        description: "Track when a pattern was resolved",
+        apply: add_pattern_resolved_at,
+    },
+    Migration {
+        version: "1.3",
+        description: "Full-text search index over patterns and solutions",
+        apply: create_pattern_fts,
+    },
+    Migration {
+        version: "1.4",
+        description: "Sync history for Central KG pushes",
+        apply: create_sync_history,
+    },
+    Migration {
+        version: "1.5",
+        description: "Outbox for Central KG sync with retries",
+        apply: create_sync_outbox,
+    },
+];
+
+/// Schema version of an open database.
+///
+/// Uses the migration history when present, then `project_info.schema_version`,
+/// then falls back to which tables exist. `None` means an empty database.
+pub fn detect_schema_version(conn: &Connection) -> Result<Option<String>, NebulaKgError> {
+    if let Some(version) = latest_applied(conn)? {
+        return Ok(Some(version));
+    }
+    if let Some(version) = schema::recorded_version(conn)? {
+        return Ok(Some(version));
+    }
+    if !schema::table_columns(conn, "patterns")?.is_empty() {
+        return Ok(Some("1.0".to_string()));
+    }
+    if !schema::table_columns(conn, "local_patterns")?.is_empty() {
+        return Ok(Some(LEGACY_SCHEMA_VERSION.to_string()));
+    }
+    Ok(None)
+}
+
+/// Migrations not yet recorded in `schema_migrations`, in order
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>, NebulaKgError> {
+    let applied = applied_versions(conn)?;
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|v| v == m.version))
+        .collect())
+}
+
+/// Apply every pending migration, each in its own transaction.
+/// Returns the versions that were applied (empty if already up to date).
+pub fn run_migrations(conn: &mut Connection) -> Result<Vec<&'static str>, NebulaKgError> {
+    // Refuse to touch a database written by a newer (unknown) schema
+    let mut recorded = applied_versions(conn)?;
+    recorded.extend(schema::recorded_version(conn)?);
+    if let Some(found) = recorded
+        .into_iter()
+        .find(|v| !MIGRATIONS.iter().any(|m| m.version == v))
+    {
+        return Err(NebulaKgError::SchemaMismatch {
+            expected: SCHEMA_VERSION.to_string(),
+            found,
+        });
+    }
+
+    conn.execute_batch(MIGRATIONS_TABLE)?;
+    let mut applied = Vec::new();
+    for migration in pending_migrations(conn)? {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, description) VALUES (?1, ?2)",
+            params![migration.version, migration.description],
+        )?;
+        if schema::table_columns(&tx, "project_info")?
+            .iter()
+            .any(|c| c == "schema_version")
+        {
+            tx.execute(
+                "UPDATE project_info SET schema_version = ?1",
+                params![migration.version],
+            )?;
+        }
+        tx.commit()?;
+
+        tracing::info!(
+            "Applied Local KG migration {}: {}",
+            migration.version,
+            migration.description
+        );
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+/// Open the database at `db_path` (creating it if needed) and migrate it
+pub fn migrate_db(db_path: &str) -> Result<Vec<&'static str>, NebulaKgError> {
+    if let Some(parent) = Path::new(db_path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    let mut conn = Connection::open(db_path)?;
+    run_migrations(&mut conn)
+}
+
+fn applied_versions(conn: &Connection) -> Result<Vec<String>, NebulaKgError> {
+    if schema::table_columns(conn, "schema_migrations")?.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare("SELECT version FROM schema_migrations")?;
+    let versions = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(versions)
+}
+
+fn latest_applied(conn: &Connection) -> Result<Option<String>, NebulaKgError> {
+    let applied = applied_versions(conn)?;
+    Ok(MIGRATIONS
+        .iter()
+        .rev()
+        .find(|m| applied.iter().any(|v| v == m.version))
+        .map(|m| m.version.to_string()))
+}
+
+/// 1.0: Universal Schema tables, plus a one-way copy of legacy rows
+fn create_universal_schema(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    tx.execute_batch(UNIVERSAL_SCHEMA)?;
+    if !schema::table_columns(tx, "local_patterns")?.is_empty() {
+        import_legacy(tx)?;
+    }
+    Ok(())
+}
+
+/// 1.1: databases created by older tooling lack `project_info.schema_version`
+fn add_schema_version_column(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    if !schema::table_columns(tx, "project_info")?
+        .iter()
+        .any(|c| c == "schema_version")
+    {
+        tx.execute_batch("ALTER TABLE project_info ADD COLUMN schema_version TEXT DEFAULT '1.1';")?;
+    }
+    Ok(())
+}
+
+/// 1.2: `patterns.resolved_at`, set when an error stops occurring (e.g. its test passes)
+fn add_pattern_resolved_at(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    if !schema::table_columns(tx, "patterns")?
+        .iter()
+        .any(|c| c == "resolved_at")
+    {
+        tx.execute_batch("ALTER TABLE patterns ADD COLUMN resolved_at TEXT;")?;
+    }
+    Ok(())
+}
+
+/// 1.3: FTS5 index over pattern signatures, descriptions and solutions, kept
+/// in sync by triggers and filled from the existing rows
+fn create_pattern_fts(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    tx.execute_batch(&schema::pattern_fts_schema())?;
+    schema::rebuild_pattern_fts(tx)
+}
+
+/// 1.4: `sync_history`. What the legacy tooling already pushed is recorded
+/// against the imported pattern/solution so it isn't submitted again.
+fn create_sync_history(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    tx.execute_batch(SYNC_HISTORY_SCHEMA)?;
+    if schema::table_columns(tx, "local_patterns")?.is_empty() {
+        return Ok(());
+    }
+
+    let mut synced: Vec<(&str, String, Option<String>)> = Vec::new();
+    {
+        let mut stmt = tx.prepare(
+            "SELECT p.id, lp.central_pattern_id FROM local_patterns lp
+             JOIN patterns p ON p.signature = lp.error_signature
+             WHERE lp.synced_to_central",
+        )?;
+        let rows = stmt.query_map([], |row| Ok(("pattern", row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            synced.push(row?);
+        }
+    }
+    if !schema::table_columns(tx, "local_solutions")?.is_empty() {
+        let mut stmt = tx.prepare(
+            "SELECT e.id, ls.central_solution_id FROM local_solutions ls
+             JOIN events e ON e.type = 'solution'
+              AND json_extract(CASE WHEN json_valid(e.metadata) THEN e.metadata END,
+                               '$.legacy_id') = ls.id
+             WHERE ls.synced_to_central",
+        )?;
+        let rows = stmt.query_map([], |row| Ok(("solution", row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            synced.push(row?);
+        }
+    }
+
+    for (sync_type, local_id, central_id) in synced {
+        tx.execute(
+            "INSERT INTO sync_history (id, sync_type, local_id, central_id, sync_status)
+             VALUES (?1, ?2, ?3, ?4, 'success')",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                sync_type,
+                local_id,
+                central_id
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// 1.5: `sync_outbox`. Starts empty: unsynced items are queued on the next sync.
+fn create_sync_outbox(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    tx.execute_batch(SYNC_OUTBOX_SCHEMA)?;
+    Ok(())
+}
+
+struct LegacyPattern {
+    id: String,
+    signature: String,
+    category: Option<String>,
+    language: Option<String>,
+    description: Option<String>,
+    context_json: Option<String>,
+    severity: Option<String>,
+    occurrence_count: i64,
+    first_seen_at: Option<String>,
+    last_seen_at: Option<String>,
+}
+
+/// Convert each `local_patterns` row into a pattern plus one `error` event, and
+/// each `local_solutions` row into a `solution` event. Patterns whose signature
+/// already exists in `patterns` are skipped.
+fn import_legacy(tx: &Transaction<'_>) -> Result<(), NebulaKgError> {
+    let legacy: Vec<LegacyPattern> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, error_signature, error_category, language, description, context_json,
+                    severity, COALESCE(occurrence_count, 1), first_seen_at, last_seen_at
+             FROM local_patterns ORDER BY first_seen_at",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(LegacyPattern {
+                id: row.get(0)?,
+                signature: row.get(1)?,
+                category: row.get(2)?,
+                language: row.get(3)?,
+                description: row.get(4)?,
+                context_json: row.get(5)?,
+                severity: row.get(6)?,
+                occurrence_count: row.get(7)?,
+                first_seen_at: row.get(8)?,
+                last_seen_at: row.get(9)?,
+            })
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let has_technologies = !schema::table_columns(tx, "local_pattern_technologies")?.is_empty();
+    let has_solutions = !schema::table_columns(tx, "local_solutions")?.is_empty();
+
+    for pattern in legacy {
+        let exists = tx
+            .query_row(
+                "SELECT 1 FROM patterns WHERE signature = ?1",
+                params![pattern.signature],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if exists {
+            continue;
+        }
+
+        let technologies: Vec<String> = if has_technologies {
+            let mut stmt = tx.prepare(
+                "SELECT technology_slug FROM local_pattern_technologies WHERE pattern_id = ?1",
+            )?;
+            let slugs = stmt
+                .query_map(params![pattern.id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            slugs
+        } else {
+            Vec::new()
+        };
+        // Legacy rows use free-form strings; store them in their schema spelling
+        let category = pattern.category.as_deref().map(Category::parse_lenient);
+        let severity = pattern
+            .severity
+            .as_deref()
+            .map(Severity::parse_lenient)
+            .unwrap_or_default();
+        // context_json is free-form in the legacy schema; keep it as text if it isn't JSON
+        let context = pattern
+            .context_json
+            .as_deref()
+            .map(|raw| serde_json::from_str(raw).unwrap_or_else(|_| json!(raw)))
+            .unwrap_or(Value::Null);
+        let meta = json!({
+            "severity": severity,
+            "category": category,
+            "language": pattern.language,
+            "context": context,
+            "signature": pattern.signature,
+            "technologies": technologies,
+            "legacy_id": pattern.id,
+        });
+        let content = pattern.description.clone().unwrap_or_else(|| {
+            format!(
+                "Error: {}",
+                category.as_ref().map_or("UNKNOWN", Category::as_str)
+            )
+        });
+        tx.execute(
+            "INSERT INTO events (id, type, phase, content, metadata, created_at)
+             VALUES (?1, 'error', 'UNKNOWN', ?2, ?3, COALESCE(?4, CURRENT_TIMESTAMP))",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                content,
+                meta.to_string(),
+                pattern.first_seen_at
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO patterns (id, signature, category, description, occurrence_count, last_seen_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, CURRENT_TIMESTAMP))",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                pattern.signature,
+                category.as_ref().map(Category::as_str),
+                pattern.description,
+                pattern.occurrence_count,
+                pattern.last_seen_at
+            ],
+        )?;
+
+        if has_solutions {
+            import_legacy_solutions(tx, &pattern)?;
+        }
+    }
+    Ok(())
+}
+
+fn import_legacy_solutions(
+    tx: &Transaction<'_>,
+    pattern: &LegacyPattern,
+) -> Result<(), NebulaKgError> {
+    let mut stmt = tx.prepare(
+        "SELECT id, title, description, code_snippet, resolution_steps,
+                time_to_resolve_minutes, COALESCE(was_successful, 1), created_at
+         FROM local_solutions WHERE pattern_id = ?1 ORDER BY created_at",
+    )?;
+    let mut rows = stmt.query(params![pattern.id])?;
+    while let Some(row) = rows.next()? {
+        let legacy_id: String = row.get(0)?;
+        let description: String = row.get(2)?;
+        let was_successful: bool = row.get(6)?;
+        let created_at: Option<String> = row.get(7)?;
+        // The legacy schema only knows success/failure; map onto the 1-5 scale
+        let effectiveness = if was_successful { 4 } else { 1 };
+        let meta = json!({
+            "effectiveness": effectiveness,
+            "target_signature": pattern.signature,
+            "title": row.get::<_, String>(1)?,
+            "code_snippet": row.get::<_, Option<String>>(3)?,
+            "resolution_steps": row.get::<_, Option<String>>(4)?,
+            "time_to_resolve_minutes": row.get::<_, Option<i64>>(5)?,
+            "was_successful": was_successful,
+            "legacy_id": legacy_id,
+        });
+        tx.execute(
+            "INSERT INTO events (id, type, phase, content, metadata, created_at)
+             VALUES (?1, 'solution', 'UNKNOWN', ?2, ?3, COALESCE(?4, CURRENT_TIMESTAMP))",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                description,
+                meta.to_string(),
+                created_at
+            ],
+        )?;
+        if was_successful {
+            tx.execute(
+                "UPDATE patterns SET solution = ?1 WHERE signature = ?2",
+                params![description, pattern.signature],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend_tests::TempDb;
+    use super::super::{KgBackend, SqliteBackend, SyncStatus, SyncType};
+    use super::*;
+
+    /// The tables of `local_kg/schema.sql` the 1.0 import reads
+    const LEGACY_SCHEMA: &str = "
+        CREATE TABLE local_patterns (
+            id TEXT PRIMARY KEY,
+            error_signature TEXT NOT NULL,
+            error_category TEXT,
+            language TEXT,
+            description TEXT,
+            context_json TEXT,
+            occurrence_count INTEGER DEFAULT 1,
+            first_seen_at TEXT DEFAULT (datetime('now')),
+            last_seen_at TEXT DEFAULT (datetime('now')),
+            synced_to_central BOOLEAN DEFAULT 0,
+            central_pattern_id TEXT,
+            severity TEXT DEFAULT 'medium'
+        );
+        CREATE TABLE local_solutions (
+            id TEXT PRIMARY KEY,
+            pattern_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            code_snippet TEXT,
+            resolution_steps TEXT,
+            time_to_resolve_minutes INTEGER,
+            was_successful BOOLEAN DEFAULT 1,
+            created_at TEXT DEFAULT (datetime('now')),
+            synced_to_central BOOLEAN DEFAULT 0,
+            central_solution_id TEXT
+        );
+        CREATE TABLE local_pattern_technologies (
+            pattern_id TEXT NOT NULL,
+            technology_slug TEXT NOT NULL,
+            PRIMARY KEY (pattern_id, technology_slug)
+        );
+        CREATE TABLE sync_history (
+            id TEXT PRIMARY KEY,
+            sync_type TEXT NOT NULL,
+            local_id TEXT NOT NULL,
+            central_id TEXT,
+            synced_at TEXT DEFAULT (datetime('now')),
+            sync_status TEXT DEFAULT 'success',
+            error_message TEXT
+        );
+
+        INSERT INTO local_patterns (id, error_signature, error_category, language, description,
+                                    context_json, occurrence_count, first_seen_at, last_seen_at,
+                                    synced_to_central, central_pattern_id, severity)
+        VALUES ('lp-1', 'E0382: borrow of moved value', 'borrowck', 'rust', 'moved into a closure',
+                '{\"file\": \"src/main.rs\"}', 3, '2024-01-01 10:00:00', '2024-01-03 10:00:00',
+                1, 'central-7', 'HIGH'),
+               ('lp-2', 'ModuleNotFoundError', 'imports', 'python', NULL,
+                'not json', 1, '2024-01-02 10:00:00', '2024-01-02 10:00:00', 0, NULL, 'urgent');
+        INSERT INTO local_pattern_technologies VALUES ('lp-1', 'tokio'), ('lp-1', 'serde');
+        INSERT INTO local_solutions (id, pattern_id, title, description, code_snippet,
+                                     resolution_steps, time_to_resolve_minutes, was_successful,
+                                     created_at, synced_to_central, central_solution_id)
+        VALUES ('ls-1', 'lp-1', 'Clone first', 'clone before moving', 'let v2 = v.clone();',
+                '1. clone\n2. move the clone', 5, 1, '2024-01-03 11:00:00', 1, 'central-s1'),
+               ('ls-2', 'lp-1', 'Rc', 'wrap it in an Rc', NULL, NULL, NULL, 0,
+                '2024-01-03 12:00:00', 0, NULL);
+    ";
+
+    fn legacy_db() -> TempDb {
+        let db = TempDb::new("legacy");
+        Connection::open(db.path())
+            .unwrap()
+            .execute_batch(LEGACY_SCHEMA)
+            .unwrap();
+        db
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn migrations_are_ordered() {
+        let versions: Vec<&str> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort_by_key(|v| {
+            v.split('.')
+                .map(|part| part.parse::<u32>().unwrap())
+                .collect::<Vec<_>>()
+        });
+        sorted.dedup();
+        assert_eq!(versions, sorted);
+        assert_eq!(versions.last(), Some(&SCHEMA_VERSION));
+
+        // Applied and recorded in that order
+        let db = TempDb::new("fresh");
+        assert_eq!(migrate_db(db.path()).unwrap(), versions);
+        let conn = Connection::open(db.path()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT version FROM schema_migrations ORDER BY rowid")
+            .unwrap();
+        let recorded: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(recorded, versions);
+        assert_eq!(
+            detect_schema_version(&conn).unwrap().as_deref(),
+            Some(SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn imports_legacy_patterns_and_solutions() {
+        let db = legacy_db();
+        {
+            let conn = Connection::open(db.path()).unwrap();
+            assert_eq!(
+                detect_schema_version(&conn).unwrap().as_deref(),
+                Some(LEGACY_SCHEMA_VERSION)
+            );
+        }
+        assert_eq!(migrate_db(db.path()).unwrap().len(), MIGRATIONS.len());
+
+        let kg = SqliteBackend::open(db.path()).unwrap();
+        let patterns = kg.search_patterns("", 10).unwrap();
+        assert_eq!(patterns.len(), 2);
+
+        let moved = &patterns[0];
+        assert_eq!(moved.signature, "E0382: borrow of moved value");
+        assert_eq!(moved.category, Some(Category::BorrowCheck));
+        assert_eq!(moved.description.as_deref(), Some("moved into a closure"));
+        assert_eq!(moved.occurrence_count, 3);
+        // Only the successful solution is promoted
+        assert_eq!(moved.solution.as_deref(), Some("clone before moving"));
+        let titles: Vec<&str> = moved.solutions.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Clone first", "Rc"]);
+        let clone = &moved.solutions[0];
+        assert_eq!(clone.effectiveness, 4);
+        assert_eq!(clone.code_snippet.as_deref(), Some("let v2 = v.clone();"));
+        assert_eq!(
+            clone.resolution_steps,
+            vec!["1. clone", "2. move the clone"]
+        );
+        assert_eq!(clone.time_to_resolve_minutes, Some(5));
+        assert_eq!(moved.solutions[1].effectiveness, 1);
+        assert!(!moved.solutions[1].was_successful);
+
+        let missing = &patterns[1];
+        assert_eq!(missing.signature, "ModuleNotFoundError");
+        assert_eq!(
+            missing.category,
+            Some(Category::Custom("imports".to_string()))
+        );
+        assert_eq!(missing.solution, None);
+
+        let events = kg.recent_events(10).unwrap();
+        let errors: Vec<_> = events.iter().filter(|e| e.event_type == "error").collect();
+        assert_eq!(errors.len(), 2);
+        let moved_event = errors
+            .iter()
+            .find(|e| e.metadata["legacy_id"] == "lp-1")
+            .unwrap();
+        assert_eq!(moved_event.severity(), Some(Severity::High));
+        assert_eq!(moved_event.metadata["context"]["file"], "src/main.rs");
+        let mut technologies: Vec<&str> = moved_event.metadata["technologies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        technologies.sort_unstable();
+        assert_eq!(technologies, vec!["serde", "tokio"]);
+        assert_eq!(moved_event.created_at, "2024-01-01 10:00:00");
+        let missing_event = errors
+            .iter()
+            .find(|e| e.metadata["legacy_id"] == "lp-2")
+            .unwrap();
+        // Unknown severities read as medium, non-JSON context is kept as text
+        assert_eq!(missing_event.severity(), Some(Severity::Medium));
+        assert_eq!(missing_event.metadata["context"], "not json");
+        assert_eq!(missing_event.content.as_deref(), Some("Error: imports"));
+
+        // What the legacy tooling pushed isn't pushed again
+        let synced: Vec<_> = kg
+            .sync_history(10)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.sync_type, r.local_id, r.central_id, r.status))
+            .collect();
+        assert_eq!(synced.len(), 2);
+        for expected in [
+            (
+                SyncType::Pattern,
+                moved.id.clone(),
+                Some("central-7".to_string()),
+                SyncStatus::Success,
+            ),
+            (
+                SyncType::Solution,
+                clone.id.clone(),
+                Some("central-s1".to_string()),
+                SyncStatus::Success,
+            ),
+        ] {
+            assert!(
+                synced.contains(&expected),
+                "{:?} not in {:?}",
+                expected,
+                synced
+            );
+        }
+        assert!(kg
+            .unsynced_patterns(10)
+            .unwrap()
+            .iter()
+            .all(|p| p.id != moved.id));
+
+        // The legacy tables are left in place
+        let conn = Connection::open(db.path()).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM local_patterns"), 2);
+    }
+
+    #[test]
+    fn rerunning_is_a_no_op() {
+        let db = legacy_db();
+        migrate_db(db.path()).unwrap();
+        assert!(migrate_db(db.path()).unwrap().is_empty());
+
+        let mut conn = Connection::open(db.path()).unwrap();
+        assert!(pending_migrations(&conn).unwrap().is_empty());
+        assert!(run_migrations(&mut conn).unwrap().is_empty());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM patterns"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM events"), 4);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sync_history"), 2);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM schema_migrations") as usize,
+            MIGRATIONS.len()
+        );
+    }
+
+    #[test]
+    fn applies_only_pending_migrations() {
+        let db = TempDb::new("partial");
+        let mut conn = Connection::open(db.path()).unwrap();
+        conn.execute_batch(MIGRATIONS_TABLE).unwrap();
+        for migration in &MIGRATIONS[..2] {
+            let tx = conn.transaction().unwrap();
+            (migration.apply)(&tx).unwrap();
+            tx.execute(
+                "INSERT INTO schema_migrations (version, description) VALUES (?1, ?2)",
+                params![migration.version, migration.description],
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let pending: Vec<&str> = pending_migrations(&conn)
+            .unwrap()
+            .iter()
+            .map(|m| m.version)
+            .collect();
+        let expected: Vec<&str> = MIGRATIONS[2..].iter().map(|m| m.version).collect();
+        assert_eq!(pending, expected);
+        assert_eq!(run_migrations(&mut conn).unwrap(), expected);
+        assert_eq!(
+            detect_schema_version(&conn).unwrap().as_deref(),
+            Some(SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn rejects_an_unknown_recorded_version() {
+        let db = TempDb::new("future");
+        migrate_db(db.path()).unwrap();
+        let mut conn = Connection::open(db.path()).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, description) VALUES ('9.9', 'from the future')",
+            [],
+        )
+        .unwrap();
+        assert!(matches!(
+            run_migrations(&mut conn),
+            Err(NebulaKgError::SchemaMismatch { found, .. }) if found == "9.9"
+        ));
+
+        // A version recorded only in project_info counts too
+        let db = TempDb::new("future-info");
+        migrate_db(db.path()).unwrap();
+        let mut conn = Connection::open(db.path()).unwrap();
+        conn.execute_batch(
+            "DELETE FROM schema_migrations;
+             INSERT INTO project_info (project_id, schema_version) VALUES ('p', '2.0');",
+        )
+        .unwrap();
+        assert!(matches!(
+            run_migrations(&mut conn),
+            Err(NebulaKgError::SchemaMismatch { found, .. }) if found == "2.0"
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+//! Panic hook that records panics in the Local KG.
+//!
+//! Every panic becomes an `error` event (category `Panic`, severity `critical`)
+//! with the message, source location, thread name and optionally a backtrace.
+//! The capture is written before the hook returns, so it survives
+//! `panic = "abort"` and panics on the main thread. The previously installed
+//! hook (usually the default stderr printer) still runs afterwards.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::Cell;
+use std::panic::{self, PanicHookInfo};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::backend::KgBackend;
+use super::signature::normalize_signature;
+use super::{Category, ErrorCapture, LocalKGBridge, Severity};
+
+/// How long the hook waits for the capture to be written
+pub const DEFAULT_PANIC_CAPTURE_TIMEOUT: Duration = Duration::from_secs(2);
+
+thread_local! {
+    /// Set on the capture thread so a panic inside the backend isn't captured again
+    static IN_CAPTURE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Configures and installs the panic hook.
+///
+/// ```ignore
+/// let kg = get_local_kg(None)?;
+/// PanicHook::new(&kg).with_backtrace(true).install();
+/// ```
+pub struct PanicHook {
+    backend: Arc<dyn KgBackend>,
+    force_backtrace: bool,
+    timeout: Duration,
+}
+
+impl PanicHook {
+    pub fn new(bridge: &LocalKGBridge) -> Self {
+        Self {
+            backend: bridge.backend.clone(),
+            force_backtrace: false,
+            timeout: DEFAULT_PANIC_CAPTURE_TIMEOUT,
+        }
+    }
+
+    /// Always capture a backtrace. By default one is only recorded when
+    /// `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE` enable it.
+    pub fn with_backtrace(mut self, force: bool) -> Self {
+        self.force_backtrace = force;
+        self
+    }
+
+    /// Upper bound on how long a panic waits for the capture to be written
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Install the hook, chaining to whatever hook was installed before
+    pub fn install(self) {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !IN_CAPTURE.with(Cell::get) {
+                self.capture(info);
+            }
+            previous(info);
+        }));
+    }
+
+    /// Write the panic to the backend and wait (bounded) for it to finish.
+    ///
+    /// The write happens on a helper thread: if the panicking thread holds a
+    /// backend lock, writing inline would deadlock, whereas here we just give
+    /// up after `timeout`.
+    fn capture(&self, info: &PanicHookInfo<'_>) {
+        let message = panic_message(info);
+        let location = info.location();
+        let thread_name = thread::current().name().unwrap_or("<unnamed>").to_string();
+
+        let backtrace = if self.force_backtrace {
+            Some(Backtrace::force_capture())
+        } else {
+            Some(Backtrace::capture()).filter(|bt| bt.status() == BacktraceStatus::Captured)
+        };
+
+        // Keep the file (panics in different files are different bugs) but drop
+        // indices, addresses and quoted values from the message
+        let normalized = normalize_signature(&message);
+        let signature = match location {
+            Some(loc) => format!("panicked at {}: {}", loc.file(), normalized.canonical),
+            None => format!("panicked: {}", normalized.canonical),
+        };
+        let capture = ErrorCapture {
+            file_path: location.map(|l| l.file().to_string()),
+            line_number: location.map(|l| l.line()),
+            stack_trace: backtrace.map(|bt| bt.to_string()),
+            ..ErrorCapture::new(signature, Category::Panic)
+        }
+        .with_severity(Severity::Critical)
+        .with_description(message)
+        .with_context(json!({
+            "column": location.map(|l| l.column()),
+            "thread": thread_name,
+            "fingerprint": normalized.fingerprint,
+        }));
+
+        let backend = self.backend.clone();
+        let (done_tx, done_rx) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("local-kg-panic-capture".to_string())
+            .spawn(move || {
+                IN_CAPTURE.with(|flag| flag.set(true));
+                let _ = done_tx.send(backend.capture(&capture));
+            });
+        if spawned.is_err() {
+            eprintln!("Local KG: could not record panic (thread spawn failed)");
+            return;
+        }
+
+        match done_rx.recv_timeout(self.timeout) {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => eprintln!("Local KG: failed to record panic: {}", e),
+            Err(_) => eprintln!("Local KG: gave up recording panic after {:?}", self.timeout),
+        }
+    }
+}
+
+/// Install the panic hook with default settings
+pub fn install_panic_hook(bridge: &LocalKGBridge) {
+    PanicHook::new(bridge).install();
+}
+
+/// The `&str`/`String` payload of a panic, or a placeholder for other payloads
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::super::memory::MemoryBackend;
+    use super::*;
+
+    #[test]
+    fn records_panics_and_chains_to_the_previous_hook() {
+        let backend = Arc::new(MemoryBackend::new());
+        let kg = LocalKGBridge::with_backend(backend.clone()).unwrap();
+
+        // Stands in for the default printer; only counts panics from our thread
+        // since the hook is process-wide
+        let original = panic::take_hook();
+        let previous_calls = Arc::new(AtomicUsize::new(0));
+        let calls = previous_calls.clone();
+        panic::set_hook(Box::new(move |_| {
+            if thread::current().name() == Some("panic-hook-test") {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+        PanicHook::new(&kg).install();
+
+        let caught = thread::Builder::new()
+            .name("panic-hook-test".to_string())
+            .spawn(|| panic::catch_unwind(|| panic!("index 7 out of range for slice")).is_err())
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let _ = panic::take_hook();
+        panic::set_hook(original);
+
+        assert!(caught);
+        assert_eq!(previous_calls.load(Ordering::SeqCst), 1);
+
+        let events = backend.recent_events(50).unwrap();
+        let event = events
+            .iter()
+            .find(|e| e.metadata["context"]["thread"] == "panic-hook-test")
+            .expect("panic was not recorded");
+        assert_eq!(event.event_type, "error");
+        assert_eq!(event.severity(), Some(Severity::Critical));
+        assert_eq!(event.category(), Some(Category::Panic));
+        assert_eq!(
+            event.content.as_deref(),
+            Some("index 7 out of range for slice")
+        );
+        assert_eq!(event.metadata["file_path"], file!());
+        assert!(event.metadata["line_number"].as_u64().unwrap() > 0);
+        assert!(event.metadata["context"]["column"].as_u64().is_some());
+        let signature = event.metadata["signature"].as_str().unwrap();
+        assert!(signature.starts_with(&format!("panicked at {}: ", file!())));
+        assert!(!signature.contains('7'));
+    }
+}
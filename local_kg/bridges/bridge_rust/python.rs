@@ -0,0 +1,78 @@
+//! Python subprocess backend.
+//!
+//! Starts `python -m local_kg.kg_worker` for every call and hands it a single
+//! JSON-RPC request on stdin. Kept for parity testing against the reference
+//! implementation; the native SQLite backend is preferred everywhere else.
+//!
+//! Error text is only ever passed as JSON data, never interpolated into
+//! Python source, so hostile input (quotes, `\r`, NUL, U+2028, ...) cannot
+//! change what the interpreter executes.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+use super::rpc::{RpcCall, RpcRequest, RpcResponse};
+use super::NebulaKgError;
+
+/// Backend that shells out to the Python Local KG
+pub struct PythonBackend {
+    db_path: String,
+    python_cmd: String,
+}
+
+impl PythonBackend {
+    pub fn new(db_path: &str, python_cmd: &str) -> Self {
+        Self {
+            db_path: db_path.to_string(),
+            python_cmd: python_cmd.to_string(),
+        }
+    }
+}
+
+impl RpcCall for PythonBackend {
+    /// Run one request through a fresh worker process
+    fn request(&self, method: &str, params: Value) -> Result<RpcResponse, NebulaKgError> {
+        let request = serde_json::to_string(&RpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method,
+            params,
+        })?;
+
+        let mut child = Command::new(&self.python_cmd)
+            .args(["-m", "local_kg.kg_worker", "--db", &self.db_path])
+            .current_dir(".")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Closing stdin after the request makes the worker exit once it has answered
+        {
+            let mut stdin = child.stdin.take().ok_or_else(|| {
+                NebulaKgError::BackendUnavailable("Python process has no stdin".to_string())
+            })?;
+            writeln!(stdin, "{}", request)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(NebulaKgError::PythonProcess {
+                exit_code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .ok_or_else(|| NebulaKgError::PythonProcess {
+                exit_code: output.status.code(),
+                stderr: "Python process returned no response".to_string(),
+            })?;
+        Ok(serde_json::from_str(line)?)
+    }
+}
@@ -0,0 +1,105 @@
+//! Resolving individual error occurrences.
+//!
+//! `capture` returns the id of the `error` event it wrote. Passing that id to
+//! `resolve_error` together with the `Fix` records a `resolution` event that
+//! links the occurrence to the solution, with the minutes between capture and
+//! fix. An occurrence without such an event is open. Once none of a
+//! pattern's occurrences are open the pattern is marked resolved, as
+//! `resolve_pattern` would.
+//!
+//! ```ignore
+//! let event_id = kg.capture(capture).await?;
+//! // ... later, once it's fixed
+//! let resolution = kg
+//!     .resolve_error(&event_id, Solution::new("Clone before moving", "...").into())
+//!     .await?;
+//! println!("fixed in {} min", resolution.time_to_resolve_minutes);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use super::{KgEvent, NebulaKgError, Solution};
+
+/// What fixed an error occurrence
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    /// A new solution, recorded for the error's pattern. Its time to resolve
+    /// is filled in if unset.
+    New(Solution),
+    /// A solution recorded earlier, by event id. Counts as helpful feedback
+    /// for that solution, which must belong to the error's pattern.
+    Existing(String),
+}
+
+impl Fix {
+    pub fn existing(solution_id: impl Into<String>) -> Self {
+        Fix::Existing(solution_id.into())
+    }
+}
+
+impl From<Solution> for Fix {
+    fn from(solution: Solution) -> Self {
+        Fix::New(solution)
+    }
+}
+
+/// An error occurrence linked to its fix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResolution {
+    /// Id of the `resolution` event
+    pub event_id: String,
+    pub error_event_id: String,
+    pub signature: String,
+    pub solution_id: String,
+    /// Whole minutes from capture to resolution
+    pub time_to_resolve_minutes: u32,
+    pub resolved_at: String,
+}
+
+impl ErrorResolution {
+    /// Read a `resolution` event written by `resolve_error` (`None` for
+    /// pattern-level resolutions)
+    pub(super) fn from_event(event: &KgEvent) -> Option<Self> {
+        let meta = &event.metadata;
+        Some(Self {
+            event_id: event.id.clone(),
+            error_event_id: meta["error_event_id"].as_str()?.to_string(),
+            signature: meta["target_signature"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            solution_id: meta["solution_id"].as_str().unwrap_or_default().to_string(),
+            time_to_resolve_minutes: meta["time_to_resolve_minutes"]
+                .as_u64()
+                .map_or(0, |m| u32::try_from(m).unwrap_or(u32::MAX)),
+            resolved_at: event.created_at.clone(),
+        })
+    }
+}
+
+/// `InvalidValue` unless the existing solution `solution_id` (recorded for
+/// `solution_signature`) fixes errors with `signature`
+pub(super) fn check_same_pattern(
+    solution_id: &str,
+    solution_signature: &str,
+    signature: &str,
+) -> Result<(), NebulaKgError> {
+    if solution_signature != signature {
+        return Err(NebulaKgError::InvalidValue(format!(
+            "solution {} is for '{}', not '{}'",
+            solution_id, solution_signature, signature
+        )));
+    }
+    Ok(())
+}
+
+/// Open vs resolved error occurrences
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolutionStats {
+    /// `error` events without a resolution
+    pub open_errors: i64,
+    /// `error` events linked to a fix by `resolve_error`
+    pub resolved_errors: i64,
+    /// Mean time from capture to fix (`None` until something is resolved)
+    pub mean_time_to_fix_minutes: Option<f64>,
+}
@@ -0,0 +1,279 @@
+//! JSON-RPC plumbing shared by the Python backends.
+//!
+//! `PythonBackend` (one process per call) and `PythonWorkerBackend` (a
+//! long-lived process) differ only in how a request reaches
+//! `python -m local_kg.kg_worker`. Both implement `RpcCall`, and every
+//! `RpcCall` is a `KgBackend` through the impl below, so the method-to-RPC
+//! mapping exists once.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::backend::KgBackend;
+use super::search::{COLUMN_WEIGHTS, SNIPPET_TOKENS};
+use super::{
+    ErrorCapture, ErrorPattern, ErrorResolution, Fix, KgEvent, NebulaKgError, OutboxItem,
+    OutboxStatus, OutboxUpdate, PatternSearch, PatternSummary, ResolutionStats, SearchHit,
+    Solution, SolutionFeedback, SyncRecord, SyncType, UnsyncedSolution,
+};
+
+/// JSON-RPC error code the worker uses for missing patterns/events
+const NOT_FOUND: i64 = -32004;
+
+/// JSON-RPC error code the worker uses for arguments that don't fit the call
+const INVALID_VALUE: i64 = -32005;
+
+#[derive(Serialize)]
+pub(super) struct RpcRequest<'a> {
+    pub jsonrpc: &'static str,
+    pub id: u64,
+    pub method: &'a str,
+    pub params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct RpcResponse {
+    pub id: Option<u64>,
+    pub result: Option<Value>,
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    /// Turn a response into the decoded result or the worker's error
+    pub(super) fn into_result<T: DeserializeOwned>(self) -> Result<T, NebulaKgError> {
+        if let Some(error) = self.error {
+            return Err(match error.code {
+                NOT_FOUND => NebulaKgError::NotFound(error.message),
+                INVALID_VALUE => NebulaKgError::InvalidValue(error.message),
+                code => NebulaKgError::Worker {
+                    code,
+                    message: error.message,
+                },
+            });
+        }
+        Ok(serde_json::from_value(self.result.unwrap_or(Value::Null))?)
+    }
+}
+
+/// A way of getting one request answered by `local_kg.kg_worker`
+pub(super) trait RpcCall: Send + Sync {
+    /// Send `method` with `params` and return the worker's response
+    fn request(&self, method: &str, params: Value) -> Result<RpcResponse, NebulaKgError>;
+
+    /// Send one request and decode its result
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, NebulaKgError> {
+        self.request(method, params)?.into_result()
+    }
+}
+
+impl<C: RpcCall> KgBackend for C {
+    fn capture(&self, capture: &ErrorCapture) -> Result<String, NebulaKgError> {
+        self.call(
+            "capture_error",
+            json!({
+                "signature": capture.signature,
+                "category": capture.category,
+                "language": capture.language,
+                "severity": capture.severity,
+                "description": capture.description,
+                "context": capture.context,
+                "level": capture.level,
+                "phase": capture.phase,
+                "constellation": capture.constellation,
+                "file_path": capture.file_path,
+                "line_number": capture.line_number,
+                "error_code": capture.error_code,
+                "stack_trace": capture.stack_trace,
+                "technologies": capture.technologies,
+            }),
+        )
+    }
+
+    fn search_patterns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        self.call("search_patterns", json!({ "query": query, "limit": limit }))
+    }
+
+    fn search(&self, search: &PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError> {
+        let Some(expression) = search.match_expression() else {
+            return Ok(Vec::new());
+        };
+        self.call(
+            "search",
+            json!({
+                "match": expression,
+                "limit": search.limit,
+                "category": search.category,
+                "include_resolved": search.include_resolved,
+                "highlight_open": search.highlight.0,
+                "highlight_close": search.highlight.1,
+                "column_weights": COLUMN_WEIGHTS,
+                "snippet_tokens": SNIPPET_TOKENS,
+            }),
+        )
+    }
+
+    fn record_solution(
+        &self,
+        pattern_id: &str,
+        solution: &Solution,
+    ) -> Result<String, NebulaKgError> {
+        self.call(
+            "add_solution",
+            json!({
+                "pattern_id": pattern_id,
+                "solution_text": solution.description,
+                "effectiveness": solution.effectiveness,
+                "title": solution.title,
+                "code_snippet": solution.code_snippet,
+                "resolution_steps": solution.resolution_steps,
+                "time_to_resolve_minutes": solution.time_to_resolve_minutes,
+                "was_successful": solution.was_successful,
+            }),
+        )
+    }
+
+    fn record_feedback(
+        &self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError> {
+        self.call(
+            "record_feedback",
+            json!({
+                "solution_id": solution_id,
+                "was_helpful": feedback.was_helpful,
+                "resolution_time_minutes": feedback.resolution_time_minutes,
+                "comment": feedback.comment,
+            }),
+        )
+    }
+
+    fn resolve_error(
+        &self,
+        error_event_id: &str,
+        fix: &Fix,
+    ) -> Result<ErrorResolution, NebulaKgError> {
+        let (solution, solution_id) = match fix {
+            Fix::New(solution) => (Some(solution), None),
+            Fix::Existing(solution_id) => (None, Some(solution_id)),
+        };
+        self.call(
+            "resolve_error",
+            json!({
+                "error_event_id": error_event_id,
+                "solution": solution,
+                "solution_id": solution_id,
+            }),
+        )
+    }
+
+    fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.call("open_errors", json!({ "limit": limit }))
+    }
+
+    fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError> {
+        self.call("resolution_stats", json!({}))
+    }
+
+    fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: &Value,
+    ) -> Result<Option<String>, NebulaKgError> {
+        self.call(
+            "resolve_pattern",
+            json!({
+                "signature": signature,
+                "content": content,
+                "context": context,
+            }),
+        )
+    }
+
+    fn get_summary(&self) -> Result<PatternSummary, NebulaKgError> {
+        self.call("get_summary", json!({}))
+    }
+
+    fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: &Value,
+    ) -> Result<String, NebulaKgError> {
+        self.call(
+            "record_event",
+            json!({
+                "event_type": event_type,
+                "phase": phase,
+                "content": content,
+                "metadata": metadata,
+            }),
+        )
+    }
+
+    fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.call("recent_events", json!({ "limit": limit }))
+    }
+
+    fn unsynced_patterns(&self, limit: usize) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        self.call("unsynced_patterns", json!({ "limit": limit }))
+    }
+
+    fn unsynced_solutions(&self, limit: usize) -> Result<Vec<UnsyncedSolution>, NebulaKgError> {
+        self.call("unsynced_solutions", json!({ "limit": limit }))
+    }
+
+    fn record_sync(&self, records: &[SyncRecord]) -> Result<(), NebulaKgError> {
+        self.call::<Value>("record_sync", json!({ "records": records }))
+            .map(|_| ())
+    }
+
+    fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError> {
+        self.call("sync_history", json!({ "limit": limit }))
+    }
+
+    fn enqueue_sync(&self, items: &[OutboxItem]) -> Result<usize, NebulaKgError> {
+        self.call("enqueue_sync", json!({ "items": items }))
+    }
+
+    fn due_sync_items(
+        &self,
+        sync_type: SyncType,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        self.call(
+            "due_sync_items",
+            json!({ "sync_type": sync_type, "limit": limit }),
+        )
+    }
+
+    fn update_sync_items(&self, updates: &[OutboxUpdate]) -> Result<(), NebulaKgError> {
+        self.call::<Value>("update_sync_items", json!({ "updates": updates }))
+            .map(|_| ())
+    }
+
+    fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        self.call("sync_outbox", json!({ "status": status, "limit": limit }))
+    }
+
+    fn schema_version(&self) -> Result<Option<String>, NebulaKgError> {
+        self.call("schema_version", json!({}))
+    }
+}
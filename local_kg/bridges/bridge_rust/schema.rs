@@ -0,0 +1,297 @@
+//! Universal Schema definition and version checks.
+//!
+//! The bridge only understands the Universal Schema (`events`, `patterns`,
+//! `project_info`). Opening anything else fails with
+//! `NebulaKgError::SchemaMismatch` rather than a confusing decode error later.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::NebulaKgError;
+
+/// Universal Schema version this bridge reads and writes
+pub const SCHEMA_VERSION: &str = "1.5";
+
+/// Tables created when the bridge opens a fresh database.
+/// Mirrors the Universal Schema shared with the Python and Node tooling.
+pub(super) const UNIVERSAL_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS project_info (
+    project_id TEXT PRIMARY KEY,
+    name TEXT,
+    framework TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    current_version TEXT DEFAULT '0.0.0.0',
+    current_phase TEXT,
+    current_constellation TEXT,
+    context_window_summary TEXT,
+    schema_version TEXT DEFAULT '1.5',
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    type TEXT NOT NULL,
+    phase TEXT,
+    content TEXT,
+    metadata TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS patterns (
+    id TEXT PRIMARY KEY,
+    signature TEXT NOT NULL,
+    category TEXT,
+    description TEXT,
+    solution TEXT,
+    occurrence_count INTEGER DEFAULT 1,
+    last_seen_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_type ON events(type);
+CREATE INDEX IF NOT EXISTS idx_patterns_signature ON patterns(signature);
+"#;
+
+/// One row per push of a pattern or solution to the Central KG. Same shape as
+/// the legacy `local_kg/schema.sql` table, which is reused if present.
+pub(super) const SYNC_HISTORY_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_history (
+    id TEXT PRIMARY KEY,
+    sync_type TEXT NOT NULL,
+    local_id TEXT NOT NULL,
+    central_id TEXT,
+    synced_at TEXT DEFAULT (datetime('now')),
+    sync_status TEXT DEFAULT 'success',
+    error_message TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_history_local ON sync_history(local_id);
+"#;
+
+/// Patterns and solutions queued for the Central KG, one row per item. `status`
+/// is `pending`, `synced` or `failed`; `payload` is the JSON request body.
+pub(super) const SYNC_OUTBOX_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_outbox (
+    id TEXT PRIMARY KEY,
+    sync_type TEXT NOT NULL,
+    local_id TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL UNIQUE,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT (datetime('now')),
+    last_error TEXT,
+    central_id TEXT,
+    created_at TEXT DEFAULT (datetime('now')),
+    UNIQUE (sync_type, local_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_outbox_due ON sync_outbox(status, next_attempt_at);
+"#;
+
+/// FTS5 index over pattern text. `solution` holds the promoted solution plus
+/// every `solution` event targeting the pattern's signature.
+const PATTERN_FTS_TABLE: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS patterns_fts USING fts5(
+    pattern_id UNINDEXED,
+    signature,
+    description,
+    solution,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+"#;
+
+/// `INSERT ... SELECT` producing the index rows for patterns matching `filter`
+fn pattern_fts_rows(filter: &str) -> String {
+    format!(
+        "INSERT INTO patterns_fts (pattern_id, signature, description, solution)
+    SELECT p.id, p.signature, p.description,
+           TRIM(COALESCE(p.solution, '') || ' ' || COALESCE((
+               SELECT group_concat(e.content, ' ') FROM events e
+               WHERE e.type = 'solution'
+                 AND json_extract(CASE WHEN json_valid(e.metadata) THEN e.metadata END,
+                                  '$.target_signature') = p.signature
+                 AND e.content IS NOT p.solution
+           ), ''))
+    FROM patterns p WHERE {};",
+        filter
+    )
+}
+
+/// Index table plus the triggers that keep it in sync with `patterns` and
+/// `solution` events (writes from the Python tooling included)
+pub(super) fn pattern_fts_schema() -> String {
+    let solution_target =
+        "json_extract(CASE WHEN json_valid(NEW.metadata) THEN NEW.metadata END, '$.target_signature')";
+    format!(
+        "{table}
+CREATE TRIGGER IF NOT EXISTS patterns_fts_insert AFTER INSERT ON patterns BEGIN
+    {insert_new}
+END;
+CREATE TRIGGER IF NOT EXISTS patterns_fts_update
+AFTER UPDATE OF signature, description, solution ON patterns BEGIN
+    DELETE FROM patterns_fts WHERE pattern_id = OLD.id;
+    {insert_new}
+END;
+CREATE TRIGGER IF NOT EXISTS patterns_fts_delete AFTER DELETE ON patterns BEGIN
+    DELETE FROM patterns_fts WHERE pattern_id = OLD.id;
+END;
+CREATE TRIGGER IF NOT EXISTS patterns_fts_solution
+AFTER INSERT ON events WHEN NEW.type = 'solution' BEGIN
+    DELETE FROM patterns_fts
+    WHERE pattern_id IN (SELECT id FROM patterns WHERE signature = {target});
+    {insert_target}
+END;
+",
+        table = PATTERN_FTS_TABLE,
+        insert_new = pattern_fts_rows("p.id = NEW.id"),
+        target = solution_target,
+        insert_target = pattern_fts_rows(&format!("p.signature = {}", solution_target)),
+    )
+}
+
+/// Rebuild the whole index from `patterns` and `events`
+pub(super) fn rebuild_pattern_fts(conn: &Connection) -> Result<(), NebulaKgError> {
+    conn.execute("DELETE FROM patterns_fts", [])?;
+    conn.execute(&pattern_fts_rows("1"), [])?;
+    Ok(())
+}
+
+/// Columns the bridge reads from each Universal Schema table
+const REQUIRED_COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "events",
+        &["id", "type", "phase", "content", "metadata", "created_at"],
+    ),
+    (
+        "patterns",
+        &[
+            "id",
+            "signature",
+            "category",
+            "description",
+            "solution",
+            "occurrence_count",
+            "last_seen_at",
+            "resolved_at",
+        ],
+    ),
+    ("project_info", &["project_id"]),
+    (
+        "patterns_fts",
+        &["pattern_id", "signature", "description", "solution"],
+    ),
+    (
+        "sync_history",
+        &[
+            "id",
+            "sync_type",
+            "local_id",
+            "central_id",
+            "synced_at",
+            "sync_status",
+            "error_message",
+        ],
+    ),
+    (
+        "sync_outbox",
+        &[
+            "id",
+            "sync_type",
+            "local_id",
+            "idempotency_key",
+            "payload",
+            "status",
+            "attempts",
+            "next_attempt_at",
+            "last_error",
+            "central_id",
+            "created_at",
+        ],
+    ),
+];
+
+/// Column names of `table`, empty if the table does not exist
+pub(super) fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, NebulaKgError> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map(params![table], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(columns)
+}
+
+/// Schema version recorded in `project_info`, if the column and a row exist
+pub(super) fn recorded_version(conn: &Connection) -> Result<Option<String>, NebulaKgError> {
+    if !table_columns(conn, "project_info")?
+        .iter()
+        .any(|c| c == "schema_version")
+    {
+        return Ok(None);
+    }
+    Ok(conn
+        .query_row(
+            "SELECT schema_version FROM project_info LIMIT 1",
+            [],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+/// Verify that existing tables match the Universal Schema this bridge expects.
+/// Tables that don't exist yet are fine (they are created afterwards).
+pub(super) fn verify(conn: &Connection) -> Result<(), NebulaKgError> {
+    let legacy = !table_columns(conn, "local_patterns")?.is_empty();
+    let universal = !table_columns(conn, "patterns")?.is_empty();
+    if legacy && !universal {
+        return Err(NebulaKgError::SchemaMismatch {
+            expected: format!("Universal Schema {}", SCHEMA_VERSION),
+            found: "legacy local_patterns schema".to_string(),
+        });
+    }
+
+    for (table, required) in REQUIRED_COLUMNS {
+        let columns = table_columns(conn, table)?;
+        if columns.is_empty() {
+            continue;
+        }
+        let missing: Vec<&str> = required
+            .iter()
+            .copied()
+            .filter(|c| !columns.iter().any(|existing| existing == c))
+            .collect();
+        if !missing.is_empty() {
+            return Err(NebulaKgError::SchemaMismatch {
+                expected: format!("Universal Schema {}", SCHEMA_VERSION),
+                found: format!("{} missing columns: {}", table, missing.join(", ")),
+            });
+        }
+    }
+
+    match recorded_version(conn)? {
+        Some(version) => check_version(&version),
+        None => Ok(()),
+    }
+}
+
+/// Fail unless `found` is the version this bridge supports
+pub(super) fn check_version(found: &str) -> Result<(), NebulaKgError> {
+    if found != SCHEMA_VERSION {
+        return Err(NebulaKgError::SchemaMismatch {
+            expected: SCHEMA_VERSION.to_string(),
+            found: found.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Like `check_version`, but a store that reports no version at all (e.g. a
+/// database nothing has migrated) fails too
+pub(super) fn require_version(found: Option<&str>) -> Result<(), NebulaKgError> {
+    match found {
+        Some(version) => check_version(version),
+        None => Err(NebulaKgError::SchemaMismatch {
+            expected: SCHEMA_VERSION.to_string(),
+            found: "unversioned database".to_string(),
+        }),
+    }
+}
@@ -0,0 +1,227 @@
+//! Full-text search over patterns.
+//!
+//! The SQLite store keeps an FTS5 index (`patterns_fts`) over each pattern's
+//! signature, description and solution text (the promoted solution plus every
+//! `solution` event recorded for it), maintained by triggers. `PatternSearch`
+//! describes a query; backends return `SearchHit`s ranked by BM25 with a
+//! highlighted snippet of the best-matching column.
+//!
+//! ```ignore
+//! let hits = kg
+//!     .search(PatternSearch::new("mismatch typ").with_prefix(true).with_limit(5))
+//!     .await?;
+//! for hit in hits {
+//!     println!("{:.2} {}", hit.score, hit.snippet);
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use super::{Category, ErrorPattern};
+
+/// BM25 column weights: signature, description, solution
+pub(super) const COLUMN_WEIGHTS: [f64; 3] = [10.0, 5.0, 2.0];
+
+/// Tokens of context FTS5 keeps around the matches in a snippet
+pub(super) const SNIPPET_TOKENS: usize = 16;
+
+/// A full-text query over pattern signatures, descriptions and solutions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternSearch {
+    /// Free text; split into words, punctuation is ignored
+    pub text: String,
+    pub limit: usize,
+    /// Match words by prefix (`mism` finds `mismatched`)
+    pub prefix: bool,
+    /// Match patterns containing any word instead of all of them
+    pub match_any: bool,
+    pub category: Option<Category>,
+    /// Include patterns marked resolved
+    pub include_resolved: bool,
+    /// Markers placed around matched words in the snippet
+    pub highlight: (String, String),
+}
+
+/// One search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub pattern: ErrorPattern,
+    /// Relevance, higher is better. The scale is backend-specific (negated
+    /// BM25 for SQLite, summed column weights of matched words for
+    /// `MemoryBackend`), so only compare scores within one result list.
+    pub score: f64,
+    /// Excerpt of the best-matching column with matches highlighted
+    pub snippet: String,
+}
+
+impl PatternSearch {
+    /// Search for all words of `text`, 10 results, resolved patterns included,
+    /// matches highlighted with `[` `]`
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            limit: 10,
+            prefix: false,
+            match_any: false,
+            category: None,
+            include_resolved: true,
+            highlight: ("[".to_string(), "]".to_string()),
+        }
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: bool) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn with_match_any(mut self, match_any: bool) -> Self {
+        self.match_any = match_any;
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<Category>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn with_include_resolved(mut self, include_resolved: bool) -> Self {
+        self.include_resolved = include_resolved;
+        self
+    }
+
+    pub fn with_highlight(mut self, open: impl Into<String>, close: impl Into<String>) -> Self {
+        self.highlight = (open.into(), close.into());
+        self
+    }
+
+    /// Lowercased words of the query, tokenized like the FTS5 index
+    pub fn terms(&self) -> Vec<String> {
+        words(&self.text)
+            .map(|(_, word)| word.to_lowercase())
+            .collect()
+    }
+
+    /// FTS5 `MATCH` expression for the query, `None` if it has no words.
+    /// Every word is quoted, so FTS5 operators in the input are matched literally.
+    pub fn match_expression(&self) -> Option<String> {
+        let terms: Vec<String> = self
+            .terms()
+            .into_iter()
+            .map(|term| {
+                if self.prefix {
+                    format!("\"{}\"*", term)
+                } else {
+                    format!("\"{}\"", term)
+                }
+            })
+            .collect();
+        if terms.is_empty() {
+            return None;
+        }
+        Some(terms.join(if self.match_any { " OR " } else { " " }))
+    }
+
+    /// Whether `pattern` passes the category / resolved filters
+    pub(super) fn accepts(&self, pattern: &ErrorPattern) -> bool {
+        (self.include_resolved || pattern.resolved_at.is_none())
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| pattern.category.as_ref() == Some(category))
+    }
+
+    /// Score `pattern` without an index (used by `MemoryBackend`). `solutions`
+    /// is the pattern's solution text. Returns `None` if it doesn't match.
+    pub(super) fn score(&self, pattern: &ErrorPattern, solutions: &str) -> Option<SearchHit> {
+        let terms = self.terms();
+        if terms.is_empty() || !self.accepts(pattern) {
+            return None;
+        }
+        let columns = [
+            pattern.signature.as_str(),
+            pattern.description.as_deref().unwrap_or(""),
+            solutions,
+        ];
+
+        let mut matched = vec![false; terms.len()];
+        let mut score = 0.0;
+        let mut best: Option<(f64, &str)> = None;
+        for (text, weight) in columns.iter().zip(COLUMN_WEIGHTS) {
+            let mut column_score = 0.0;
+            for (_, word) in words(text) {
+                let word = word.to_lowercase();
+                for (i, term) in terms.iter().enumerate() {
+                    if self.term_matches(term, &word) {
+                        matched[i] = true;
+                        column_score += weight;
+                    }
+                }
+            }
+            if column_score > best.map_or(0.0, |(s, _)| s) {
+                best = Some((column_score, text));
+            }
+            score += column_score;
+        }
+
+        let found = if self.match_any {
+            matched.iter().any(|m| *m)
+        } else {
+            matched.iter().all(|m| *m)
+        };
+        let (_, text) = best.filter(|_| found)?;
+        Some(SearchHit {
+            pattern: pattern.clone(),
+            score,
+            snippet: self.highlight_text(text, &terms),
+        })
+    }
+
+    fn term_matches(&self, term: &str, word: &str) -> bool {
+        if self.prefix {
+            word.starts_with(term)
+        } else {
+            word == term
+        }
+    }
+
+    /// `text` with every matching word wrapped in the highlight markers
+    fn highlight_text(&self, text: &str, terms: &[String]) -> String {
+        let (open, close) = &self.highlight;
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, word) in words(text) {
+            let lower = word.to_lowercase();
+            if terms.iter().any(|term| self.term_matches(term, &lower)) {
+                out.push_str(&text[last..start]);
+                out.push_str(open);
+                out.push_str(word);
+                out.push_str(close);
+                last = start + word.len();
+            }
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+}
+
+/// Alphanumeric runs of `text` with their byte offsets (FTS5 `unicode61` tokens)
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            words.push((s, &text[s..i]));
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words.into_iter()
+}
@@ -0,0 +1,785 @@
+//! Offline semantic index over patterns (feature `semantic-index`).
+//!
+//! Each pattern's normalized signature, description and solution are embedded
+//! into a vector and kept in an HNSW graph, so "what's the fix for this?" can
+//! match errors that share meaning rather than exact words. Embeddings come
+//! from an `Embedder`: plug in a locally loaded model, or use the built-in
+//! `HashingEmbedder`, which needs no model files.
+//!
+//! Vectors are persisted next to the database (`<db>.vectors`) as an
+//! append-only log and the graph is rebuilt when the index is opened. The
+//! bridge wraps its backend so every capture and solution updates the index,
+//! and `sync` embeds patterns written by other processes (e.g. the Python
+//! tooling) since the index was last opened.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+
+use super::backend::KgBackend;
+use super::hnsw::Hnsw;
+use super::signature::{fnv1a, normalize_signature};
+use super::{
+    ErrorCapture, ErrorPattern, ErrorResolution, Fix, KgEvent, NebulaKgError, OutboxItem,
+    OutboxStatus, OutboxUpdate, PatternSearch, PatternSummary, ResolutionStats, SearchHit,
+    SimilarPattern, SimilaritySearch, Solution, SolutionFeedback, SyncRecord, SyncType,
+    UnsyncedSolution,
+};
+
+/// First bytes of a vector log; bump the digit when the record layout changes
+const MAGIC: &[u8; 8] = b"NKGVEC1\n";
+
+/// Dimensions of `HashingEmbedder::default()`
+pub const DEFAULT_EMBEDDING_DIMENSIONS: usize = 256;
+
+/// Longest id or signature accepted from a vector log
+const MAX_TEXT_BYTES: usize = 1 << 20;
+
+/// Patterns fetched when looking one up by signature
+const LOOKUP_LIMIT: usize = 50;
+
+/// Most frequent patterns embedded by `SemanticIndex::sync`
+const SYNC_LIMIT: usize = 10_000;
+
+/// Turns text into a fixed-size vector
+pub trait Embedder: Send + Sync {
+    /// Identifies the model and its settings. An index built with a different
+    /// id is discarded and re-embedded.
+    fn model_id(&self) -> String;
+
+    fn dimensions(&self) -> usize;
+
+    /// Embed `text`; the result must have `dimensions()` entries
+    fn embed(&self, text: &str) -> Result<Vec<f32>, NebulaKgError>;
+}
+
+/// Model-free embedding: words, word pairs and character trigrams hashed into
+/// a signed bag-of-features vector. Captures shared vocabulary (including
+/// partial words like `borrow`/`borrowed`), not synonyms.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn add(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature);
+        let bucket = (hash % self.dimensions as u64) as usize;
+        vector[bucket] += if hash >> 63 == 0 { weight } else { -weight };
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_EMBEDDING_DIMENSIONS)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn model_id(&self) -> String {
+        format!("hashing-v1-{}", self.dimensions)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, NebulaKgError> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+
+        let mut vector = vec![0.0; self.dimensions];
+        for word in &words {
+            self.add(&mut vector, &format!("w:{}", word), 1.0);
+            let padded: Vec<char> = format!("^{}$", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add(&mut vector, &format!("t:{}", trigram), 0.3);
+            }
+        }
+        for pair in words.windows(2) {
+            self.add(&mut vector, &format!("b:{} {}", pair[0], pair[1]), 0.6);
+        }
+        Ok(normalized(vector))
+    }
+}
+
+/// A pattern close in meaning to the query
+#[derive(Debug, Clone)]
+pub struct SemanticMatch {
+    pub pattern: ErrorPattern,
+    /// Cosine similarity, -1.0 ..= 1.0 (in practice 0.0 ..= 1.0)
+    pub score: f32,
+}
+
+struct Entry {
+    signature: String,
+    text_hash: u64,
+    node: usize,
+}
+
+struct IndexState {
+    graph: Hnsw,
+    /// Live entries by pattern id
+    entries: HashMap<String, Entry>,
+    /// Pattern id of every graph node (retired nodes included)
+    node_ids: Vec<String>,
+    log: Option<BufWriter<File>>,
+}
+
+/// Vector index over patterns, persisted as `<db>.vectors`
+pub struct SemanticIndex {
+    embedder: Arc<dyn Embedder>,
+    state: Mutex<IndexState>,
+}
+
+impl SemanticIndex {
+    /// Vector file used for the database at `db_path`
+    pub fn default_path(db_path: &str) -> PathBuf {
+        PathBuf::from(format!("{}.vectors", db_path))
+    }
+
+    /// Index that is never written to disk
+    pub fn in_memory(embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            state: Mutex::new(IndexState {
+                graph: Hnsw::default(),
+                entries: HashMap::new(),
+                node_ids: Vec::new(),
+                log: None,
+            }),
+        }
+    }
+
+    /// Load the vectors at `path` (created if missing). A file written for a
+    /// different model, or unreadable, is started afresh; call `sync` to fill it.
+    pub fn open(
+        path: impl AsRef<Path>,
+        embedder: Arc<dyn Embedder>,
+    ) -> Result<Self, NebulaKgError> {
+        let path = path.as_ref();
+        let index = Self::in_memory(embedder);
+        let header = index.header();
+
+        let (records, complete) = match File::open(path) {
+            Ok(file) => {
+                let size = file.metadata()?.len();
+                match read_log(BufReader::new(file), &header, index.embedder.dimensions()) {
+                    Some(records) => {
+                        let read =
+                            header.len() as u64 + records.iter().map(Record::size).sum::<u64>();
+                        (records, read == size)
+                    }
+                    None => {
+                        tracing::warn!(
+                            "Semantic index {} is for another model or unreadable; rebuilding",
+                            path.display()
+                        );
+                        (Vec::new(), false)
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => (Vec::new(), false),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut state = index.state.lock()?;
+        let total = records.len();
+        for record in records {
+            state.insert(record);
+        }
+
+        // Rewrite the log when it is new, discarded, cut short (appends would be
+        // misaligned) or mostly superseded records
+        let live = state.entries.len();
+        if !complete || total > 2 * live + 64 {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp = path.with_extension("vectors.tmp");
+            {
+                let mut out = BufWriter::new(File::create(&tmp)?);
+                out.write_all(&header)?;
+                for (id, entry) in &state.entries {
+                    let vector = state.graph.vector(entry.node);
+                    write_record(&mut out, id, &entry.signature, entry.text_hash, vector)?;
+                }
+                out.flush()?;
+            }
+            fs::rename(&tmp, path)?;
+        }
+        state.log = Some(BufWriter::new(OpenOptions::new().append(true).open(path)?));
+        drop(state);
+        Ok(index)
+    }
+
+    /// Number of indexed patterns
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|s| s.entries.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Embed every pattern that is new or changed since it was indexed.
+    /// Returns how many were embedded.
+    pub fn sync(&self, backend: &dyn KgBackend) -> Result<usize, NebulaKgError> {
+        let mut embedded = 0;
+        for pattern in backend.search_patterns("", SYNC_LIMIT)? {
+            if self.update(&pattern)? {
+                embedded += 1;
+            }
+        }
+        Ok(embedded)
+    }
+
+    /// (Re-)embed `pattern` if its text changed. Returns whether it was embedded.
+    pub fn update(&self, pattern: &ErrorPattern) -> Result<bool, NebulaKgError> {
+        let text = pattern_text(pattern);
+        let text_hash = fnv1a(&text);
+        if self
+            .state
+            .lock()?
+            .entries
+            .get(&pattern.id)
+            .is_some_and(|e| e.text_hash == text_hash)
+        {
+            return Ok(false);
+        }
+
+        let vector = self.embed(&text)?;
+        let mut state = self.state.lock()?;
+        if let Some(log) = state.log.as_mut() {
+            write_record(log, &pattern.id, &pattern.signature, text_hash, &vector)?;
+            log.flush()?;
+        }
+        state.insert(Record {
+            id: pattern.id.clone(),
+            signature: pattern.signature.clone(),
+            text_hash,
+            vector,
+        });
+        Ok(true)
+    }
+
+    /// Up to `limit` indexed patterns nearest to `text`, as (pattern id,
+    /// signature, cosine similarity)
+    pub fn nearest(
+        &self,
+        text: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, String, f32)>, NebulaKgError> {
+        let query = self.embed(text)?;
+        let state = self.state.lock()?;
+        Ok(state
+            .graph
+            .search(&query, limit)
+            .into_iter()
+            .filter_map(|(node, score)| {
+                let id = &state.node_ids[node];
+                state
+                    .entries
+                    .get(id)
+                    .map(|entry| (id.clone(), entry.signature.clone(), score))
+            })
+            .collect())
+    }
+
+    /// Signature of the indexed pattern with this id or signature
+    fn signature_of(&self, id_or_signature: &str) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        state
+            .entries
+            .get(id_or_signature)
+            .map(|entry| entry.signature.clone())
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, NebulaKgError> {
+        let vector = self.embedder.embed(text)?;
+        if vector.len() != self.embedder.dimensions() {
+            return Err(NebulaKgError::InvalidValue(format!(
+                "embedder {} returned {} dimensions, expected {}",
+                self.embedder.model_id(),
+                vector.len(),
+                self.embedder.dimensions()
+            )));
+        }
+        Ok(normalized(vector))
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let model = self.embedder.model_id();
+        let mut header = MAGIC.to_vec();
+        header.extend((self.embedder.dimensions() as u32).to_le_bytes());
+        header.extend((model.len() as u32).to_le_bytes());
+        header.extend(model.as_bytes());
+        header
+    }
+}
+
+struct Record {
+    id: String,
+    signature: String,
+    text_hash: u64,
+    vector: Vec<f32>,
+}
+
+impl Record {
+    /// Bytes the record takes in the log
+    fn size(&self) -> u64 {
+        (4 + self.id.len() + 4 + self.signature.len() + 8 + self.vector.len() * 4) as u64
+    }
+}
+
+impl IndexState {
+    /// Add a record, retiring the pattern's previous node
+    fn insert(&mut self, record: Record) {
+        if let Some(old) = self.entries.get(&record.id) {
+            self.graph.mark_deleted(old.node);
+        }
+        let node = self
+            .graph
+            .insert(record.vector, fnv1a(&record.id) ^ record.text_hash);
+        self.node_ids.push(record.id.clone());
+        self.entries.insert(
+            record.id,
+            Entry {
+                signature: record.signature,
+                text_hash: record.text_hash,
+                node,
+            },
+        );
+    }
+}
+
+/// Backend wrapper that keeps a `SemanticIndex` up to date. Index failures are
+/// logged, never returned: a capture must not fail because of the index.
+pub(super) struct IndexedBackend {
+    inner: Arc<dyn KgBackend>,
+    index: Arc<SemanticIndex>,
+}
+
+impl IndexedBackend {
+    pub(super) fn new(inner: Arc<dyn KgBackend>, index: Arc<SemanticIndex>) -> Self {
+        Self { inner, index }
+    }
+
+    fn refresh(&self, signature: &str) {
+        let result =
+            find_pattern(self.inner.as_ref(), signature).and_then(|pattern| match pattern {
+                Some(pattern) => self.index.update(&pattern).map(|_| ()),
+                None => Ok(()),
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to update semantic index for '{}': {}", signature, e);
+        }
+    }
+}
+
+/// The pattern with exactly this signature
+pub(super) fn find_pattern(
+    backend: &dyn KgBackend,
+    signature: &str,
+) -> Result<Option<ErrorPattern>, NebulaKgError> {
+    Ok(backend
+        .search_patterns(signature, LOOKUP_LIMIT)?
+        .into_iter()
+        .find(|p| p.signature == signature))
+}
+
+impl KgBackend for IndexedBackend {
+    fn capture(&self, capture: &ErrorCapture) -> Result<String, NebulaKgError> {
+        let event_id = self.inner.capture(capture)?;
+        self.refresh(&capture.signature);
+        Ok(event_id)
+    }
+
+    fn search_patterns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        self.inner.search_patterns(query, limit)
+    }
+
+    fn search(&self, search: &PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError> {
+        self.inner.search(search)
+    }
+
+    fn find_similar(
+        &self,
+        search: &SimilaritySearch,
+    ) -> Result<Vec<SimilarPattern>, NebulaKgError> {
+        self.inner.find_similar(search)
+    }
+
+    fn record_solution(
+        &self,
+        pattern_id: &str,
+        solution: &Solution,
+    ) -> Result<String, NebulaKgError> {
+        let event_id = self.inner.record_solution(pattern_id, solution)?;
+        let signature = self
+            .index
+            .signature_of(pattern_id)
+            .unwrap_or_else(|| pattern_id.to_string());
+        self.refresh(&signature);
+        Ok(event_id)
+    }
+
+    fn record_feedback(
+        &self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError> {
+        // Feedback can change which solution is promoted; `sync` re-embeds such
+        // patterns the next time the index is opened
+        self.inner.record_feedback(solution_id, feedback)
+    }
+
+    fn resolve_error(
+        &self,
+        error_event_id: &str,
+        fix: &Fix,
+    ) -> Result<ErrorResolution, NebulaKgError> {
+        // Either kind of fix can change the promoted solution
+        let resolution = self.inner.resolve_error(error_event_id, fix)?;
+        self.refresh(&resolution.signature);
+        Ok(resolution)
+    }
+
+    fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.inner.open_errors(limit)
+    }
+
+    fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError> {
+        self.inner.resolution_stats()
+    }
+
+    fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: &Value,
+    ) -> Result<Option<String>, NebulaKgError> {
+        self.inner.resolve_pattern(signature, content, context)
+    }
+
+    fn get_summary(&self) -> Result<PatternSummary, NebulaKgError> {
+        self.inner.get_summary()
+    }
+
+    fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: &Value,
+    ) -> Result<String, NebulaKgError> {
+        self.inner
+            .record_event(event_type, phase, content, metadata)
+    }
+
+    fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        self.inner.recent_events(limit)
+    }
+
+    fn unsynced_patterns(&self, limit: usize) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        self.inner.unsynced_patterns(limit)
+    }
+
+    fn unsynced_solutions(&self, limit: usize) -> Result<Vec<UnsyncedSolution>, NebulaKgError> {
+        self.inner.unsynced_solutions(limit)
+    }
+
+    fn record_sync(&self, records: &[SyncRecord]) -> Result<(), NebulaKgError> {
+        self.inner.record_sync(records)
+    }
+
+    fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError> {
+        self.inner.sync_history(limit)
+    }
+
+    fn enqueue_sync(&self, items: &[OutboxItem]) -> Result<usize, NebulaKgError> {
+        self.inner.enqueue_sync(items)
+    }
+
+    fn due_sync_items(
+        &self,
+        sync_type: SyncType,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        self.inner.due_sync_items(sync_type, limit)
+    }
+
+    fn update_sync_items(&self, updates: &[OutboxUpdate]) -> Result<(), NebulaKgError> {
+        self.inner.update_sync_items(updates)
+    }
+
+    fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        self.inner.sync_outbox(status, limit)
+    }
+
+    fn schema_version(&self) -> Result<Option<String>, NebulaKgError> {
+        self.inner.schema_version()
+    }
+}
+
+/// Text embedded for a pattern
+fn pattern_text(pattern: &ErrorPattern) -> String {
+    let mut text = normalize_signature(&pattern.signature).canonical;
+    for part in [&pattern.description, &pattern.solution]
+        .into_iter()
+        .flatten()
+    {
+        text.push('\n');
+        text.push_str(part);
+    }
+    text
+}
+
+/// Scale to unit length (all-zero vectors are left as they are)
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn write_record(
+    out: &mut impl Write,
+    id: &str,
+    signature: &str,
+    text_hash: u64,
+    vector: &[f32],
+) -> std::io::Result<()> {
+    for text in [id, signature] {
+        out.write_all(&(text.len() as u32).to_le_bytes())?;
+        out.write_all(text.as_bytes())?;
+    }
+    out.write_all(&text_hash.to_le_bytes())?;
+    for value in vector {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Records of a vector log, or `None` if its header doesn't match. A record
+/// cut short by a crash ends the log.
+fn read_log(mut input: impl Read, header: &[u8], dimensions: usize) -> Option<Vec<Record>> {
+    let mut found = vec![0; header.len()];
+    input.read_exact(&mut found).ok()?;
+    if found != header {
+        return None;
+    }
+
+    let mut records = Vec::new();
+    while let Some(record) = read_record(&mut input, dimensions) {
+        records.push(record);
+    }
+    Some(records)
+}
+
+fn read_record(input: &mut impl Read, dimensions: usize) -> Option<Record> {
+    let mut text = || -> Option<String> {
+        let mut len = [0; 4];
+        input.read_exact(&mut len).ok()?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_TEXT_BYTES {
+            return None;
+        }
+        let mut bytes = vec![0; len];
+        input.read_exact(&mut bytes).ok()?;
+        String::from_utf8(bytes).ok()
+    };
+    let id = text()?;
+    let signature = text()?;
+
+    let mut hash = [0; 8];
+    input.read_exact(&mut hash).ok()?;
+    let mut raw = vec![0; dimensions * 4];
+    input.read_exact(&mut raw).ok()?;
+    let vector = raw
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    Some(Record {
+        id,
+        signature,
+        text_hash: u64::from_le_bytes(hash),
+        vector,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend_tests::TempDb;
+    use super::super::memory::MemoryBackend;
+    use super::super::{Category, LocalKGBridge};
+    use super::*;
+
+    fn pattern(id: &str, signature: &str, description: &str) -> ErrorPattern {
+        ErrorPattern {
+            id: id.to_string(),
+            signature: signature.to_string(),
+            category: None,
+            description: Some(description.to_string()),
+            solution: None,
+            occurrence_count: 1,
+            last_seen_at: None,
+            resolved_at: None,
+            solutions: Vec::new(),
+        }
+    }
+
+    fn hashing_index() -> SemanticIndex {
+        SemanticIndex::in_memory(Arc::new(HashingEmbedder::default()))
+    }
+
+    #[test]
+    fn near_duplicate_ranks_first() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let backend = Arc::new(MemoryBackend::new());
+        let kg = LocalKGBridge::with_backend(backend).unwrap();
+        assert!(matches!(
+            runtime.block_on(kg.semantic_search("anything", 3)),
+            Err(NebulaKgError::Config(_))
+        ));
+
+        let captures = [
+            (
+                "borrow of moved value: `config`",
+                Category::BorrowCheck,
+                "value used here after move into the closure",
+            ),
+            (
+                "mismatched types: expected `u32`, found `i64`",
+                Category::CompileError,
+                "expected u32 because of the return type",
+            ),
+            (
+                "connection refused (os error 111)",
+                Category::Runtime,
+                "the database server is not accepting connections",
+            ),
+        ];
+        for (signature, category, description) in captures {
+            runtime
+                .block_on(
+                    kg.capture(
+                        ErrorCapture::new(signature, category).with_description(description),
+                    ),
+                )
+                .unwrap();
+        }
+        // Patterns captured before the index was enabled are embedded on attach
+        let kg = kg.with_semantic_index(hashing_index()).unwrap();
+        runtime
+            .block_on(
+                kg.capture(
+                    ErrorCapture::new(
+                        "cannot find value `cfg` in this scope",
+                        Category::CompileError,
+                    )
+                    .with_description("not found in this scope"),
+                ),
+            )
+            .unwrap();
+
+        let matches = runtime
+            .block_on(kg.semantic_search(
+                "borrow of moved value: `settings`, value used after move",
+                4,
+            ))
+            .unwrap();
+        assert_eq!(matches.len(), 4);
+        assert_eq!(
+            matches[0].pattern.signature,
+            "borrow of moved value: `config`"
+        );
+        assert!(matches[0].score > matches[1].score);
+
+        let matches = runtime
+            .block_on(kg.semantic_search("cannot find value `opts` in this scope", 1))
+            .unwrap();
+        assert_eq!(
+            matches[0].pattern.signature,
+            "cannot find value `cfg` in this scope"
+        );
+    }
+
+    #[test]
+    fn re_embeds_only_changed_patterns() {
+        let index = hashing_index();
+        let mut moved = pattern("p1", "borrow of moved value: `x`", "value moved");
+        assert!(index.update(&moved).unwrap());
+        assert!(!index.update(&moved).unwrap());
+
+        moved.solution = Some("clone the value before moving it".to_string());
+        assert!(index.update(&moved).unwrap());
+        assert!(index
+            .update(&pattern("p2", "index out of bounds", "the len is 3"))
+            .unwrap());
+        assert_eq!(index.len(), 2);
+
+        // The retired vector of `p1` is not returned alongside the new one
+        let nearest = index.nearest("clone the moved value", 5).unwrap();
+        let ids: Vec<&str> = nearest.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, ["p1", "p2"]);
+    }
+
+    #[test]
+    fn persists_vectors_per_model() {
+        let db = TempDb::new("semantic");
+        let path = SemanticIndex::default_path(db.path());
+        {
+            let index = SemanticIndex::open(&path, Arc::new(HashingEmbedder::default())).unwrap();
+            assert!(index.is_empty());
+            index
+                .update(&pattern("p1", "borrow of moved value: `x`", "value moved"))
+                .unwrap();
+            index
+                .update(&pattern("p2", "index out of bounds", "the len is 3"))
+                .unwrap();
+        }
+
+        let index = SemanticIndex::open(&path, Arc::new(HashingEmbedder::default())).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.nearest("index out of bounds", 1).unwrap()[0].0, "p2");
+        assert!(!index
+            .update(&pattern("p2", "index out of bounds", "the len is 3"))
+            .unwrap());
+
+        // A different model can't use these vectors: start afresh
+        let index = SemanticIndex::open(&path, Arc::new(HashingEmbedder::new(64))).unwrap();
+        assert!(index.is_empty());
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            index.header(),
+            "log was not rewritten for the new model"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Rust-aware error signature normalization.
+//!
+//! The same error reported at two places differs only in details: file
+//! paths, line/column numbers, the types and names rustc quotes in backticks,
+//! lifetimes, addresses and generic arguments. `normalize_signature` strips
+//! those to a canonical form, so `mismatched types` in two files becomes one
+//! pattern, and derives a fingerprint from the rustc error code (`E0308`)
+//! when there is one, or from the canonical form otherwise.
+
+use serde_json::{json, Value};
+
+use super::ErrorCapture;
+
+/// A signature reduced to what identifies the kind of error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedSignature {
+    /// e.g. "E0308: mismatched types: expected `_`, found `_`"
+    pub canonical: String,
+    /// 16 hex digits; equal for errors in the same group
+    pub fingerprint: String,
+    /// rustc error code (`E0xxx`), when the signature carries one
+    pub error_code: Option<String>,
+}
+
+/// Normalize a raw error signature or message.
+///
+/// Accepts rustc's forms (`error[E0308]: ...`, `E0308: ...`) as well as free
+/// text. Normalizing a canonical form returns it unchanged.
+pub fn normalize_signature(raw: &str) -> NormalizedSignature {
+    let (error_code, message) = split_error_code(raw.trim());
+    let message = normalize_message(message);
+    let canonical = match &error_code {
+        Some(code) if message.is_empty() => code.clone(),
+        Some(code) => format!("{}: {}", code, message),
+        None => message,
+    };
+    // Errors sharing a code are one group, whatever the wording of the message
+    let fingerprint = fnv1a(error_code.as_deref().unwrap_or(&canonical));
+
+    NormalizedSignature {
+        canonical,
+        fingerprint: format!("{:016x}", fingerprint),
+        error_code,
+    }
+}
+
+impl ErrorCapture {
+    /// Replace the signature with its canonical form. The original signature
+    /// and the fingerprint are kept in `context` (`raw_signature`, `fingerprint`);
+    /// a rustc error code found in the signature fills `error_code` if unset.
+    pub fn normalized(mut self) -> Self {
+        let normalized = normalize_signature(&self.signature);
+        let mut context = match self.context.take() {
+            Some(Value::Object(map)) => map,
+            Some(other) => [("value".to_string(), other)].into_iter().collect(),
+            None => Default::default(),
+        };
+        context.insert("raw_signature".to_string(), json!(self.signature));
+        context.insert("fingerprint".to_string(), json!(normalized.fingerprint));
+        if self.error_code.is_none() {
+            self.error_code = normalized.error_code;
+        }
+        self.signature = normalized.canonical;
+        self.context = Some(Value::Object(context));
+        self
+    }
+}
+
+/// Split off a leading `error[E0308]:` / `E0308:` / `error:` prefix
+fn split_error_code(raw: &str) -> (Option<String>, &str) {
+    for level in ["error", "warning"] {
+        if let Some(rest) = raw.strip_prefix(level) {
+            if let Some((code, message)) = rest
+                .strip_prefix('[')
+                .and_then(|r| r.split_once("]:"))
+                .filter(|(code, _)| is_error_code(code))
+            {
+                return (Some(code.to_string()), message.trim_start());
+            }
+            if let Some(message) = rest.strip_prefix(':') {
+                return (None, message.trim_start());
+            }
+        }
+    }
+
+    if let Some(code) = raw.get(..5).filter(|code| is_error_code(code)) {
+        let rest = &raw[5..];
+        if rest.is_empty() || rest.starts_with(':') {
+            return (
+                Some(code.to_string()),
+                rest.trim_start_matches(':').trim_start(),
+            );
+        }
+    }
+    (None, raw)
+}
+
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Replace the instance-specific parts of a message with placeholders
+fn normalize_message(message: &str) -> String {
+    let chars: Vec<char> = message.chars().collect();
+    let mut out = String::with_capacity(message.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = out.chars().last();
+        let at_word_start = !prev.is_some_and(is_ident_char);
+
+        // `Vec<u8>`, `x`, `&'a str`: whatever rustc quotes is a type or a name
+        if c == '`' {
+            if let Some(len) = chars[i + 1..].iter().position(|&c| c == '`') {
+                out.push_str("`_`");
+                i += len + 2;
+                continue;
+            }
+        }
+
+        // 0x7ffd5e8c
+        if c == '0'
+            && at_word_start
+            && chars.get(i + 1) == Some(&'x')
+            && chars.get(i + 2).is_some_and(char::is_ascii_hexdigit)
+        {
+            out.push_str("0x_");
+            i += 2;
+            while chars.get(i).is_some_and(char::is_ascii_hexdigit) {
+                i += 1;
+            }
+            continue;
+        }
+
+        // src/main.rs:10:5, /home/me/.cargo/registry/..., C:\src\lib.rs
+        if at_word_start && !c.is_whitespace() {
+            let end = chars[i..]
+                .iter()
+                .position(|c| c.is_whitespace())
+                .map_or(chars.len(), |len| i + len);
+            let token: String = chars[i..end].iter().collect();
+            if let Some(replaced) = normalize_path_token(&token) {
+                out.push_str(&replaced);
+                i = end;
+                continue;
+            }
+            // A URL is kept whole, or its path would be taken for a file path
+            if token.contains("://") {
+                out.push_str(&token);
+                i = end;
+                continue;
+            }
+        }
+
+        // Line/column numbers, counts, indices (but not `2nd` or `0x_`)
+        if c.is_ascii_digit() && at_word_start {
+            let end = chars[i..]
+                .iter()
+                .position(|c| !c.is_ascii_digit())
+                .map_or(chars.len(), |len| i + len);
+            if !chars.get(end).is_some_and(|c| is_ident_char(*c)) {
+                out.push('N');
+                i = end;
+                continue;
+            }
+        }
+
+        // 'a, 'static (but not a quoted name like 'main' or a char literal 'x')
+        if c == '\''
+            && at_word_start
+            && chars
+                .get(i + 1)
+                .is_some_and(|c| c.is_alphabetic() || *c == '_')
+        {
+            let len = chars[i + 1..]
+                .iter()
+                .position(|c| !is_ident_char(*c))
+                .unwrap_or(chars.len() - i - 1);
+            if chars.get(i + 1 + len) != Some(&'\'') {
+                out.push_str("'_");
+                i += len + 1;
+                continue;
+            }
+        }
+
+        // Option<Box<dyn Error>> -> Option<_>
+        if c == '<' && prev.is_some_and(is_ident_char) {
+            if let Some(len) = generic_args_len(&chars[i..]) {
+                out.push_str("<_>");
+                i += len;
+                continue;
+            }
+        }
+
+        out.push(c);
+        i += 1;
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `<path>` (plus trailing punctuation) if `token` is a source path with an
+/// optional `:line:col` suffix
+fn normalize_path_token(token: &str) -> Option<String> {
+    let unquoted = token.trim_start_matches(['(', '[', '"', '\'']);
+    let leading = &token[..token.len() - unquoted.len()];
+    let core = unquoted.trim_end_matches([',', ';', ':', ')', ']', '"', '\'', '.']);
+    let trailing = &unquoted[core.len()..];
+    // Cut `:10:5` off `src/main.rs:10:5`
+    let path = core
+        .char_indices()
+        .find(|(i, c)| *c == ':' && core[i + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map_or(core, |(i, _)| &core[..i]);
+
+    // A bare `and/or` is prose, not a path
+    let is_path = (path.ends_with(".rs")
+        || path.contains('\\')
+        || (path.contains('/') && (path.starts_with('/') || path.contains('.'))))
+        && !path.contains("://")
+        && path.chars().any(char::is_alphabetic);
+    is_path.then(|| format!("{}<path>{}", leading, trailing))
+}
+
+/// Length of a balanced `<...>` at the start of `chars` (ignoring `->`)
+fn generic_args_len(chars: &[char]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '<' => depth += 1,
+            '>' if i > 0 && chars[i - 1] == '-' => {}
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 64-bit FNV-1a: stable across runs, platforms and Rust versions
+/// (unlike `DefaultHasher`), so fingerprints can be stored and shared
+pub(super) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Category;
+    use super::*;
+
+    fn canonical(raw: &str) -> String {
+        normalize_signature(raw).canonical
+    }
+
+    #[test]
+    fn replaces_instance_details() {
+        for (raw, expected) in [
+            (
+                "error[E0308]: mismatched types: expected `u32`, found `String`",
+                "E0308: mismatched types: expected `_`, found `_`",
+            ),
+            (
+                "E0382: borrow of moved value: `v`",
+                "E0382: borrow of moved value: `_`",
+            ),
+            ("error[E0599]:", "E0599"),
+            ("error: linking with `cc` failed", "linking with `_` failed"),
+            (
+                "thread 'main' panicked at src/main.rs:10:5:",
+                "thread 'main' panicked at <path>:",
+            ),
+            (
+                "could not read (/home/me/.cargo/registry/src/lib.rs) or C:\\src\\lib.rs",
+                "could not read (<path>) or <path>",
+            ),
+            (
+                "see https://doc.rust-lang.org/error_codes/E0308.html and/or ask",
+                "see https://doc.rust-lang.org/error_codes/E0308.html and/or ask",
+            ),
+            (
+                "expected 2 arguments, found 13 (2nd call, u32)",
+                "expected N arguments, found N (2nd call, u32)",
+            ),
+            (
+                "lifetime 'a does not outlive 'static for char 'x'",
+                "lifetime '_ does not outlive '_ for char 'x'",
+            ),
+            (
+                "cannot convert Option<Box<dyn Error>> into Result<T, E> via fn() -> u8",
+                "cannot convert Option<_> into Result<_> via fn() -> u8",
+            ),
+            ("address 0x7ffd5e8c is invalid", "address 0x_ is invalid"),
+            (
+                "expected `u32 but the quote is open",
+                "expected `u32 but the quote is open",
+            ),
+            ("  spread   over\n lines  ", "spread over lines"),
+        ] {
+            assert_eq!(canonical(raw), expected, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn handles_multi_byte_text_at_replacement_boundaries() {
+        for (raw, expected) in [
+            ("ünïcødé 42 ñ", "ünïcødé N ñ"),
+            ("é`ü`é and 🦀`Vec<ß>`🦀", "é`_`é and 🦀`_`🦀"),
+            // Digits and hex glued to a letter are part of a name
+            ("é1 漢字0x1f", "é1 漢字0x1f"),
+            ("данные/файл.rs:3:1 failed", "<path> failed"),
+            (
+                "'ß does not live long enough",
+                "'_ does not live long enough",
+            ),
+            ("Größe<Ä, Ö> mismatch", "Größe<_> mismatch"),
+            // Not an error code, and byte 5 falls inside `é`
+            ("E030é: text", "E030é: text"),
+            ("E03é8: text", "E03é8: text"),
+        ] {
+            assert_eq!(canonical(raw), expected, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn normalizing_is_idempotent() {
+        for raw in [
+            "error[E0308]: mismatched types: expected `u32`, found `String`",
+            "thread 'main' panicked at src/main.rs:10:5:\ncalled `Option::unwrap()` on a `None` value",
+            "lifetime 'a in Option<&'a [u8; 4]> at 0xdeadbeef",
+            "ünïcødé 🦀 C:\\src\\lib.rs:1 Größe<Ä>",
+            "E0599",
+        ] {
+            let once = normalize_signature(raw);
+            let twice = normalize_signature(&once.canonical);
+            assert_eq!(twice, once, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn fingerprints_by_error_code() {
+        let a = normalize_signature("error[E0308]: mismatched types");
+        let b = normalize_signature("E0308: expected `u8`, found `i64` in src/lib.rs:4:2");
+        let c = normalize_signature("error[E0382]: mismatched types");
+        assert_eq!(a.error_code.as_deref(), Some("E0308"));
+        assert_eq!(a.fingerprint, b.fingerprint);
+        assert_ne!(a.fingerprint, c.fingerprint);
+        assert_eq!(a.fingerprint.len(), 16);
+        assert_eq!(a.fingerprint, format!("{:016x}", fnv1a("E0308")));
+
+        // Without a code the canonical form decides
+        let x = normalize_signature("index out of bounds at src/a.rs:1:2: len is 3");
+        let y = normalize_signature("index out of bounds at src/b.rs:9:9: len is 7");
+        let z = normalize_signature("attempt to divide by zero");
+        assert_eq!(x.error_code, None);
+        assert_eq!(x.fingerprint, y.fingerprint);
+        assert_ne!(x.fingerprint, z.fingerprint);
+    }
+
+    #[test]
+    fn normalized_capture_keeps_the_raw_signature() {
+        let capture = ErrorCapture::new(
+            "error[E0277]: `Foo` doesn't implement `Debug`",
+            Category::CompileError,
+        )
+        .with_context(json!({ "crate": "demo" }))
+        .normalized();
+        assert_eq!(capture.signature, "E0277: `_` doesn't implement `_`");
+        assert_eq!(capture.error_code.as_deref(), Some("E0277"));
+        let context = capture.context.unwrap();
+        assert_eq!(context["crate"], "demo");
+        assert_eq!(
+            context["raw_signature"],
+            "error[E0277]: `Foo` doesn't implement `Debug`"
+        );
+        assert_eq!(context["fingerprint"], format!("{:016x}", fnv1a("E0277")));
+    }
+}
@@ -0,0 +1,340 @@
+//! Fuzzy matching of error signatures.
+//!
+//! Offline counterpart of the Central KG's similar-errors query (`pg_trgm`
+//! `similarity() > 0.3`). Both signatures are normalized first (see
+//! `normalize_signature`), then compared with one of three metrics, each
+//! scoring from 0.0 (nothing in common) to 1.0 (identical):
+//!
+//! - `Trigram`: `pg_trgm`-style trigram set overlap (the default)
+//! - `Jaccard`: overlap of the word sets
+//! - `EditDistance`: 1 - Levenshtein distance / length of the longer signature
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::signature::normalize_signature;
+use super::{Category, ErrorPattern};
+
+/// Minimum score for a match, as used by the Central KG
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.3;
+
+/// Most frequent patterns considered by `KgBackend::find_similar`
+pub(super) const MAX_SIMILARITY_CANDIDATES: usize = 2000;
+
+/// Characters compared by `EditDistance` (keeps the quadratic cost bounded)
+const MAX_EDIT_CHARS: usize = 512;
+
+/// How two normalized signatures are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SimilarityMetric {
+    #[default]
+    Trigram,
+    Jaccard,
+    EditDistance,
+}
+
+impl SimilarityMetric {
+    /// Similarity of two already-normalized signatures, 0.0 ..= 1.0
+    pub fn score(&self, a: &str, b: &str) -> f64 {
+        if a == b {
+            return 1.0;
+        }
+        match self {
+            SimilarityMetric::Trigram => set_overlap(&trigrams(a), &trigrams(b)),
+            SimilarityMetric::Jaccard => set_overlap(&word_set(a), &word_set(b)),
+            SimilarityMetric::EditDistance => edit_similarity(a, b),
+        }
+    }
+}
+
+/// A "have we seen something like this?" query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilaritySearch {
+    /// Raw or normalized signature to compare against
+    pub signature: String,
+    pub metric: SimilarityMetric,
+    /// Matches scoring below this are dropped
+    pub threshold: f64,
+    pub limit: usize,
+    pub category: Option<Category>,
+    /// Include patterns marked resolved (their fix is usually what you want)
+    pub include_resolved: bool,
+    /// Only return patterns that have a known solution
+    pub require_solution: bool,
+}
+
+/// A pattern similar to the searched signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarPattern {
+    pub pattern: ErrorPattern,
+    /// Similarity, 0.0 ..= 1.0
+    pub score: f64,
+}
+
+impl SimilaritySearch {
+    /// Trigram similarity at `DEFAULT_SIMILARITY_THRESHOLD`, 5 results
+    pub fn new(signature: impl Into<String>) -> Self {
+        Self {
+            signature: signature.into(),
+            metric: SimilarityMetric::default(),
+            threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            limit: 5,
+            category: None,
+            include_resolved: true,
+            require_solution: false,
+        }
+    }
+
+    pub fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<Category>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn with_include_resolved(mut self, include_resolved: bool) -> Self {
+        self.include_resolved = include_resolved;
+        self
+    }
+
+    pub fn with_require_solution(mut self, require_solution: bool) -> Self {
+        self.require_solution = require_solution;
+        self
+    }
+
+    /// Score and filter `candidates`: most similar first, then patterns with a
+    /// solution, then the most frequent
+    pub fn rank(&self, candidates: Vec<ErrorPattern>) -> Vec<SimilarPattern> {
+        let wanted = normalize_signature(&self.signature).canonical;
+        let mut matches: Vec<SimilarPattern> = candidates
+            .into_iter()
+            .filter(|p| self.include_resolved || p.resolved_at.is_none())
+            .filter(|p| !self.require_solution || p.solution.is_some())
+            .filter(|p| {
+                self.category
+                    .as_ref()
+                    .is_none_or(|category| p.category.as_ref() == Some(category))
+            })
+            .filter_map(|pattern| {
+                let candidate = normalize_signature(&pattern.signature).canonical;
+                let score = self.metric.score(&wanted, &candidate);
+                (score >= self.threshold).then_some(SimilarPattern { pattern, score })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| {
+                    b.pattern
+                        .solution
+                        .is_some()
+                        .cmp(&a.pattern.solution.is_some())
+                })
+                .then_with(|| b.pattern.occurrence_count.cmp(&a.pattern.occurrence_count))
+        });
+        matches.truncate(self.limit);
+        matches
+    }
+}
+
+/// |A ∩ B| / |A ∪ B|; 0.0 when both are empty
+fn set_overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Lowercased alphanumeric words
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    words(text).collect()
+}
+
+/// Trigrams of each word padded with two leading and one trailing space, as
+/// `pg_trgm` does
+fn trigrams(text: &str) -> HashSet<String> {
+    let mut set = HashSet::new();
+    for word in words(text) {
+        let padded: Vec<char> = format!("  {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            set.insert(window.iter().collect());
+        }
+    }
+    set
+}
+
+/// 1 - Levenshtein distance / length of the longer string (in characters)
+fn edit_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().take(MAX_EDIT_CHARS).collect();
+    let b: Vec<char> = b.chars().take(MAX_EDIT_CHARS).collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(signature: &str, occurrences: i64, solution: Option<&str>) -> ErrorPattern {
+        ErrorPattern {
+            id: signature.to_string(),
+            signature: signature.to_string(),
+            category: Some(Category::BorrowCheck),
+            description: None,
+            solution: solution.map(str::to_string),
+            occurrence_count: occurrences,
+            last_seen_at: None,
+            resolved_at: None,
+            solutions: Vec::new(),
+        }
+    }
+
+    fn signatures(matches: &[SimilarPattern]) -> Vec<&str> {
+        matches
+            .iter()
+            .map(|m| m.pattern.signature.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn near_duplicate_ranks_first() {
+        let candidates = vec![
+            pattern("mismatched types: expected `String`, found `&str`", 9, None),
+            pattern("borrow of moved value: `config`", 1, None),
+            pattern(
+                "cannot borrow `self.items` as mutable more than once",
+                4,
+                None,
+            ),
+            pattern("connection refused (os error 111)", 20, None),
+        ];
+        for metric in [
+            SimilarityMetric::Trigram,
+            SimilarityMetric::Jaccard,
+            SimilarityMetric::EditDistance,
+        ] {
+            let matches = SimilaritySearch::new("borrow of moved value: `settings`")
+                .with_metric(metric)
+                .rank(candidates.clone());
+            assert_eq!(
+                matches[0].pattern.signature, "borrow of moved value: `config`",
+                "{:?}",
+                metric
+            );
+            // Backticked names are normalized away, so this one is identical
+            assert_eq!(matches[0].score, 1.0);
+            assert!(!signatures(&matches).contains(&"connection refused (os error 111)"));
+        }
+    }
+
+    #[test]
+    fn scores_range_from_zero_to_one() {
+        for metric in [
+            SimilarityMetric::Trigram,
+            SimilarityMetric::Jaccard,
+            SimilarityMetric::EditDistance,
+        ] {
+            assert_eq!(
+                metric.score("index out of bounds", "index out of bounds"),
+                1.0
+            );
+            let close = metric.score("index out of bounds", "index out of range");
+            let far = metric.score("index out of bounds", "permission denied");
+            assert!(close > far, "{:?}: {} <= {}", metric, close, far);
+            assert!((0.0..1.0).contains(&close));
+            assert!((0.0..1.0).contains(&far));
+        }
+        assert_eq!(SimilarityMetric::Jaccard.score("a b", "c d"), 0.0);
+        assert_eq!(edit_similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+    }
+
+    #[test]
+    fn filters_and_breaks_ties() {
+        let mut resolved = pattern("panicked at src/main.rs: index out of bounds", 50, None);
+        resolved.resolved_at = Some("2024-01-01T00:00:00Z".to_string());
+        let mut other_category = pattern("panicked at src/lib.rs: index out of bounds", 70, None);
+        other_category.category = Some(Category::Panic);
+        let candidates = vec![
+            pattern("panicked at src/a.rs: index out of bounds", 3, None),
+            pattern("panicked at src/b.rs: index out of bounds", 8, None),
+            pattern(
+                "panicked at src/c.rs: index out of bounds",
+                1,
+                Some("check len"),
+            ),
+            resolved,
+            other_category,
+        ];
+
+        // Equal scores: solution first, then the most frequent
+        let search =
+            SimilaritySearch::new("index out of bounds").with_metric(SimilarityMetric::Jaccard);
+        let matches = search.clone().with_limit(10).rank(candidates.clone());
+        assert_eq!(
+            signatures(&matches),
+            [
+                "panicked at src/c.rs: index out of bounds",
+                "panicked at src/lib.rs: index out of bounds",
+                "panicked at src/main.rs: index out of bounds",
+                "panicked at src/b.rs: index out of bounds",
+                "panicked at src/a.rs: index out of bounds",
+            ]
+        );
+
+        let matches = search
+            .clone()
+            .with_category(Category::BorrowCheck)
+            .with_include_resolved(false)
+            .with_limit(2)
+            .rank(candidates.clone());
+        assert_eq!(
+            signatures(&matches),
+            [
+                "panicked at src/c.rs: index out of bounds",
+                "panicked at src/b.rs: index out of bounds",
+            ]
+        );
+
+        let matches = search.with_require_solution(true).rank(candidates);
+        assert_eq!(
+            signatures(&matches),
+            ["panicked at src/c.rs: index out of bounds"]
+        );
+    }
+}
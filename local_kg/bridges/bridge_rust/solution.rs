@@ -0,0 +1,676 @@
+//! `Solution`: a fix recorded for a pattern, built up field by field.
+//!
+//! Carries the fields of the legacy `local_solutions` table and the Central
+//! KG `solutions` table (title, description, code snippet, resolution steps,
+//! time to resolve, outcome). Backends store it as a `solution` event: the
+//! description is the event content and the other fields go into `metadata`
+//! under the same keys the legacy import uses, so solutions recorded by older
+//! tools (text plus `effectiveness` only) read back with derived defaults.
+//!
+//! Each time a solution is applied, `SolutionFeedback` records whether it
+//! helped (a `solution_feedback` event, like the Central KG's
+//! `solution_feedback` table). From that every solution gets a
+//! `success_rate` (helpful / all feedback, as the Central KG computes it) and
+//! a `confidence`: the success rate smoothed towards the author's rating, so
+//! a fresh solution rated 3 starts at 0.5 (the Central KG default) and
+//! feedback takes over as it accumulates. A pattern's solutions are ranked by
+//! confidence, and the best one with confidence of at least 0.75 (a fresh
+//! solution rated 4) is promoted onto the pattern.
+//!
+//! ```ignore
+//! let fix = Solution::new("Clone before moving", "The closure takes ownership of `config`.")
+//!     .with_code_snippet("let config = config.clone();")
+//!     .with_steps(["Clone the value", "Move the clone into the closure"])
+//!     .with_time_to_resolve(5);
+//! kg.record_solution(&pattern.id, fix).await?;
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::KgEvent;
+
+/// Effectiveness assumed when none (or garbage) was recorded
+const NEUTRAL_EFFECTIVENESS: u8 = 3;
+
+/// Effectiveness of a solution that worked (the legacy import's mapping)
+const SUCCESSFUL_EFFECTIVENESS: u8 = 4;
+
+/// Confidence a solution needs to be promoted onto its pattern
+pub(super) const PROMOTE_CONFIDENCE: f64 = 0.75;
+
+/// Pieces of feedback the author's rating counts as
+const RATING_WEIGHT: f64 = 2.0;
+
+/// Characters kept when a title is derived from the description
+const TITLE_CHARS: usize = 80;
+
+/// A fix for an error pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Solution {
+    /// Id of the `solution` event (empty until recorded)
+    #[serde(default)]
+    pub id: String,
+    pub title: String,
+    /// What the fix is (stored as the event content)
+    pub description: String,
+    pub code_snippet: Option<String>,
+    #[serde(default)]
+    pub resolution_steps: Vec<String>,
+    pub time_to_resolve_minutes: Option<u32>,
+    pub was_successful: bool,
+    /// The author's rating, 1 (didn't help) ..= 5 (fixed it)
+    pub effectiveness: u8,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Feedback received: helped / didn't help
+    #[serde(default)]
+    pub helpful_count: u32,
+    #[serde(default)]
+    pub unhelpful_count: u32,
+    /// Share of feedback that said it helped (0.0 without feedback)
+    #[serde(default)]
+    pub success_rate: f64,
+    /// Success rate smoothed towards the effectiveness rating, 0.0 ..= 1.0
+    #[serde(default)]
+    pub confidence: f64,
+}
+
+/// Whether a solution helped, recorded each time it is applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolutionFeedback {
+    pub was_helpful: bool,
+    pub resolution_time_minutes: Option<u32>,
+    pub comment: Option<String>,
+}
+
+impl SolutionFeedback {
+    pub fn helpful() -> Self {
+        Self::new(true)
+    }
+
+    pub fn unhelpful() -> Self {
+        Self::new(false)
+    }
+
+    pub fn new(was_helpful: bool) -> Self {
+        Self {
+            was_helpful,
+            resolution_time_minutes: None,
+            comment: None,
+        }
+    }
+
+    pub fn with_resolution_time(mut self, minutes: u32) -> Self {
+        self.resolution_time_minutes = Some(minutes);
+        self
+    }
+
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Value of the event's `content` column
+    pub(super) fn event_content(&self) -> String {
+        self.comment.clone().unwrap_or_else(|| {
+            if self.was_helpful {
+                "Solution helped".to_string()
+            } else {
+                "Solution did not help".to_string()
+            }
+        })
+    }
+
+    /// The event's `metadata` JSON
+    pub(super) fn event_metadata(&self, solution_id: &str, target_signature: &str) -> Value {
+        let mut meta = Map::new();
+        meta.insert("solution_id".to_string(), json!(solution_id));
+        meta.insert("target_signature".to_string(), json!(target_signature));
+        meta.insert("was_helpful".to_string(), json!(self.was_helpful));
+        if let Some(minutes) = self.resolution_time_minutes {
+            meta.insert("resolution_time_minutes".to_string(), json!(minutes));
+        }
+        if let Some(comment) = &self.comment {
+            meta.insert("comment".to_string(), json!(comment));
+        }
+        Value::Object(meta)
+    }
+}
+
+impl Solution {
+    /// A successful fix (effectiveness 4, enough to be promoted onto the pattern)
+    pub fn new(title: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            id: String::new(),
+            title: title.into(),
+            description: description.into(),
+            code_snippet: None,
+            resolution_steps: Vec::new(),
+            time_to_resolve_minutes: None,
+            was_successful: true,
+            effectiveness: SUCCESSFUL_EFFECTIVENESS,
+            created_at: None,
+            helpful_count: 0,
+            unhelpful_count: 0,
+            success_rate: 0.0,
+            confidence: rating_confidence(SUCCESSFUL_EFFECTIVENESS),
+        }
+    }
+
+    /// Plain solution text as passed to `add_solution`; the title is the first
+    /// line of `text` and it counts as successful unless rated below neutral
+    pub fn from_text(text: impl Into<String>, effectiveness: u8) -> Self {
+        let description = text.into();
+        let effectiveness = effectiveness.clamp(1, 5);
+        Self {
+            title: title_from(&description),
+            was_successful: effectiveness >= NEUTRAL_EFFECTIVENESS,
+            effectiveness,
+            confidence: rating_confidence(effectiveness),
+            ..Self::new("", description)
+        }
+    }
+
+    pub fn with_code_snippet(mut self, code_snippet: impl Into<String>) -> Self {
+        self.code_snippet = Some(code_snippet.into());
+        self
+    }
+
+    pub fn with_step(mut self, step: impl Into<String>) -> Self {
+        self.resolution_steps.push(step.into());
+        self
+    }
+
+    pub fn with_steps<I, T>(mut self, steps: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.resolution_steps
+            .extend(steps.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_time_to_resolve(mut self, minutes: u32) -> Self {
+        self.time_to_resolve_minutes = Some(minutes);
+        self
+    }
+
+    /// Record the outcome. Also sets effectiveness the way the legacy import
+    /// does (4 if it worked, 1 if not); call `with_effectiveness` afterwards
+    /// to rate it differently.
+    pub fn with_successful(mut self, was_successful: bool) -> Self {
+        self.was_successful = was_successful;
+        let effectiveness = if was_successful {
+            SUCCESSFUL_EFFECTIVENESS
+        } else {
+            1
+        };
+        self.with_effectiveness(effectiveness)
+    }
+
+    /// Rating from 1 to 5 (clamped)
+    pub fn with_effectiveness(mut self, effectiveness: u8) -> Self {
+        self.effectiveness = effectiveness.clamp(1, 5);
+        self.update_scores();
+        self
+    }
+
+    /// Whether the solution is good enough to be promoted onto its pattern
+    /// (if it also ranks first)
+    pub fn is_promotable(&self) -> bool {
+        self.confidence >= PROMOTE_CONFIDENCE
+    }
+
+    /// Count one piece of feedback
+    pub(super) fn apply_feedback(&mut self, was_helpful: bool) {
+        if was_helpful {
+            self.helpful_count += 1;
+        } else {
+            self.unhelpful_count += 1;
+        }
+        self.update_scores();
+    }
+
+    fn update_scores(&mut self) {
+        let helpful = f64::from(self.helpful_count);
+        let total = helpful + f64::from(self.unhelpful_count);
+        self.success_rate = if total > 0.0 { helpful / total } else { 0.0 };
+        self.confidence = (helpful + RATING_WEIGHT * rating_confidence(self.effectiveness))
+            / (total + RATING_WEIGHT);
+    }
+
+    /// The event's `metadata` JSON. Optional fields are only written when set.
+    pub(super) fn event_metadata(&self, target_signature: &str) -> Value {
+        let mut meta = Map::new();
+        meta.insert("effectiveness".to_string(), json!(self.effectiveness));
+        meta.insert("target_signature".to_string(), json!(target_signature));
+        meta.insert("title".to_string(), json!(self.title));
+        meta.insert("was_successful".to_string(), json!(self.was_successful));
+
+        let optional = [
+            ("code_snippet", self.code_snippet.as_ref().map(|v| json!(v))),
+            (
+                "resolution_steps",
+                Some(json!(self.resolution_steps)).filter(|_| !self.resolution_steps.is_empty()),
+            ),
+            (
+                "time_to_resolve_minutes",
+                self.time_to_resolve_minutes.map(|v| json!(v)),
+            ),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                meta.insert(key.to_string(), value);
+            }
+        }
+        Value::Object(meta)
+    }
+
+    /// Read a `solution` event back, filling in what older writers left out
+    pub(super) fn from_event(event: &KgEvent) -> Self {
+        let meta = &event.metadata;
+        let description = event.content.clone().unwrap_or_default();
+        let effectiveness = match &meta["effectiveness"] {
+            Value::String(s) => parse_effectiveness(s),
+            value => value
+                .as_i64()
+                .map_or(NEUTRAL_EFFECTIVENESS, |e| e.clamp(1, 5) as u8),
+        };
+        let resolution_steps = match &meta["resolution_steps"] {
+            Value::Array(steps) => steps
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            // Legacy rows hold the steps as one block of text
+            Value::String(text) => text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let mut solution = Self {
+            id: event.id.clone(),
+            title: meta["title"]
+                .as_str()
+                .map_or_else(|| title_from(&description), String::from),
+            code_snippet: meta["code_snippet"].as_str().map(String::from),
+            resolution_steps,
+            time_to_resolve_minutes: meta["time_to_resolve_minutes"]
+                .as_u64()
+                .and_then(|m| u32::try_from(m).ok()),
+            was_successful: meta["was_successful"]
+                .as_bool()
+                .unwrap_or(effectiveness >= NEUTRAL_EFFECTIVENESS),
+            effectiveness,
+            created_at: Some(event.created_at.clone()),
+            description,
+            helpful_count: 0,
+            unhelpful_count: 0,
+            success_rate: 0.0,
+            confidence: 0.0,
+        };
+        solution.update_scores();
+        solution
+    }
+}
+
+/// Solutions per target signature built from `solution` and
+/// `solution_feedback` events (in any order), each list ranked best first
+pub(super) fn solutions_from_events<'a>(
+    events: impl IntoIterator<Item = &'a KgEvent>,
+) -> HashMap<String, Vec<Solution>> {
+    let mut solutions: Vec<(String, Solution)> = Vec::new();
+    let mut feedback: Vec<(&str, bool)> = Vec::new();
+    for event in events {
+        let meta = &event.metadata;
+        match event.event_type.as_str() {
+            "solution" => {
+                if let Some(target) = meta["target_signature"].as_str() {
+                    solutions.push((target.to_string(), Solution::from_event(event)));
+                }
+            }
+            "solution_feedback" => {
+                if let (Some(id), Some(helpful)) =
+                    (meta["solution_id"].as_str(), meta["was_helpful"].as_bool())
+                {
+                    feedback.push((id, helpful));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let positions: HashMap<String, usize> = solutions
+        .iter()
+        .enumerate()
+        .map(|(i, (_, s))| (s.id.clone(), i))
+        .collect();
+    for (id, helpful) in feedback {
+        if let Some(&i) = positions.get(id) {
+            solutions[i].1.apply_feedback(helpful);
+        }
+    }
+
+    let mut by_signature: HashMap<String, Vec<Solution>> = HashMap::new();
+    for (target, solution) in solutions {
+        by_signature.entry(target).or_default().push(solution);
+    }
+    for list in by_signature.values_mut() {
+        rank_solutions(list);
+    }
+    by_signature
+}
+
+/// Best first: highest confidence, then success rate, then most helpful
+/// feedback, then newest. `solutions` must be in recording order.
+pub(super) fn rank_solutions(solutions: &mut [Solution]) {
+    // Reversed first so that, of otherwise equal solutions, the later one wins
+    solutions.reverse();
+    solutions.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then(b.success_rate.total_cmp(&a.success_rate))
+            .then(b.helpful_count.cmp(&a.helpful_count))
+            .then_with(|| b.created_at.cmp(&a.created_at))
+    });
+}
+
+/// What the pattern's promoted `solution` should be given its ranked
+/// solutions: the best one if it qualifies. Otherwise a promoted text that
+/// came from one of them is withdrawn, and one set some other way is kept.
+pub(super) fn promoted_solution(current: Option<&str>, ranked: &[Solution]) -> Option<String> {
+    match ranked.first().filter(|best| best.is_promotable()) {
+        Some(best) => Some(best.description.clone()),
+        None if ranked
+            .iter()
+            .any(|s| Some(s.description.as_str()) == current) =>
+        {
+            None
+        }
+        None => current.map(String::from),
+    }
+}
+
+/// Effectiveness arrives as a string from the string-based API; anything that
+/// isn't a number counts as neutral
+pub(super) fn parse_effectiveness(value: &str) -> u8 {
+    value
+        .trim()
+        .parse::<i64>()
+        .map_or(NEUTRAL_EFFECTIVENESS, |e| e.clamp(1, 5) as u8)
+}
+
+/// Confidence implied by a 1-5 rating alone: 1 -> 0.0, 3 -> 0.5, 5 -> 1.0
+fn rating_confidence(effectiveness: u8) -> f64 {
+    (f64::from(effectiveness.clamp(1, 5)) - 1.0) / 4.0
+}
+
+/// First non-empty line of `text`, shortened to `TITLE_CHARS`
+fn title_from(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+    if line.chars().count() <= TITLE_CHARS {
+        return line.to_string();
+    }
+    let mut title: String = line.chars().take(TITLE_CHARS - 1).collect();
+    title.push('…');
+    title
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, event_type: &str, content: &str, metadata: Value) -> KgEvent {
+        KgEvent {
+            id: id.to_string(),
+            event_type: event_type.to_string(),
+            phase: Some("UNKNOWN".to_string()),
+            content: Some(content.to_string()),
+            metadata,
+            created_at: "2024-05-01T10:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_event_metadata() {
+        let solution = Solution::new("Clone before moving", "The closure takes ownership.")
+            .with_code_snippet("let config = config.clone();")
+            .with_steps(["Clone the value", "Move the clone"])
+            .with_time_to_resolve(5);
+        let metadata = solution.event_metadata("borrow of moved value: `config`");
+        assert_eq!(
+            metadata["target_signature"],
+            "borrow of moved value: `config`"
+        );
+        assert_eq!(metadata["effectiveness"], 4);
+
+        let read = Solution::from_event(&event("s1", "solution", &solution.description, metadata));
+        assert_eq!(read.id, "s1");
+        assert_eq!(read.created_at.as_deref(), Some("2024-05-01T10:00:00Z"));
+        assert_eq!(
+            read,
+            Solution {
+                id: read.id.clone(),
+                created_at: read.created_at.clone(),
+                ..solution
+            }
+        );
+
+        // Unset optional fields aren't written
+        let metadata = Solution::new("t", "d").event_metadata("sig");
+        assert!(metadata.get("code_snippet").is_none());
+        assert!(metadata.get("resolution_steps").is_none());
+        assert!(metadata.get("time_to_resolve_minutes").is_none());
+    }
+
+    #[test]
+    fn reads_legacy_solution_events() {
+        // As written by the Python `add_solution` and the legacy import
+        let read = Solution::from_event(&event(
+            "s1",
+            "solution",
+            "\n  Add a lifetime parameter to the struct\nand to its impl block",
+            json!({
+                "target_signature": "missing lifetime specifier",
+                "effectiveness": " 5 ",
+                "resolution_steps": "1. add <'a>\n\n   2. thread it through\n",
+            }),
+        ));
+        assert_eq!(read.title, "Add a lifetime parameter to the struct");
+        assert_eq!(read.effectiveness, 5);
+        assert!(read.was_successful);
+        assert_eq!(
+            read.resolution_steps,
+            ["1. add <'a>", "2. thread it through"]
+        );
+        assert_eq!(read.code_snippet, None);
+        assert_eq!(read.time_to_resolve_minutes, None);
+
+        for (effectiveness, expected) in [
+            (json!("high"), 3),
+            (json!("0"), 1),
+            (json!(9), 5),
+            (json!(2), 2),
+            (Value::Null, 3),
+        ] {
+            let read = Solution::from_event(&event(
+                "s2",
+                "solution",
+                "text",
+                json!({ "effectiveness": effectiveness }),
+            ));
+            assert_eq!(read.effectiveness, expected, "{}", effectiveness);
+            assert_eq!(read.was_successful, expected >= 3);
+        }
+    }
+
+    #[test]
+    fn derives_title_and_outcome_from_text() {
+        let solution = Solution::from_text("Use `Arc<Mutex<_>>`\nso both threads share it", 2);
+        assert_eq!(solution.title, "Use `Arc<Mutex<_>>`");
+        assert!(!solution.was_successful);
+        assert_eq!(Solution::from_text("x", 0).effectiveness, 1);
+
+        let long = "é".repeat(100);
+        let title = Solution::from_text(long, 3).title;
+        assert_eq!(title.chars().count(), TITLE_CHARS);
+        assert!(title.ends_with('…'));
+
+        let failed = Solution::new("t", "d").with_successful(false);
+        assert_eq!(failed.effectiveness, 1);
+        assert_eq!(failed.with_effectiveness(7).effectiveness, 5);
+    }
+
+    fn rated(id: &str, effectiveness: u8, created_at: Option<&str>) -> Solution {
+        Solution {
+            id: id.to_string(),
+            created_at: created_at.map(String::from),
+            ..Solution::from_text(id, effectiveness)
+        }
+    }
+
+    fn ids(solutions: &[Solution]) -> Vec<&str> {
+        solutions.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn confidence_starts_from_the_rating() {
+        assert_eq!(Solution::from_text("x", 3).confidence, 0.5);
+        assert_eq!(Solution::from_text("x", 1).confidence, 0.0);
+        assert_eq!(Solution::from_text("x", 5).confidence, 1.0);
+        let fresh = Solution::new("t", "d");
+        assert_eq!(fresh.confidence, PROMOTE_CONFIDENCE);
+        assert!(fresh.is_promotable());
+        assert!(!Solution::from_text("x", 3).is_promotable());
+
+        let read = Solution::from_event(&event(
+            "s1",
+            "solution",
+            "text",
+            json!({ "effectiveness": "3" }),
+        ));
+        assert_eq!(read.confidence, 0.5);
+        assert_eq!(read.success_rate, 0.0);
+
+        // Feedback takes over as it accumulates
+        let mut solution = Solution::from_text("x", 5);
+        for _ in 0..8 {
+            solution.apply_feedback(false);
+        }
+        assert_eq!(solution.success_rate, 0.0);
+        assert_eq!(solution.confidence, 0.2);
+    }
+
+    #[test]
+    fn feedback_flips_the_ranking() {
+        let feedback = |solution_id: &str, helpful: bool| {
+            event(
+                "f",
+                "solution_feedback",
+                "",
+                json!({ "solution_id": solution_id, "was_helpful": helpful }),
+            )
+        };
+        let solution = |id: &str, effectiveness: u8| {
+            event(
+                id,
+                "solution",
+                id,
+                json!({ "target_signature": "sig", "effectiveness": effectiveness }),
+            )
+        };
+
+        let mut events = vec![solution("s1", 5), solution("s2", 3)];
+        let ranked = &solutions_from_events(&events)["sig"];
+        assert_eq!(ids(ranked), ["s1", "s2"]);
+
+        // Feedback may be read before the solution it is about
+        events.splice(
+            0..0,
+            [
+                feedback("s1", false),
+                feedback("s2", true),
+                feedback("missing", true),
+            ],
+        );
+        for _ in 0..2 {
+            events.extend([feedback("s1", false), feedback("s2", true)]);
+        }
+        let ranked = &solutions_from_events(&events)["sig"];
+        assert_eq!(ids(ranked), ["s2", "s1"]);
+        assert_eq!((ranked[0].helpful_count, ranked[0].unhelpful_count), (3, 0));
+        assert_eq!(ranked[0].success_rate, 1.0);
+        assert_eq!(ranked[0].confidence, 0.8);
+        assert_eq!(ranked[1].confidence, 0.4);
+    }
+
+    #[test]
+    fn promotes_only_a_confident_best_solution() {
+        let mut ranked = vec![rated("works", 5, None), rated("maybe", 3, None)];
+        rank_solutions(&mut ranked);
+        assert_eq!(promoted_solution(None, &ranked).as_deref(), Some("works"));
+
+        // The promoted solution stops helping: below the threshold it is withdrawn
+        for _ in 0..2 {
+            ranked[0].apply_feedback(false);
+        }
+        assert!(ranked[0].confidence < PROMOTE_CONFIDENCE);
+        rank_solutions(&mut ranked);
+        assert_eq!(promoted_solution(Some("works"), &ranked), None);
+
+        // Text promoted some other way (e.g. by the Python tooling) is kept
+        assert_eq!(
+            promoted_solution(Some("from elsewhere"), &ranked).as_deref(),
+            Some("from elsewhere")
+        );
+        assert_eq!(
+            promoted_solution(Some("kept"), &[]).as_deref(),
+            Some("kept")
+        );
+    }
+
+    #[test]
+    fn breaks_confidence_ties() {
+        let mut success = rated("success", 3, None);
+        success.apply_feedback(true);
+        success.apply_feedback(false);
+        let mut more_helpful = rated("more_helpful", 3, None);
+        for helpful in [true, true, false, false] {
+            more_helpful.apply_feedback(helpful);
+        }
+        assert_eq!(success.confidence, more_helpful.confidence);
+
+        let mut ranked = vec![
+            rated("older", 3, Some("2024-05-01T10:00:00Z")),
+            rated("first", 3, None),
+            rated("newer", 3, Some("2024-05-02T10:00:00Z")),
+            rated("second", 3, None),
+            success,
+            more_helpful,
+        ];
+        rank_solutions(&mut ranked);
+        assert_eq!(
+            ids(&ranked),
+            [
+                "more_helpful",
+                "success",
+                "newer",
+                "older",
+                "second",
+                "first"
+            ]
+        );
+    }
+}
@@ -0,0 +1,859 @@
+//! Native SQLite store for the Universal Schema.
+//!
+//! Reads and writes the same `events` / `patterns` / `project_info` tables as
+//! `local_kg/local_kg.py`, so a Rust process can capture errors without
+//! starting a Python interpreter.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use super::backend::KgBackend;
+use super::migrations;
+use super::resolution::check_same_pattern;
+use super::schema;
+use super::search::{COLUMN_WEIGHTS, SNIPPET_TOKENS};
+use super::solution::{promoted_solution, solutions_from_events};
+use super::{
+    Category, ErrorCapture, ErrorPattern, ErrorResolution, Fix, KgEvent, NebulaKgError, OutboxItem,
+    OutboxStatus, OutboxUpdate, PatternSearch, PatternSummary, ResolutionStats, SearchHit,
+    Solution, SolutionFeedback, SyncRecord, SyncStatus, SyncType, UnsyncedSolution,
+};
+
+/// Pattern columns in `ErrorPattern` field order
+const PATTERN_SELECT: &str = "SELECT id, signature, category, description, solution, \
+     occurrence_count, last_seen_at, resolved_at FROM patterns";
+
+/// Event columns in `KgEvent` field order (see `event_from_row`)
+const EVENT_SELECT: &str = "SELECT id, type, phase, content, metadata, created_at FROM events";
+
+/// `metadata`, or NULL if it isn't valid JSON (so `json_extract` can't fail)
+const META: &str = "CASE WHEN json_valid(metadata) THEN metadata END";
+
+/// `error` events that no `resolve_error` resolution points at
+fn open_error_filter() -> String {
+    format!(
+        "type = 'error' AND id NOT IN (
+             SELECT json_extract({META}, '$.error_event_id') FROM events
+             WHERE type = 'resolution' AND json_extract({META}, '$.error_event_id') IS NOT NULL)"
+    )
+}
+
+/// Ids of type `?1` with a successful sync record or already in the outbox
+const SYNCED_OR_QUEUED_IDS: &str =
+    "SELECT local_id FROM sync_history WHERE sync_type = ?1 AND sync_status = 'success'
+     UNION SELECT local_id FROM sync_outbox WHERE sync_type = ?1";
+
+/// Outbox columns in `OutboxItem` field order (see `outbox_from_row`)
+const OUTBOX_SELECT: &str = "SELECT id, sync_type, local_id, idempotency_key, payload, status, \
+     attempts, next_attempt_at, last_error, central_id, created_at FROM sync_outbox";
+
+/// Direct SQLite access to a Universal Schema database.
+pub struct SqliteBackend {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    /// Open (or create) the database at `db_path` and apply pending migrations.
+    /// Fails with `SchemaMismatch` if the file holds an incompatible schema.
+    pub fn open(db_path: &str) -> Result<Self, NebulaKgError> {
+        if let Some(parent) = Path::new(db_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mut conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        migrations::run_migrations(&mut conn)?;
+        schema::verify(&conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+/// `None` for rows of a type this bridge doesn't write
+fn outbox_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Option<OutboxItem>> {
+    let Ok(sync_type) = row.get::<_, String>(1)?.parse() else {
+        return Ok(None);
+    };
+    let payload: String = row.get(4)?;
+    Ok(Some(OutboxItem {
+        id: row.get(0)?,
+        sync_type,
+        local_id: row.get(2)?,
+        idempotency_key: row.get(3)?,
+        payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+        status: OutboxStatus::parse_lenient(&row.get::<_, String>(5)?),
+        attempts: row.get(6)?,
+        next_attempt_at: row.get(7)?,
+        last_error: row.get(8)?,
+        central_id: row.get(9)?,
+        created_at: row.get(10)?,
+    }))
+}
+
+/// `query` with LIKE wildcards (`%`, `_`) and the escape character itself
+/// escaped, so it matches literally under `ESCAPE '\'`
+fn escape_like(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn pattern_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ErrorPattern> {
+    Ok(ErrorPattern {
+        id: row.get(0)?,
+        signature: row.get(1)?,
+        category: row
+            .get::<_, Option<String>>(2)?
+            .map(|c| Category::parse_lenient(&c)),
+        description: row.get(3)?,
+        solution: row.get(4)?,
+        occurrence_count: row.get::<_, Option<i64>>(5)?.unwrap_or(1),
+        last_seen_at: row.get(6)?,
+        resolved_at: row.get(7)?,
+        solutions: Vec::new(),
+    })
+}
+
+/// `SELECT id, type, phase, content, metadata, created_at` row as an event
+fn event_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<KgEvent> {
+    let metadata: Option<String> = row.get(4)?;
+    Ok(KgEvent {
+        id: row.get(0)?,
+        event_type: row.get(1)?,
+        phase: row.get(2)?,
+        content: row.get(3)?,
+        metadata: metadata
+            .and_then(|m| serde_json::from_str(&m).ok())
+            .unwrap_or(Value::Null),
+        created_at: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+    })
+}
+
+/// Ranked solutions (with their feedback) for each of `signatures`
+fn load_solutions(
+    conn: &Connection,
+    signatures: &[&str],
+) -> Result<HashMap<String, Vec<Solution>>, NebulaKgError> {
+    if signatures.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, type, phase, content, metadata, created_at FROM events
+         WHERE type IN ('solution', 'solution_feedback') AND json_valid(metadata)
+           AND json_extract(metadata, '$.target_signature') IN (SELECT value FROM json_each(?1))
+         ORDER BY created_at, rowid",
+    )?;
+    let events = stmt
+        .query_map(params![json!(signatures).to_string()], event_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(solutions_from_events(&events))
+}
+
+/// Fill in `solutions` for each pattern
+fn attach_solutions<'a>(
+    conn: &Connection,
+    patterns: impl IntoIterator<Item = &'a mut ErrorPattern>,
+) -> Result<(), NebulaKgError> {
+    let mut patterns: Vec<&mut ErrorPattern> = patterns.into_iter().collect();
+    let signatures: Vec<&str> = patterns.iter().map(|p| p.signature.as_str()).collect();
+    let solutions = load_solutions(conn, &signatures)?;
+    for pattern in patterns.iter_mut() {
+        if let Some(list) = solutions.get(&pattern.signature) {
+            pattern.solutions = list.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Insert a `solution` event for `signature` and re-rank. Returns the event id.
+fn insert_solution(
+    conn: &Connection,
+    signature: &str,
+    solution: &Solution,
+) -> Result<String, NebulaKgError> {
+    let event_id = uuid::Uuid::new_v4().to_string();
+    let meta = solution.event_metadata(signature);
+    conn.execute(
+        "INSERT INTO events (id, type, phase, content, metadata)
+         VALUES (?1, 'solution', 'UNKNOWN', ?2, ?3)",
+        params![event_id, solution.description, meta.to_string()],
+    )?;
+    promote_best(conn, signature)?;
+    Ok(event_id)
+}
+
+/// Signature of the pattern the solution event `solution_id` was recorded for
+fn solution_signature(conn: &Connection, solution_id: &str) -> Result<String, NebulaKgError> {
+    conn.query_row(
+        "SELECT json_extract(metadata, '$.target_signature') FROM events
+         WHERE id = ?1 AND type = 'solution' AND json_valid(metadata)",
+        params![solution_id],
+        |row| row.get::<_, Option<String>>(0),
+    )
+    .optional()?
+    .flatten()
+    .ok_or_else(|| NebulaKgError::NotFound(format!("solution {}", solution_id)))
+}
+
+/// Insert a `solution_feedback` event and re-rank. Returns the event id.
+fn insert_feedback(
+    conn: &Connection,
+    solution_id: &str,
+    feedback: &SolutionFeedback,
+) -> Result<String, NebulaKgError> {
+    let signature = solution_signature(conn, solution_id)?;
+
+    let event_id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO events (id, type, phase, content, metadata)
+         VALUES (?1, 'solution_feedback', 'UNKNOWN', ?2, ?3)",
+        params![
+            event_id,
+            feedback.event_content(),
+            feedback.event_metadata(solution_id, &signature).to_string()
+        ],
+    )?;
+    promote_best(conn, &signature)?;
+    Ok(event_id)
+}
+
+/// Re-derive the pattern's promoted `solution` from its ranked solutions
+fn promote_best(conn: &Connection, signature: &str) -> Result<(), NebulaKgError> {
+    let ranked = load_solutions(conn, &[signature])?
+        .remove(signature)
+        .unwrap_or_default();
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT solution FROM patterns WHERE signature = ?1",
+            params![signature],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let promoted = promoted_solution(current.as_deref(), &ranked);
+    if promoted != current {
+        conn.execute(
+            "UPDATE patterns SET solution = ?1 WHERE signature = ?2",
+            params![promoted, signature],
+        )?;
+    }
+    Ok(())
+}
+
+impl KgBackend for SqliteBackend {
+    fn capture(&self, capture: &ErrorCapture) -> Result<String, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+
+        let event_id = uuid::Uuid::new_v4().to_string();
+        let meta = capture.event_metadata();
+        let content = capture.event_content();
+
+        tx.execute(
+            "INSERT INTO events (id, type, phase, content, metadata)
+             VALUES (?1, 'error', ?2, ?3, ?4)",
+            params![event_id, capture.event_phase(), content, meta.to_string()],
+        )?;
+
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT id FROM patterns WHERE signature = ?1",
+                params![capture.signature],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match existing {
+            Some(pattern_id) => {
+                tx.execute(
+                    "UPDATE patterns
+                     SET occurrence_count = occurrence_count + 1,
+                         last_seen_at = CURRENT_TIMESTAMP,
+                         resolved_at = NULL
+                     WHERE id = ?1",
+                    params![pattern_id],
+                )?;
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO patterns (id, signature, category, description, occurrence_count)
+                     VALUES (?1, ?2, ?3, ?4, 1)",
+                    params![
+                        uuid::Uuid::new_v4().to_string(),
+                        capture.signature,
+                        capture.category.as_str(),
+                        capture.description
+                    ],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(event_id)
+    }
+
+    fn search_patterns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let sql = format!(
+            "{} WHERE signature LIKE ?1 ESCAPE '\\' OR category LIKE ?1 ESCAPE '\\'
+                OR description LIKE ?1 ESCAPE '\\'
+             ORDER BY occurrence_count DESC LIMIT ?2",
+            PATTERN_SELECT
+        );
+        let like = format!("%{}%", escape_like(query));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut patterns = stmt
+            .query_map(params![like, limit as i64], pattern_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_solutions(&conn, &mut patterns)?;
+
+        Ok(patterns)
+    }
+
+    fn search(&self, search: &PatternSearch) -> Result<Vec<SearchHit>, NebulaKgError> {
+        let Some(expression) = search.match_expression() else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn.lock()?;
+        let [signature, description, solution] = COLUMN_WEIGHTS;
+        let bm25 = format!(
+            "bm25(patterns_fts, 0.0, {:?}, {:?}, {:?})",
+            signature, description, solution
+        );
+        let sql = format!(
+            "SELECT p.id, p.signature, p.category, p.description, p.solution,
+                    p.occurrence_count, p.last_seen_at, p.resolved_at,
+                    -{bm25}, snippet(patterns_fts, -1, ?1, ?2, '…', {tokens})
+             FROM patterns_fts JOIN patterns p ON p.id = patterns_fts.pattern_id
+             WHERE patterns_fts MATCH ?3
+               AND (?4 IS NULL OR p.category = ?4)
+               AND (?5 OR p.resolved_at IS NULL)
+             ORDER BY {bm25} LIMIT ?6",
+            bm25 = bm25,
+            tokens = SNIPPET_TOKENS,
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut hits = stmt
+            .query_map(
+                params![
+                    search.highlight.0,
+                    search.highlight.1,
+                    expression,
+                    search.category.as_ref().map(Category::as_str),
+                    search.include_resolved,
+                    search.limit as i64
+                ],
+                |row| {
+                    Ok(SearchHit {
+                        pattern: pattern_from_row(row)?,
+                        score: row.get(8)?,
+                        snippet: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_solutions(&conn, hits.iter_mut().map(|hit| &mut hit.pattern))?;
+
+        Ok(hits)
+    }
+
+    /// The best-ranked solution is promoted onto the pattern, as in `LocalKG.add_solution`
+    fn record_solution(
+        &self,
+        pattern_id: &str,
+        solution: &Solution,
+    ) -> Result<String, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+
+        let signature: String = tx
+            .query_row(
+                "SELECT signature FROM patterns WHERE id = ?1 OR signature = ?1",
+                params![pattern_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| NebulaKgError::NotFound(format!("pattern {}", pattern_id)))?;
+
+        let event_id = insert_solution(&tx, &signature, solution)?;
+
+        tx.commit()?;
+        Ok(event_id)
+    }
+
+    fn record_feedback(
+        &self,
+        solution_id: &str,
+        feedback: &SolutionFeedback,
+    ) -> Result<String, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        let event_id = insert_feedback(&tx, solution_id, feedback)?;
+        tx.commit()?;
+        Ok(event_id)
+    }
+
+    /// Resolving an occurrence twice returns the first resolution
+    fn resolve_error(
+        &self,
+        error_event_id: &str,
+        fix: &Fix,
+    ) -> Result<ErrorResolution, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+
+        let existing = tx
+            .query_row(
+                &format!(
+                    "{} WHERE type = 'resolution'
+                       AND json_extract({}, '$.error_event_id') = ?1",
+                    EVENT_SELECT, META
+                ),
+                params![error_event_id],
+                event_from_row,
+            )
+            .optional()?;
+        if let Some(resolution) = existing.as_ref().and_then(ErrorResolution::from_event) {
+            return Ok(resolution);
+        }
+
+        let (signature, minutes): (String, i64) = tx
+            .query_row(
+                &format!(
+                    "SELECT json_extract({}, '$.signature'),
+                            CAST(MAX(0, (julianday('now') - julianday(created_at)) * 1440) AS INTEGER)
+                     FROM events WHERE id = ?1 AND type = 'error'",
+                    META
+                ),
+                params![error_event_id],
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .and_then(|(signature, minutes)| Some((signature?, minutes)))
+            .ok_or_else(|| NebulaKgError::NotFound(format!("error event {}", error_event_id)))?;
+        let minutes = u32::try_from(minutes).unwrap_or(u32::MAX);
+
+        let solution_id = match fix {
+            Fix::New(solution) => {
+                let mut solution = solution.clone();
+                solution.time_to_resolve_minutes.get_or_insert(minutes);
+                insert_solution(&tx, &signature, &solution)?
+            }
+            Fix::Existing(solution_id) => {
+                check_same_pattern(
+                    solution_id,
+                    &solution_signature(&tx, solution_id)?,
+                    &signature,
+                )?;
+                let feedback = SolutionFeedback::helpful().with_resolution_time(minutes);
+                insert_feedback(&tx, solution_id, &feedback)?;
+                solution_id.clone()
+            }
+        };
+
+        let event_id = uuid::Uuid::new_v4().to_string();
+        let meta = json!({
+            "error_event_id": error_event_id,
+            "solution_id": solution_id,
+            "target_signature": signature,
+            "time_to_resolve_minutes": minutes,
+        });
+        tx.execute(
+            "INSERT INTO events (id, type, phase, content, metadata)
+             VALUES (?1, 'resolution', 'UNKNOWN', ?2, ?3)",
+            params![
+                event_id,
+                format!("Resolved in {} min", minutes),
+                meta.to_string()
+            ],
+        )?;
+
+        // The pattern is resolved once none of its occurrences are open
+        let still_open: i64 = tx.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM events
+                 WHERE {} AND json_extract({}, '$.signature') = ?1",
+                open_error_filter(),
+                META
+            ),
+            params![signature],
+            |row| row.get(0),
+        )?;
+        if still_open == 0 {
+            tx.execute(
+                "UPDATE patterns SET resolved_at = CURRENT_TIMESTAMP
+                 WHERE signature = ?1 AND resolved_at IS NULL",
+                params![signature],
+            )?;
+        }
+
+        let resolved_at: String = tx.query_row(
+            "SELECT created_at FROM events WHERE id = ?1",
+            params![event_id],
+            |row| row.get(0),
+        )?;
+        tx.commit()?;
+
+        Ok(ErrorResolution {
+            event_id,
+            error_event_id: error_event_id.to_string(),
+            signature,
+            solution_id,
+            time_to_resolve_minutes: minutes,
+            resolved_at,
+        })
+    }
+
+    fn open_errors(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE {} ORDER BY created_at DESC, rowid DESC LIMIT ?1",
+            EVENT_SELECT,
+            open_error_filter()
+        ))?;
+        let events = stmt
+            .query_map(params![limit as i64], event_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+
+    fn resolution_stats(&self) -> Result<ResolutionStats, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let stats = conn.query_row(
+            &format!(
+                "SELECT (SELECT COUNT(*) FROM events WHERE {open}),
+                        COUNT(*),
+                        AVG(json_extract({meta}, '$.time_to_resolve_minutes'))
+                 FROM events
+                 WHERE type = 'resolution' AND json_extract({meta}, '$.error_event_id') IS NOT NULL",
+                open = open_error_filter(),
+                meta = META
+            ),
+            [],
+            |row| {
+                Ok(ResolutionStats {
+                    open_errors: row.get(0)?,
+                    resolved_errors: row.get(1)?,
+                    mean_time_to_fix_minutes: row.get(2)?,
+                })
+            },
+        )?;
+        Ok(stats)
+    }
+
+    fn resolve_pattern(
+        &self,
+        signature: &str,
+        content: &str,
+        context: &Value,
+    ) -> Result<Option<String>, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+
+        let updated = tx.execute(
+            "UPDATE patterns SET resolved_at = CURRENT_TIMESTAMP
+             WHERE signature = ?1 AND resolved_at IS NULL",
+            params![signature],
+        )?;
+        if updated == 0 {
+            return Ok(None);
+        }
+
+        let event_id = uuid::Uuid::new_v4().to_string();
+        let meta = json!({
+            "target_signature": signature,
+            "context": context,
+        });
+        tx.execute(
+            "INSERT INTO events (id, type, phase, content, metadata)
+             VALUES (?1, 'resolution', 'UNKNOWN', ?2, ?3)",
+            params![event_id, content, meta.to_string()],
+        )?;
+
+        tx.commit()?;
+        Ok(Some(event_id))
+    }
+
+    fn get_summary(&self) -> Result<PatternSummary, NebulaKgError> {
+        let conn = self.conn.lock()?;
+
+        let (total_patterns, patterns_with_solution) = conn.query_row(
+            "SELECT COUNT(*), COUNT(solution) FROM patterns",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let (total_errors, total_solutions) = conn.query_row(
+            "SELECT COUNT(*) FILTER (WHERE type = 'error'),
+                    COUNT(*) FILTER (WHERE type = 'solution')
+             FROM events",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "{} ORDER BY occurrence_count DESC LIMIT 10",
+            PATTERN_SELECT
+        ))?;
+        let mut top_patterns = stmt
+            .query_map([], pattern_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_solutions(&conn, &mut top_patterns)?;
+
+        Ok(PatternSummary {
+            total_patterns,
+            total_errors,
+            total_solutions,
+            patterns_with_solution,
+            top_patterns,
+        })
+    }
+
+    fn record_event(
+        &self,
+        event_type: &str,
+        phase: &str,
+        content: &str,
+        metadata: &Value,
+    ) -> Result<String, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let event_id = uuid::Uuid::new_v4().to_string();
+
+        conn.execute(
+            "INSERT INTO events (id, type, phase, content, metadata) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![event_id, event_type, phase, content, metadata.to_string()],
+        )?;
+
+        Ok(event_id)
+    }
+
+    fn recent_events(&self, limit: usize) -> Result<Vec<KgEvent>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} ORDER BY created_at DESC, rowid DESC LIMIT ?1",
+            EVENT_SELECT
+        ))?;
+
+        let events = stmt
+            .query_map(params![limit as i64], event_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    fn unsynced_patterns(&self, limit: usize) -> Result<Vec<ErrorPattern>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE id NOT IN ({}) ORDER BY occurrence_count DESC, rowid LIMIT ?2",
+            PATTERN_SELECT, SYNCED_OR_QUEUED_IDS
+        ))?;
+        let mut patterns = stmt
+            .query_map(params!["pattern", limit as i64], pattern_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_solutions(&conn, &mut patterns)?;
+        Ok(patterns)
+    }
+
+    fn unsynced_solutions(&self, limit: usize) -> Result<Vec<UnsyncedSolution>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let target = "json_extract(CASE WHEN json_valid(e.metadata) THEN e.metadata END, \
+                      '$.target_signature')";
+        let mut stmt = conn.prepare(&format!(
+            "SELECT e.id, {target}, (
+                 SELECT h.central_id FROM sync_history h JOIN patterns p ON p.id = h.local_id
+                 WHERE h.sync_type = 'pattern' AND h.sync_status = 'success'
+                   AND p.signature = {target}
+                 ORDER BY h.synced_at DESC, h.rowid DESC LIMIT 1)
+             FROM events e
+             WHERE e.type = 'solution' AND {target} IS NOT NULL AND e.id NOT IN ({synced})
+             ORDER BY e.created_at, e.rowid LIMIT ?2",
+            target = target,
+            synced = SYNCED_OR_QUEUED_IDS
+        ))?;
+        let rows = stmt
+            .query_map(params!["solution", limit as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let signatures: Vec<&str> = rows.iter().map(|(_, sig, _)| sig.as_str()).collect();
+        let solutions = load_solutions(&conn, &signatures)?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, signature, central_pattern_id)| {
+                let solution = solutions.get(&signature)?.iter().find(|s| s.id == id)?;
+                Some(UnsyncedSolution {
+                    solution: solution.clone(),
+                    signature,
+                    central_pattern_id,
+                })
+            })
+            .collect())
+    }
+
+    fn record_sync(&self, records: &[SyncRecord]) -> Result<(), NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        for record in records {
+            tx.execute(
+                "INSERT INTO sync_history
+                     (id, sync_type, local_id, central_id, sync_status, error_message)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    record.id,
+                    record.sync_type.as_str(),
+                    record.local_id,
+                    record.central_id,
+                    record.status.as_str(),
+                    record.error_message
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn sync_history(&self, limit: usize) -> Result<Vec<SyncRecord>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, sync_type, local_id, central_id, sync_status, error_message, synced_at
+             FROM sync_history ORDER BY synced_at DESC, rowid DESC LIMIT ?1",
+        )?;
+        let records = stmt
+            .query_map(params![limit as i64], |row| {
+                // Rows of types this bridge doesn't write are skipped
+                let Ok(sync_type) = row.get::<_, String>(1)?.parse() else {
+                    return Ok(None);
+                };
+                Ok(Some(SyncRecord {
+                    id: row.get(0)?,
+                    sync_type,
+                    local_id: row.get(2)?,
+                    central_id: row.get(3)?,
+                    status: SyncStatus::parse_lenient(
+                        &row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    ),
+                    error_message: row.get(5)?,
+                    synced_at: row.get(6)?,
+                }))
+            })?
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    fn enqueue_sync(&self, items: &[OutboxItem]) -> Result<usize, NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        let mut added = 0;
+        for item in items {
+            added += tx.execute(
+                "INSERT OR IGNORE INTO sync_outbox
+                     (id, sync_type, local_id, idempotency_key, payload, status, attempts,
+                      last_error, central_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    item.id,
+                    item.sync_type.as_str(),
+                    item.local_id,
+                    item.idempotency_key,
+                    item.payload.to_string(),
+                    item.status.as_str(),
+                    item.attempts,
+                    item.last_error,
+                    item.central_id
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    fn due_sync_items(
+        &self,
+        sync_type: SyncType,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE sync_type = ?1 AND status = 'pending' AND next_attempt_at <= datetime('now')
+             ORDER BY created_at, rowid LIMIT ?2",
+            OUTBOX_SELECT
+        ))?;
+        let items = stmt
+            .query_map(params![sync_type.as_str(), limit as i64], outbox_from_row)?
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    fn update_sync_items(&self, updates: &[OutboxUpdate]) -> Result<(), NebulaKgError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        for update in updates {
+            tx.execute(
+                "UPDATE sync_outbox SET status = ?2, attempts = attempts + 1,
+                     central_id = COALESCE(?3, central_id), last_error = ?4,
+                     next_attempt_at = datetime('now', '+' || ?5 || ' seconds')
+                 WHERE id = ?1",
+                params![
+                    update.id,
+                    update.status.as_str(),
+                    update.central_id,
+                    update.error,
+                    update.retry_in_secs as i64
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn sync_outbox(
+        &self,
+        status: Option<OutboxStatus>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE ?1 IS NULL OR status = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+            OUTBOX_SELECT
+        ))?;
+        let items = stmt
+            .query_map(
+                params![status.map(|s| s.as_str()), limit as i64],
+                outbox_from_row,
+            )?
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// The latest applied migration (see `migrations::detect_schema_version`)
+    fn schema_version(&self) -> Result<Option<String>, NebulaKgError> {
+        let conn = self.conn.lock()?;
+        migrations::detect_schema_version(&conn)
+    }
+}
@@ -0,0 +1,1029 @@
+//! Pushing local patterns and solutions to the Central KG.
+//!
+//! `CentralSync` does what `local_kg/sync.py` does, natively: every pattern
+//! not yet synced goes to `POST /api/v1/patterns/submit`, then every solution
+//! whose pattern has a central id goes to `POST /api/v1/sync/solutions`, each
+//! as a batch of one (see `CentralSync::push_solutions`). Each outcome is
+//! written to the `sync_history` table, and an item counts as synced once it
+//! has a `success` row there. Requests go through a `SyncTransport` (plain
+//! HTTP by default, see `HttpTransport`), so tests can point the client at a
+//! local mock server or swap the transport entirely.
+//!
+//! Items go through a durable outbox (`sync_outbox`) rather than straight to
+//! the network. Each run first queues whatever is new, with its request body
+//! and an idempotency key, then sends the items that are due. A failed item
+//! stays `pending` and is retried after an exponential backoff with jitter
+//! (`RetryPolicy`), until it is rejected outright or runs out of attempts and
+//! is marked `failed`. Retries reuse the item's key, sent as the
+//! `Idempotency-Key` header (and, for solutions, an `idempotency_key` field
+//! in the entry), so a Central KG that honors it can drop a request that
+//! arrived but whose answer was lost. The current Central KG endpoints
+//! ignore the key, so such a request may still be stored twice.
+//!
+//! With `"auto_sync": true` the bridge runs a sync in the background after
+//! each capture or solution, at most once per `sync_interval_minutes`.
+//!
+//! ```ignore
+//! let summary = kg.sync_to_central().await?;
+//! println!("{} patterns, {} solutions synced", summary.patterns_synced, summary.solutions_synced);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::backend::KgBackend;
+use super::http::HttpTransport;
+use super::{Category, NebulaConfig, NebulaKgError, Solution};
+
+/// Endpoint taking one pattern per request
+pub const PATTERN_SUBMIT_PATH: &str = "/api/v1/patterns/submit";
+/// Endpoint taking a batch of solutions (sent one at a time, see `CentralSync::push_solutions`)
+pub const SOLUTION_SYNC_PATH: &str = "/api/v1/sync/solutions";
+
+/// Patterns (and separately solutions) pushed per sync run
+const SYNC_BATCH_SIZE: usize = 100;
+
+/// Sends JSON requests to the Central KG
+pub trait SyncTransport: Send + Sync {
+    /// POST `body` to `path` (relative to the Central KG URL) and return the
+    /// decoded response (`Null` if empty). HTTP errors are `NebulaKgError::Central`.
+    /// `idempotency_key`, when given, is sent as the `Idempotency-Key` header.
+    fn post_json(
+        &self,
+        path: &str,
+        body: &Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<Value, NebulaKgError>;
+}
+
+/// What a `sync_history` row is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncType {
+    /// A row of `patterns`, by id
+    Pattern,
+    /// A `solution` event, by id
+    Solution,
+}
+
+impl SyncType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncType::Pattern => "pattern",
+            SyncType::Solution => "solution",
+        }
+    }
+}
+
+impl fmt::Display for SyncType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyncType {
+    type Err = NebulaKgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pattern" => Ok(SyncType::Pattern),
+            "solution" => Ok(SyncType::Solution),
+            other => Err(NebulaKgError::InvalidValue(format!(
+                "unknown sync type: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Outcome recorded in `sync_history`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    Success,
+    Failed,
+    /// Written by the legacy tooling for pushes it never finished
+    Pending,
+}
+
+impl SyncStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncStatus::Success => "success",
+            SyncStatus::Failed => "failed",
+            SyncStatus::Pending => "pending",
+        }
+    }
+
+    /// Parse a stored status; anything unrecognised counts as `Failed` so the
+    /// item is retried
+    pub fn parse_lenient(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "success" => SyncStatus::Success,
+            "pending" => SyncStatus::Pending,
+            _ => SyncStatus::Failed,
+        }
+    }
+}
+
+/// Where an item stands in the sync outbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    /// Accepted by the Central KG
+    Synced,
+    /// Rejected, or out of attempts; not retried
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Synced => "synced",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse a stored status; anything unrecognised counts as `Pending`
+    pub fn parse_lenient(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "synced" => OutboxStatus::Synced,
+            "failed" => OutboxStatus::Failed,
+            _ => OutboxStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for OutboxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A row of `sync_outbox`: one pattern or solution on its way to the Central KG
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: String,
+    pub sync_type: SyncType,
+    /// Pattern id or solution event id
+    pub local_id: String,
+    /// Sent with every attempt, so the Central KG can drop repeats
+    pub idempotency_key: String,
+    /// Request body (for a solution, its batch entry), fixed when queued
+    pub payload: Value,
+    pub status: OutboxStatus,
+    /// Attempts made so far
+    pub attempts: u32,
+    /// When the item is next due; set by the store
+    #[serde(default)]
+    pub next_attempt_at: Option<String>,
+    pub last_error: Option<String>,
+    /// Id the Central KG assigned, once synced
+    pub central_id: Option<String>,
+    /// Set by the store when the item is queued
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+impl OutboxItem {
+    /// A new `pending` item, due immediately
+    pub fn new(sync_type: SyncType, local_id: impl Into<String>, payload: Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            sync_type,
+            local_id: local_id.into(),
+            idempotency_key: uuid::Uuid::new_v4().to_string(),
+            payload,
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            next_attempt_at: None,
+            last_error: None,
+            central_id: None,
+            created_at: None,
+        }
+    }
+}
+
+/// Outcome of one attempt at an outbox item. The store counts the attempt
+/// and sets `next_attempt_at` to now plus `retry_in_secs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxUpdate {
+    /// `OutboxItem::id`
+    pub id: String,
+    pub status: OutboxStatus,
+    /// Kept from earlier attempts when `None`
+    pub central_id: Option<String>,
+    pub error: Option<String>,
+    pub retry_in_secs: u64,
+}
+
+/// When to retry a failed outbox item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Delay before the first retry; doubled for each further attempt
+    pub base_delay: Duration,
+    /// Upper bound for the delay
+    pub max_delay: Duration,
+    /// Attempts before an item is marked `failed`
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(6 * 60 * 60),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after the `attempt`-th failed attempt (1-based): the capped
+    /// exponential delay, half of it fixed and half random ("equal jitter"),
+    /// so clients that went offline together don't retry in lockstep
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let half = delay / 2;
+        // uuid v4 is the random source already at hand
+        let jitter_ms = uuid::Uuid::new_v4().as_u128() % (half.as_millis() + 1);
+        half + Duration::from_millis(jitter_ms as u64)
+    }
+}
+
+/// A row of `sync_history`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub id: String,
+    pub sync_type: SyncType,
+    /// Pattern id or solution event id
+    pub local_id: String,
+    /// Id the Central KG assigned, if it returned one
+    pub central_id: Option<String>,
+    pub status: SyncStatus,
+    pub error_message: Option<String>,
+    /// Set by the store when the record is written
+    #[serde(default)]
+    pub synced_at: Option<String>,
+}
+
+impl SyncRecord {
+    pub fn success(
+        sync_type: SyncType,
+        local_id: impl Into<String>,
+        central_id: Option<String>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            sync_type,
+            local_id: local_id.into(),
+            central_id,
+            status: SyncStatus::Success,
+            error_message: None,
+            synced_at: None,
+        }
+    }
+
+    pub fn failed(
+        sync_type: SyncType,
+        local_id: impl Into<String>,
+        error: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            sync_type,
+            local_id: local_id.into(),
+            central_id: None,
+            status: SyncStatus::Failed,
+            error_message: Some(error.into()),
+            synced_at: None,
+        }
+    }
+}
+
+/// A solution without a `success` sync record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsyncedSolution {
+    pub solution: Solution,
+    /// Signature of the pattern it fixes
+    pub signature: String,
+    /// Central id of that pattern, once the pattern is synced
+    pub central_pattern_id: Option<String>,
+}
+
+/// Counts from one sync run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub patterns_synced: usize,
+    pub patterns_failed: usize,
+    pub solutions_synced: usize,
+    pub solutions_failed: usize,
+    pub errors: Vec<String>,
+}
+
+impl SyncSummary {
+    fn fail(&mut self, error: impl fmt::Display) {
+        self.errors.push(error.to_string());
+    }
+}
+
+/// Client pushing unsynced patterns and solutions to the Central KG
+pub struct CentralSync {
+    transport: Arc<dyn SyncTransport>,
+    instance_id: String,
+    language: Option<String>,
+    /// Minimum time between automatic syncs
+    interval: Duration,
+    retry: RetryPolicy,
+    /// Serializes runs, so the same item is never pushed twice concurrently
+    running: Mutex<()>,
+    /// An automatic sync is queued but hasn't started reading the store yet
+    queued: AtomicBool,
+    last_auto_sync: Mutex<Option<Instant>>,
+}
+
+impl CentralSync {
+    /// Client for `instance_id` sending requests through `transport`
+    pub fn new(transport: Arc<dyn SyncTransport>, instance_id: impl Into<String>) -> Self {
+        Self {
+            transport,
+            instance_id: instance_id.into(),
+            language: None,
+            interval: Duration::ZERO,
+            retry: RetryPolicy::default(),
+            running: Mutex::new(()),
+            queued: AtomicBool::new(false),
+            last_auto_sync: Mutex::new(None),
+        }
+    }
+
+    /// Client built from `central_kg_url`, `central_kg_api_key`,
+    /// `central_kg_instance_id`, `language` and `sync_interval_minutes`.
+    /// `None` without a URL. Fails with `Config` if the URL is set but the
+    /// instance id isn't: a fresh id on every start would make each run look
+    /// like a new instance to the Central KG.
+    pub fn from_config(config: &NebulaConfig) -> Result<Option<Self>, NebulaKgError> {
+        let Some(url) = config.central_kg_url.as_deref() else {
+            return Ok(None);
+        };
+        let mut transport = HttpTransport::new(url)?;
+        if let Some(api_key) = &config.central_kg_api_key {
+            transport = transport.with_api_key(api_key.clone())?;
+        }
+        let instance_id = config
+            .central_kg_instance_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .ok_or_else(|| {
+                NebulaKgError::Config(
+                    "central_kg_url is set but central_kg_instance_id is not".to_string(),
+                )
+            })?;
+
+        let mut sync = Self::new(Arc::new(transport), instance_id);
+        sync.language = config.language.clone();
+        if let Some(minutes) = config.sync_interval_minutes {
+            sync.interval = Duration::from_secs(minutes.saturating_mul(60));
+        }
+        Ok(Some(sync))
+    }
+
+    /// Language reported with each pattern
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Minimum time between automatic syncs (default: none)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Backoff for failed items (default: `RetryPolicy::default()`)
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Queue everything not yet synced, then send what is due: patterns
+    /// first, then solutions.
+    ///
+    /// Failed items are recorded as `failed` in the history and retried
+    /// after a backoff. If the Central KG can't be reached at all the run
+    /// stops early and the items stay queued; the returned summary says what
+    /// happened. Only store errors are returned as `Err`.
+    pub fn sync(&self, backend: &dyn KgBackend) -> Result<SyncSummary, NebulaKgError> {
+        let _running = self.running.lock()?;
+        self.queued.store(false, Ordering::SeqCst);
+
+        let mut summary = SyncSummary::default();
+        self.queue_patterns(backend)?;
+        let reachable = self.push_patterns(backend, &mut summary)?;
+        // Queued even when offline, now that more patterns may have central ids
+        self.queue_solutions(backend)?;
+        if reachable {
+            self.push_solutions(backend, &mut summary)?;
+        }
+        Ok(summary)
+    }
+
+    fn queue_patterns(&self, backend: &dyn KgBackend) -> Result<(), NebulaKgError> {
+        let items: Vec<OutboxItem> = backend
+            .unsynced_patterns(SYNC_BATCH_SIZE)?
+            .into_iter()
+            .map(|pattern| {
+                let payload = json!({
+                    "instance_id": self.instance_id,
+                    "error_signature": pattern.signature,
+                    "error_category": pattern.category.as_ref().map(Category::as_str),
+                    "language": self.language,
+                    "description": pattern.description,
+                    "occurrence_count": pattern.occurrence_count,
+                    "technologies": [],
+                });
+                OutboxItem::new(SyncType::Pattern, pattern.id, payload)
+            })
+            .collect();
+        if !items.is_empty() {
+            backend.enqueue_sync(&items)?;
+        }
+        Ok(())
+    }
+
+    /// Solutions are queued once their pattern has a central id
+    fn queue_solutions(&self, backend: &dyn KgBackend) -> Result<(), NebulaKgError> {
+        let items: Vec<OutboxItem> = backend
+            .unsynced_solutions(SYNC_BATCH_SIZE)?
+            .into_iter()
+            .filter_map(|unsynced| {
+                let pattern_id = unsynced.central_pattern_id?;
+                let solution = unsynced.solution;
+                let payload = json!({
+                    "pattern_id": pattern_id,
+                    "pattern_signature": unsynced.signature,
+                    "title": solution.title,
+                    "description": solution.description,
+                    "code_snippet": solution.code_snippet,
+                    "difficulty_level": "intermediate",
+                    "technologies": [],
+                });
+                Some(OutboxItem::new(SyncType::Solution, solution.id, payload))
+            })
+            .collect();
+        if !items.is_empty() {
+            backend.enqueue_sync(&items)?;
+        }
+        Ok(())
+    }
+
+    /// Returns false if the Central KG was unreachable
+    fn push_patterns(
+        &self,
+        backend: &dyn KgBackend,
+        summary: &mut SyncSummary,
+    ) -> Result<bool, NebulaKgError> {
+        for item in backend.due_sync_items(SyncType::Pattern, SYNC_BATCH_SIZE)? {
+            let response = self.transport.post_json(
+                PATTERN_SUBMIT_PATH,
+                &item.payload,
+                Some(&item.idempotency_key),
+            );
+            let unreachable = matches!(&response, Err(e) if is_unreachable(e));
+            let result = match response {
+                Ok(response) => {
+                    match central_id(&response, &["id", "pattern_id", "existing_pattern_id"]) {
+                        Some(central_id) => ItemResult::Synced(Some(central_id)),
+                        None => ItemResult::Retry("response has no id".to_string()),
+                    }
+                }
+                Err(e) if is_rejection(&e) => ItemResult::Rejected(e.to_string()),
+                Err(e) => ItemResult::Retry(e.to_string()),
+            };
+            self.settle(backend, &[(item, result)], summary)?;
+            if unreachable {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Each solution is sent as a batch of its own. The Central KG answers a
+    /// batch with `accepted` / `failed` counts only, so in a larger batch a
+    /// partial failure couldn't be pinned on an entry, and resending the
+    /// whole batch would store the accepted entries twice.
+    fn push_solutions(
+        &self,
+        backend: &dyn KgBackend,
+        summary: &mut SyncSummary,
+    ) -> Result<(), NebulaKgError> {
+        for item in backend.due_sync_items(SyncType::Solution, SYNC_BATCH_SIZE)? {
+            let mut entry = item.payload.clone();
+            if let Value::Object(fields) = &mut entry {
+                fields.insert("idempotency_key".to_string(), json!(item.idempotency_key));
+            }
+            let response = self.transport.post_json(
+                SOLUTION_SYNC_PATH,
+                &json!([entry]),
+                Some(&item.idempotency_key),
+            );
+            let unreachable = matches!(&response, Err(e) if is_unreachable(e));
+            let result = match response {
+                Ok(response) => solution_result(&item, &response),
+                Err(e) if is_rejection(&e) => ItemResult::Rejected(e.to_string()),
+                Err(e) => ItemResult::Retry(e.to_string()),
+            };
+            self.settle(backend, &[(item, result)], summary)?;
+            if unreachable {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the outcome of one attempt at each item, in the history and the outbox
+    fn settle(
+        &self,
+        backend: &dyn KgBackend,
+        outcomes: &[(OutboxItem, ItemResult)],
+        summary: &mut SyncSummary,
+    ) -> Result<(), NebulaKgError> {
+        let mut records = Vec::with_capacity(outcomes.len());
+        let mut updates = Vec::with_capacity(outcomes.len());
+        for (item, result) in outcomes {
+            let (synced, failed) = match item.sync_type {
+                SyncType::Pattern => (&mut summary.patterns_synced, &mut summary.patterns_failed),
+                SyncType::Solution => {
+                    (&mut summary.solutions_synced, &mut summary.solutions_failed)
+                }
+            };
+            let attempt = item.attempts + 1;
+            let (status, error, retry_in) = match result {
+                ItemResult::Synced(central_id) => {
+                    *synced += 1;
+                    records.push(SyncRecord::success(
+                        item.sync_type,
+                        &item.local_id,
+                        central_id.clone(),
+                    ));
+                    updates.push(OutboxUpdate {
+                        id: item.id.clone(),
+                        status: OutboxStatus::Synced,
+                        central_id: central_id.clone(),
+                        error: None,
+                        retry_in_secs: 0,
+                    });
+                    continue;
+                }
+                ItemResult::Rejected(error) => (OutboxStatus::Failed, error, Duration::ZERO),
+                ItemResult::Retry(error) if attempt >= self.retry.max_attempts => {
+                    (OutboxStatus::Failed, error, Duration::ZERO)
+                }
+                ItemResult::Retry(error) => {
+                    (OutboxStatus::Pending, error, self.retry.delay(attempt))
+                }
+            };
+            *failed += 1;
+            summary.fail(match status {
+                OutboxStatus::Pending => format!(
+                    "{} {} (attempt {}, retry in {}s): {}",
+                    item.sync_type,
+                    item.local_id,
+                    attempt,
+                    retry_in.as_secs(),
+                    error
+                ),
+                _ => format!(
+                    "{} {} (attempt {}, giving up): {}",
+                    item.sync_type, item.local_id, attempt, error
+                ),
+            });
+            records.push(SyncRecord::failed(item.sync_type, &item.local_id, error));
+            updates.push(OutboxUpdate {
+                id: item.id.clone(),
+                status,
+                central_id: None,
+                error: Some(error.clone()),
+                retry_in_secs: retry_in.as_secs(),
+            });
+        }
+        backend.record_sync(&records)?;
+        backend.update_sync_items(&updates)
+    }
+
+    /// Queue a background sync after a write, unless one is already queued or
+    /// the last automatic sync was less than the interval ago
+    pub(super) fn schedule(self: &Arc<Self>, backend: Arc<dyn KgBackend>) {
+        {
+            let Ok(mut last) = self.last_auto_sync.lock() else {
+                return;
+            };
+            if last.is_some_and(|at| at.elapsed() < self.interval) {
+                return;
+            }
+            if self.queued.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            self.queued.store(false, Ordering::SeqCst);
+            return;
+        };
+        let sync = self.clone();
+        handle.spawn_blocking(move || match sync.sync(backend.as_ref()) {
+            Ok(summary) if !summary.errors.is_empty() => {
+                tracing::warn!("Central KG auto-sync: {}", summary.errors.join("; "))
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Central KG auto-sync failed: {}", e),
+        });
+    }
+}
+
+/// What one attempt did for one item
+#[derive(Debug, Clone)]
+enum ItemResult {
+    /// Accepted, with the central id if the response had one
+    Synced(Option<String>),
+    /// Refused in a way retrying won't fix
+    Rejected(String),
+    Retry(String),
+}
+
+/// Outcome of sending `item` as a batch of one. A `results` array is
+/// searched for the item's `idempotency_key`, then `index` 0, then taken by
+/// position; without one the `failed` count decides.
+fn solution_result(item: &OutboxItem, response: &Value) -> ItemResult {
+    let Some(results) = response["results"].as_array() else {
+        return match response["failed"].as_u64().unwrap_or(0) {
+            0 => ItemResult::Synced(None),
+            _ => ItemResult::Retry("Central KG reported the solution as failed".to_string()),
+        };
+    };
+
+    let entry = results
+        .iter()
+        .find(|r| r["idempotency_key"].as_str() == Some(item.idempotency_key.as_str()))
+        .or_else(|| results.iter().find(|r| r["index"].as_u64() == Some(0)))
+        .or_else(|| {
+            results
+                .first()
+                .filter(|r| r["idempotency_key"].is_null() && r["index"].is_null())
+        });
+    match entry {
+        Some(entry) => entry_result(entry),
+        None => ItemResult::Retry("missing from the batch response".to_string()),
+    }
+}
+
+/// Outcome of one `results` entry, from its `status`
+fn entry_result(entry: &Value) -> ItemResult {
+    let status = entry["status"]
+        .as_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let error = match &entry["error"] {
+        Value::String(error) => error.clone(),
+        Value::Null => format!("status \"{}\"", status),
+        error => error.to_string(),
+    };
+    match status.as_str() {
+        "accepted" | "created" | "success" | "synced" | "duplicate" | "exists" => {
+            ItemResult::Synced(central_id(
+                entry,
+                &["id", "solution_id", "existing_solution_id"],
+            ))
+        }
+        "rejected" | "invalid" => ItemResult::Rejected(error),
+        _ => ItemResult::Retry(error),
+    }
+}
+
+/// First of `keys` holding an id (string or number) in `response`
+fn central_id(response: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match &response[key] {
+        Value::String(id) if !id.is_empty() => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    })
+}
+
+/// The request never got an HTTP answer (connection refused, timeout, ...)
+fn is_unreachable(error: &NebulaKgError) -> bool {
+    matches!(error, NebulaKgError::Io(_))
+}
+
+/// A 4xx answer that retrying the same request won't change, or a request
+/// that can't be sent at all (e.g. a key that isn't a valid header value)
+fn is_rejection(error: &NebulaKgError) -> bool {
+    match error {
+        NebulaKgError::Central { status, .. } => {
+            (400..500).contains(status) && !matches!(status, 408 | 429)
+        }
+        NebulaKgError::Config(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::super::{ErrorCapture, MemoryBackend};
+    use super::*;
+
+    /// What the mock Central KG does with one connection
+    enum Reply {
+        /// Write these bytes as the response
+        Raw(String),
+        /// Read the request, then close without answering
+        Drop,
+    }
+
+    fn json_reply(status: &str, body: &Value) -> Reply {
+        let body = body.to_string();
+        Reply::Raw(format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        ))
+    }
+
+    fn chunked_reply(body: &Value) -> Reply {
+        let body = body.to_string();
+        let (head, tail) = body.split_at(body.len() / 2);
+        Reply::Raw(format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x};ext=1\r\n{}\r\n0\r\n\r\n",
+            head.len(),
+            head,
+            tail.len(),
+            tail
+        ))
+    }
+
+    /// Serve `replies` to one connection each, in order. Joining the handle
+    /// returns the raw requests received.
+    fn mock_central(replies: Vec<Reply>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+                requests.push(request);
+
+                if let Reply::Raw(response) = reply {
+                    reader.get_mut().write_all(response.as_bytes()).unwrap();
+                }
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn capture_times(kg: &dyn KgBackend, signature: &str, times: usize) -> String {
+        for _ in 0..times {
+            kg.capture(&ErrorCapture::new(signature, Category::CompileError))
+                .unwrap();
+        }
+        kg.search_patterns(signature, 1).unwrap()[0].id.clone()
+    }
+
+    fn outbox_status(kg: &dyn KgBackend, local_id: &str) -> (OutboxStatus, Option<String>) {
+        let item = kg
+            .sync_outbox(None, 100)
+            .unwrap()
+            .into_iter()
+            .find(|item| item.local_id == local_id)
+            .unwrap();
+        (item.status, item.central_id)
+    }
+
+    #[test]
+    fn patterns_sync_over_http() {
+        let kg = MemoryBackend::new();
+        // Sent most frequent first
+        let content_length = capture_times(&kg, "E0001 content length", 4);
+        let chunked = capture_times(&kg, "E0002 chunked", 3);
+        let rejected = capture_times(&kg, "E0003 rejected", 2);
+        let dropped = capture_times(&kg, "E0004 dropped", 1);
+
+        let (url, server) = mock_central(vec![
+            json_reply("200 OK", &json!({ "id": "central-1" })),
+            chunked_reply(&json!({ "pattern_id": "central-2" })),
+            json_reply(
+                "422 Unprocessable Entity",
+                &json!({ "detail": "bad signature" }),
+            ),
+            Reply::Drop,
+        ]);
+        let transport = HttpTransport::new(&url)
+            .unwrap()
+            .with_api_key("secret")
+            .unwrap();
+        let sync = CentralSync::new(Arc::new(transport), "test-instance");
+
+        let summary = sync.sync(&kg).unwrap();
+        assert_eq!((summary.patterns_synced, summary.patterns_failed), (2, 2));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 4);
+        for request in &requests {
+            assert!(request.starts_with(&format!("POST {} HTTP/1.1\r\n", PATTERN_SUBMIT_PATH)));
+            assert!(request.contains("\r\nX-API-Key: secret\r\n"));
+            assert!(request.contains("\r\nIdempotency-Key: "));
+            assert!(request.contains("\"instance_id\":\"test-instance\""));
+        }
+
+        let history: Vec<_> = kg
+            .sync_history(10)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.local_id, r.status, r.central_id))
+            .collect();
+        for expected in [
+            (
+                content_length.clone(),
+                SyncStatus::Success,
+                Some("central-1".to_string()),
+            ),
+            (
+                chunked.clone(),
+                SyncStatus::Success,
+                Some("central-2".to_string()),
+            ),
+            (rejected.clone(), SyncStatus::Failed, None),
+            (dropped.clone(), SyncStatus::Failed, None),
+        ] {
+            assert!(
+                history.contains(&expected),
+                "{:?} not in {:?}",
+                expected,
+                history
+            );
+        }
+        assert_eq!(history.len(), 4);
+
+        assert_eq!(
+            outbox_status(&kg, &content_length),
+            (OutboxStatus::Synced, Some("central-1".to_string()))
+        );
+        assert_eq!(
+            outbox_status(&kg, &chunked),
+            (OutboxStatus::Synced, Some("central-2".to_string()))
+        );
+        // A 4xx won't change on retry; a dropped connection might
+        assert_eq!(outbox_status(&kg, &rejected), (OutboxStatus::Failed, None));
+        assert_eq!(outbox_status(&kg, &dropped), (OutboxStatus::Pending, None));
+    }
+
+    /// The solution entries in a raw request
+    fn batch_entries(request: &str) -> Vec<Value> {
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn solutions_partial_failure_retries_only_the_failed_one() {
+        let kg = MemoryBackend::new();
+        let pattern = capture_times(&kg, "E0005 partial failure", 1);
+        let solutions: Vec<String> = ["first fix", "second fix", "third fix"]
+            .into_iter()
+            .map(|text| kg.add_solution(&pattern, text, "4").unwrap())
+            .collect();
+
+        // The Central KG only counts accepted and failed entries
+        let (url, server) = mock_central(vec![
+            json_reply("200 OK", &json!({ "id": "central-p" })),
+            json_reply("200 OK", &json!({ "accepted": 1, "failed": 0 })),
+            json_reply("200 OK", &json!({ "accepted": 0, "failed": 1 })),
+            json_reply("200 OK", &json!({ "accepted": 1, "failed": 0 })),
+        ]);
+        let retry = RetryPolicy {
+            base_delay: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+        let sync = CentralSync::new(Arc::new(HttpTransport::new(&url).unwrap()), "test-instance")
+            .with_retry_policy(retry);
+
+        let summary = sync.sync(&kg).unwrap();
+        assert_eq!((summary.solutions_synced, summary.solutions_failed), (2, 1));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 4);
+        let mut failed_key = None;
+        for (request, solution) in requests[1..].iter().zip(&solutions) {
+            assert!(request.starts_with(&format!("POST {} HTTP/1.1\r\n", SOLUTION_SYNC_PATH)));
+            let entries = batch_entries(request);
+            assert_eq!(entries.len(), 1);
+            let key = entries[0]["idempotency_key"].as_str().unwrap().to_string();
+            assert!(request.contains(&format!("\r\nIdempotency-Key: {}\r\n", key)));
+            if *solution == solutions[1] {
+                failed_key = Some(key);
+            }
+        }
+
+        assert_eq!(outbox_status(&kg, &solutions[0]).0, OutboxStatus::Synced);
+        assert_eq!(outbox_status(&kg, &solutions[1]).0, OutboxStatus::Pending);
+        assert_eq!(outbox_status(&kg, &solutions[2]).0, OutboxStatus::Synced);
+        let failed: Vec<_> = kg
+            .sync_history(10)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.status == SyncStatus::Failed)
+            .map(|r| r.local_id)
+            .collect();
+        assert_eq!(failed, vec![solutions[1].clone()]);
+
+        // Only the failed solution is sent again, under the same key
+        let (url, server) = mock_central(vec![json_reply(
+            "200 OK",
+            &json!({ "accepted": 1, "failed": 0 }),
+        )]);
+        let sync = CentralSync::new(Arc::new(HttpTransport::new(&url).unwrap()), "test-instance")
+            .with_retry_policy(retry);
+        let summary = sync.sync(&kg).unwrap();
+        assert_eq!((summary.solutions_synced, summary.solutions_failed), (1, 0));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        let entries = batch_entries(&requests[0]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0]["idempotency_key"].as_str(),
+            failed_key.as_deref()
+        );
+        for solution in &solutions {
+            assert_eq!(outbox_status(&kg, solution).0, OutboxStatus::Synced);
+        }
+    }
+
+    #[test]
+    fn header_values_reject_control_characters() {
+        let transport = HttpTransport::new("http://127.0.0.1:9").unwrap();
+        for key in ["key\r\nX-Injected: 1", "key\n", "key\0"] {
+            assert!(matches!(
+                transport.clone().with_api_key(key),
+                Err(NebulaKgError::Config(_))
+            ));
+        }
+        assert!(matches!(
+            transport.post_json("/", &Value::Null, Some("key\r\nX-Injected: 1")),
+            Err(NebulaKgError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn from_config_requires_an_instance_id() {
+        let config: NebulaConfig =
+            serde_json::from_value(json!({ "central_kg_url": "http://127.0.0.1:9" })).unwrap();
+        assert!(matches!(
+            CentralSync::from_config(&config),
+            Err(NebulaKgError::Config(_))
+        ));
+
+        let config: NebulaConfig = serde_json::from_value(json!({
+            "central_kg_url": "http://127.0.0.1:9",
+            "central_kg_instance_id": "laptop",
+        }))
+        .unwrap();
+        let sync = CentralSync::from_config(&config).unwrap().unwrap();
+        assert_eq!(sync.instance_id(), "laptop");
+    }
+}
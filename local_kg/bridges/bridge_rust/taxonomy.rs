@@ -0,0 +1,187 @@
+//! Typed severities and categories.
+//!
+//! Both serialize to the strings the Universal Schema stores (`"high"`,
+//! `"CompileError"`, ...). Parsing is lenient so rows written by other tools or
+//! older bridges still decode: case, `_`/`-`/space separators and common
+//! synonyms (`warning`, `fatal`, `borrowck`, ...) are accepted. `FromStr`
+//! rejects unknown values of either type. When decoding stored data
+//! (`parse_lenient` on either type, and `Deserialize`), unknown categories
+//! become `Category::Custom` and unknown severities read as `Medium`. Code that means a custom category says `Category::Custom(..)`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::NebulaKgError;
+
+/// How bad an error is. Ordered from `Low` to `Critical`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Severity {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+
+    /// Schema string (`low`, `medium`, `high`, `critical`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Parse a stored value, falling back to `Medium` for anything unrecognised
+    pub fn parse_lenient(value: &str) -> Self {
+        value.parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for Severity {
+    type Err = NebulaKgError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" | "minor" | "trivial" | "info" | "note" => Ok(Severity::Low),
+            "medium" | "moderate" | "normal" | "warn" | "warning" => Ok(Severity::Medium),
+            "high" | "major" | "severe" | "error" => Ok(Severity::High),
+            "critical" | "crit" | "fatal" | "blocker" => Ok(Severity::Critical),
+            _ => Err(NebulaKgError::InvalidValue(format!(
+                "unknown severity '{}' (expected low, medium, high or critical)",
+                value
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Severity::parse_lenient(&value))
+    }
+}
+
+/// What kind of error a pattern is
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// rustc errors other than borrow-check and lifetime ones
+    CompileError,
+    BorrowCheck,
+    Lifetime,
+    Panic,
+    TestFailure,
+    Clippy,
+    /// Errors logged while the program runs (`tracing`, `log`)
+    Runtime,
+    /// Crate resolution, version conflicts, build scripts
+    Dependency,
+    /// OS / target specific problems
+    Platform,
+    Workaround,
+    /// Any other category, stored verbatim
+    Custom(String),
+}
+
+impl Category {
+    /// Schema string (the variant name, or the custom value)
+    pub fn as_str(&self) -> &str {
+        match self {
+            Category::CompileError => "CompileError",
+            Category::BorrowCheck => "BorrowCheck",
+            Category::Lifetime => "Lifetime",
+            Category::Panic => "Panic",
+            Category::TestFailure => "TestFailure",
+            Category::Clippy => "Clippy",
+            Category::Runtime => "Runtime",
+            Category::Dependency => "Dependency",
+            Category::Platform => "Platform",
+            Category::Workaround => "Workaround",
+            Category::Custom(value) => value,
+        }
+    }
+
+    /// The known category `value` names (ignoring case and separators)
+    fn parse_known(value: &str) -> Option<Self> {
+        let key: String = value
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match key.as_str() {
+            "compileerror" | "compile" | "compiler" | "build" | "builderror" => {
+                Some(Category::CompileError)
+            }
+            "borrowcheck" | "borrowck" | "borrow" | "borrowchecker" => Some(Category::BorrowCheck),
+            "lifetime" | "lifetimes" => Some(Category::Lifetime),
+            "panic" => Some(Category::Panic),
+            "testfailure" | "test" | "testfailed" => Some(Category::TestFailure),
+            "clippy" | "lint" => Some(Category::Clippy),
+            "runtime" | "runtimeerror" => Some(Category::Runtime),
+            "dependency" | "dependencies" | "deps" => Some(Category::Dependency),
+            "platform" => Some(Category::Platform),
+            "workaround" => Some(Category::Workaround),
+            _ => None,
+        }
+    }
+
+    /// Parse a stored value, keeping anything unrecognised as `Custom`
+    pub fn parse_lenient(value: &str) -> Self {
+        Self::parse_known(value).unwrap_or_else(|| Category::Custom(value.trim().to_string()))
+    }
+}
+
+impl FromStr for Category {
+    type Err = NebulaKgError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Category::parse_known(value).ok_or_else(|| {
+            NebulaKgError::InvalidValue(format!(
+                "unknown category '{}' (use Category::Custom for your own)",
+                value
+            ))
+        })
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Category {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Category {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Category::parse_lenient(&value))
+    }
+}
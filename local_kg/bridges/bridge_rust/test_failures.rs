@@ -0,0 +1,875 @@
+//! Test results from libtest and cargo-nextest output.
+//!
+//! Three formats are understood:
+//!
+//! - libtest's plain text (`cargo test`): `test <name> ... FAILED` for each
+//!   failing test and a `---- <name> stdout ----` section with its captured
+//!   output at the end of each test binary's run.
+//! - libtest's JSON event stream (`cargo test -- -Z unstable-options --format json`),
+//!   which nextest also emits with `--message-format libtest-json`; nextest
+//!   names tests `<binary-id>$<test>`.
+//! - JUnit XML as written by nextest (`target/nextest/<profile>/junit.xml`).
+//!
+//! Each parser yields a `TestReport` with the failed tests and the tests that
+//! passed, each with the crate it belongs to when the output names it (for
+//! plain text and libtest JSON, from cargo's `Running` lines). Failures are
+//! captured as `TestFailure` patterns; a later pass of the same test in the
+//! same crate resolves its pattern.
+
+use serde_json::{json, Value};
+
+use super::{Category, ErrorCapture, Severity};
+
+/// Captured output kept in a capture's context (the panic is at the start)
+const MAX_OUTPUT_EXCERPT: usize = 4000;
+
+/// One failed test
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    /// Full test path, e.g. `tests::parses_empty_input`
+    pub name: String,
+    /// Crate (or test binary) containing the test, when the format reports it
+    pub crate_name: Option<String>,
+    /// Panic message (the lines after `panicked at ...:`), if any
+    pub message: Option<String>,
+    /// Location of the panic
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Captured output of the test
+    pub output: String,
+    /// Tool that reported the failure: `libtest` or `nextest`
+    pub source: String,
+}
+
+/// One passed test
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassedTest {
+    pub name: String,
+    pub crate_name: Option<String>,
+}
+
+/// Outcome of one test run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestReport {
+    pub failures: Vec<TestFailure>,
+    pub passed: Vec<PassedTest>,
+}
+
+/// Pattern signature for a test, e.g. `test failed: my_crate::tests::parse`.
+/// Stable across runs and output formats, so repeated failures share a
+/// pattern and a later pass can resolve it; the crate keeps equally named
+/// tests in different crates apart.
+pub fn test_signature(crate_name: Option<&str>, name: &str) -> String {
+    match crate_name {
+        Some(crate_name) => format!("test failed: {}::{}", crate_name, name),
+        None => format!("test failed: {}", name),
+    }
+}
+
+impl TestFailure {
+    pub fn signature(&self) -> String {
+        test_signature(self.crate_name.as_deref(), &self.name)
+    }
+
+    /// Build the capture recorded for this failure
+    pub fn to_capture(&self) -> ErrorCapture {
+        ErrorCapture {
+            file_path: self.file.clone(),
+            line_number: self.line,
+            ..ErrorCapture::new(self.signature(), Category::TestFailure)
+        }
+        .with_severity(Severity::High)
+        .with_description(
+            self.message
+                .clone()
+                .unwrap_or_else(|| format!("test {} failed", self.name)),
+        )
+        .with_context(json!({
+            "source": self.source,
+            "test_name": self.name,
+            "crate": self.crate_name,
+            "output": excerpt(&self.output),
+        }))
+    }
+}
+
+impl PassedTest {
+    /// Signature of the pattern a pass of this test resolves
+    pub fn signature(&self) -> String {
+        test_signature(self.crate_name.as_deref(), &self.name)
+    }
+}
+
+impl TestReport {
+    /// Tests that passed and did not also fail in this run (a test can run
+    /// in several binaries of a crate); these are the patterns safe to resolve.
+    pub fn resolvable(&self) -> impl Iterator<Item = &PassedTest> {
+        self.passed.iter().filter(|passed| {
+            !self
+                .failures
+                .iter()
+                .any(|f| f.name == passed.name && f.crate_name == passed.crate_name)
+        })
+    }
+}
+
+/// Incremental parser for libtest's plain-text output
+#[derive(Default)]
+pub struct LibtestParser {
+    /// Crate of the test binary running now, from cargo's last `Running` line
+    crate_name: Option<String>,
+    failed: Vec<(Option<String>, String)>,
+    passed: Vec<PassedTest>,
+    sections: Vec<(Option<String>, String, Vec<String>)>,
+    in_section: bool,
+}
+
+impl LibtestParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one line of `cargo test` output. Cargo prints its `Running`
+    /// lines on stderr; feed those too, or tests are reported without a crate.
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(crate_name) = crate_from_running_line(line.trim()) {
+            self.crate_name = Some(crate_name);
+            self.in_section = false;
+            return;
+        }
+        if let Some(rest) = line.strip_prefix("test ") {
+            if let Some(name) = rest.strip_suffix(" ... FAILED") {
+                let test = (self.crate_name.clone(), name.to_string());
+                if !self.failed.contains(&test) {
+                    self.failed.push(test);
+                }
+                return;
+            }
+            if let Some(name) = rest.strip_suffix(" ... ok") {
+                self.passed.push(PassedTest {
+                    name: name.to_string(),
+                    crate_name: self.crate_name.clone(),
+                });
+                return;
+            }
+        }
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            self.sections
+                .push((self.crate_name.clone(), name.to_string(), Vec::new()));
+            self.in_section = true;
+            return;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            self.in_section = false;
+            return;
+        }
+        if self.in_section {
+            if let Some((_, _, lines)) = self.sections.last_mut() {
+                lines.push(line.to_string());
+            }
+        }
+    }
+
+    /// Failed tests (in the order libtest reported them) and passed tests seen so far
+    pub fn finish(self) -> TestReport {
+        let LibtestParser {
+            failed,
+            passed,
+            sections,
+            ..
+        } = self;
+        let failures = failed
+            .into_iter()
+            .map(|(crate_name, name)| {
+                let output = sections
+                    .iter()
+                    .find(|(section_crate, section, _)| {
+                        *section_crate == crate_name && *section == name
+                    })
+                    .map(|(_, _, lines)| lines.join("\n"))
+                    .unwrap_or_default();
+                failure_from_output(name, crate_name, &output, "libtest")
+            })
+            .collect();
+        TestReport { failures, passed }
+    }
+}
+
+/// Parse a complete `cargo test` output
+pub fn parse_libtest_output(output: &str) -> TestReport {
+    let mut parser = LibtestParser::new();
+    for line in output.lines() {
+        parser.push_line(line);
+    }
+    parser.finish()
+}
+
+/// Incremental parser for libtest / nextest JSON events
+#[derive(Default)]
+pub struct LibtestJsonParser {
+    crate_name: Option<String>,
+    report: TestReport,
+}
+
+impl LibtestJsonParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Crate the tests belong to. libtest's own JSON doesn't name it; cargo's
+    /// `Running ... (target/debug/deps/<crate>-<hash>)` lines, if fed to
+    /// `push_line`, update it per test binary.
+    pub fn with_crate_name(mut self, crate_name: &str) -> Self {
+        self.crate_name = Some(crate_name.to_string());
+        self
+    }
+
+    /// Feed one line of output. Lines that are not test events are ignored.
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.trim();
+        if let Some(crate_name) = crate_from_running_line(line) {
+            self.crate_name = Some(crate_name);
+            return;
+        }
+        if !line.starts_with('{') {
+            return;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            return;
+        };
+
+        // libtest-json-plus: nextest names the crate on suite events
+        if let Some(crate_name) = event["nextest"]["crate"].as_str() {
+            self.crate_name = Some(crate_name.to_string());
+        }
+        if event["type"] != "test" {
+            return;
+        }
+        let Some(full_name) = event["name"].as_str() else {
+            return;
+        };
+        let (crate_name, name, source) = match full_name.split_once('$') {
+            Some((binary_id, name)) => (
+                Some(crate_from_binary_id(binary_id)),
+                name.to_string(),
+                "nextest",
+            ),
+            None => (self.crate_name.clone(), full_name.to_string(), "libtest"),
+        };
+
+        match event["event"].as_str() {
+            Some("ok") => self.report.passed.push(PassedTest { name, crate_name }),
+            Some("failed") => {
+                let output = event["stdout"].as_str().unwrap_or("");
+                let mut failure = failure_from_output(name, crate_name, output, source);
+                if failure.message.is_none() {
+                    // e.g. "note: test did not panic as expected"
+                    failure.message = event["message"].as_str().map(str::to_string);
+                }
+                self.report.failures.push(failure);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn finish(self) -> TestReport {
+        self.report
+    }
+}
+
+/// Parse a complete libtest / nextest JSON event stream
+pub fn parse_libtest_json(output: &str) -> TestReport {
+    let mut parser = LibtestJsonParser::new();
+    for line in output.lines() {
+        parser.push_line(line);
+    }
+    parser.finish()
+}
+
+/// Parse a JUnit XML report (as written by nextest).
+///
+/// A `<testcase>` with a `<failure>` or `<error>` child failed; one with
+/// `<skipped>` is ignored; anything else (including nextest's
+/// `<flakyFailure>` retries that eventually passed) passed.
+pub fn parse_junit_xml(xml: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<testcase") {
+        rest = &rest[start..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let open_tag = &rest[..tag_end];
+        let (body, next) = if open_tag.ends_with('/') {
+            ("", tag_end + 1)
+        } else {
+            let end = rest.find("</testcase>").unwrap_or(rest.len());
+            (&rest[tag_end + 1..end], end)
+        };
+        rest = &rest[next..];
+
+        let Some(name) = xml_attr(open_tag, "name") else {
+            continue;
+        };
+        if body.contains("<skipped") {
+            continue;
+        }
+        let failure = xml_element(body, "failure").or_else(|| xml_element(body, "error"));
+        let crate_name = xml_attr(open_tag, "classname").map(|c| crate_from_binary_id(&c));
+        let Some((failure_tag, failure_text)) = failure else {
+            report.passed.push(PassedTest { name, crate_name });
+            continue;
+        };
+
+        // nextest runs each test in its own process, so the panic is usually on stderr
+        let output: Vec<String> = [
+            xml_element(body, "system-out").map(|(_, text)| text),
+            xml_element(body, "system-err").map(|(_, text)| text),
+            Some(failure_text),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+        let mut failure = failure_from_output(name, crate_name, &output.join("\n"), "nextest");
+        if failure.message.is_none() {
+            failure.message = xml_attr(failure_tag, "message");
+        }
+        report.failures.push(failure);
+    }
+    report
+}
+
+fn failure_from_output(
+    name: String,
+    crate_name: Option<String>,
+    output: &str,
+    source: &str,
+) -> TestFailure {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut message = None;
+    let mut file = None;
+    let mut line_no = None;
+
+    // Recent toolchains put the thread id in between: thread 'name' (1234) panicked at
+    if let Some(index) = lines
+        .iter()
+        .position(|l| l.starts_with("thread '") && l.contains(" panicked at "))
+    {
+        let rest = lines[index]
+            .split_once(" panicked at ")
+            .map(|(_, rest)| rest)
+            .unwrap_or("");
+        let location = if let Some(quoted) = rest.strip_prefix('\'') {
+            // Before Rust 1.73: thread 'name' panicked at 'message', src/lib.rs:10:5
+            let (text, location) = quoted.rsplit_once("', ").unwrap_or((quoted, ""));
+            message = Some(text.to_string());
+            location
+        } else {
+            // thread 'name' panicked at src/lib.rs:10:5:
+            // <message lines>
+            // note: run with `RUST_BACKTRACE=1` ...
+            let text: Vec<&str> = lines[index + 1..]
+                .iter()
+                .copied()
+                .take_while(|l| !l.starts_with("note: ") && !l.starts_with("stack backtrace:"))
+                .collect();
+            let text = text.join("\n").trim().to_string();
+            if !text.is_empty() {
+                message = Some(text);
+            }
+            rest.trim_end_matches(':')
+        };
+
+        let mut parts = location.rsplitn(3, ':');
+        let _column = parts.next();
+        line_no = parts.next().and_then(|l| l.parse().ok());
+        file = parts.next().map(str::to_string);
+    }
+
+    TestFailure {
+        name,
+        crate_name,
+        message,
+        file,
+        line: line_no,
+        output: output.trim().to_string(),
+        source: source.to_string(),
+    }
+}
+
+/// At most `MAX_OUTPUT_EXCERPT` characters of `output`, marked when cut
+fn excerpt(output: &str) -> String {
+    match output.char_indices().nth(MAX_OUTPUT_EXCERPT) {
+        Some((cut, _)) => format!("{}\n[... truncated]", &output[..cut]),
+        None => output.to_string(),
+    }
+}
+
+/// `     Running unittests src/lib.rs (target/debug/deps/my_crate-1a2b3c)` -> `my_crate`,
+/// `   Doc-tests my_crate` -> `my_crate` (`line` already trimmed)
+fn crate_from_running_line(line: &str) -> Option<String> {
+    if let Some(crate_name) = line.strip_prefix("Doc-tests ") {
+        return Some(crate_name.trim().to_string());
+    }
+    let path = line
+        .strip_prefix("Running ")?
+        .rsplit_once('(')?
+        .1
+        .strip_suffix(')')?;
+    let binary = path.rsplit(['/', '\\']).next()?;
+    let binary = binary.strip_suffix(".exe").unwrap_or(binary);
+    let (name, _hash) = binary.rsplit_once('-')?;
+    Some(name.to_string())
+}
+
+/// nextest binary ids are `<crate>`, `<crate>::<test target>` or `<crate>::bin/<name>`
+fn crate_from_binary_id(binary_id: &str) -> String {
+    binary_id
+        .split_once("::")
+        .map(|(crate_name, _)| crate_name)
+        .unwrap_or(binary_id)
+        .to_string()
+}
+
+/// Value of attribute `name` in an opening tag
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    loop {
+        let index = rest.find(name)?;
+        let before = rest[..index].chars().last();
+        let after = &rest[index + name.len()..];
+        rest = after;
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(after) = after.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+        let value = &after[1..];
+        let end = value.find(quote)?;
+        return Some(xml_unescape(&value[..end]));
+    }
+}
+
+/// First `<name ...>text</name>` (or `<name .../>`) in `body`: (opening tag, text)
+fn xml_element<'a>(body: &'a str, name: &str) -> Option<(&'a str, String)> {
+    let open = format!("<{}", name);
+    let mut search = 0;
+    let start = loop {
+        let start = search + body[search..].find(&open)?;
+        // Don't match a longer element name that shares the prefix
+        match body[start + open.len()..].chars().next() {
+            Some(c) if c.is_whitespace() || c == '>' || c == '/' => break start,
+            _ => search = start + open.len(),
+        }
+    };
+    let tag_end = start + body[start..].find('>')?;
+    let tag = &body[start..tag_end];
+    if tag.ends_with('/') {
+        return Some((tag, String::new()));
+    }
+    let close = format!("</{}>", name);
+    let text_end = body[tag_end..]
+        .find(&close)
+        .map(|i| tag_end + i)
+        .unwrap_or(body.len());
+    Some((tag, xml_text(&body[tag_end + 1..text_end])))
+}
+
+/// Element text: CDATA sections verbatim, everything else unescaped
+fn xml_text(raw: &str) -> String {
+    let mut text = String::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("<![CDATA[") {
+        text.push_str(&xml_unescape(&rest[..start]));
+        let cdata = &rest[start + "<![CDATA[".len()..];
+        let end = cdata.find("]]>").unwrap_or(cdata.len());
+        text.push_str(&cdata[..end]);
+        rest = cdata.get(end + "]]>".len()..).unwrap_or("");
+    }
+    text.push_str(&xml_unescape(rest));
+    text
+}
+
+fn xml_unescape(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        text.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let decoded = match &rest[1..semi] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                text.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::{KgBackend, LocalKGBridge, MemoryBackend};
+    use super::*;
+
+    /// `cargo test --no-fail-fast` in a workspace, stdout and stderr interleaved
+    const CARGO_TEST: &str = "   Compiling alpha v0.1.0 (/work/alpha)
+   Compiling beta v0.1.0 (/work/beta)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.84s
+     Running unittests src/lib.rs (target/debug/deps/alpha-3f2a9c1d0b7e6a54)
+
+running 4 tests
+test tests::adds ... ok
+test tests::parse ... FAILED
+test tests::renders ... ignored
+test tests::splits a ... b ... ok
+
+failures:
+
+---- tests::parse stdout ----
+parsing \"1 + 1\"
+
+thread 'tests::parse' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::parse
+
+test result: FAILED. 2 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+error: test failed, to rerun pass `-p alpha --lib`
+     Running unittests src/lib.rs (target/debug/deps/beta-0c9d8e7f6a5b4c3d)
+
+running 1 test
+test tests::parse ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+   Doc-tests beta
+
+running 1 test
+test src/lib.rs - render (line 5) ... FAILED
+
+failures:
+
+---- src/lib.rs - render (line 5) stdout ----
+Test executable failed (exit status: 101).
+
+stderr:
+thread 'main' panicked at 'not yet implemented', src/lib.rs:7:5
+
+failures:
+    src/lib.rs - render (line 5)
+
+test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.12s
+";
+
+    /// `cargo test -- -Z unstable-options --format json` plus nextest events
+    const LIBTEST_JSON: &str = r#"     Running unittests src/lib.rs (target/debug/deps/alpha-3f2a9c1d0b7e6a54)
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::parse" }
+{ "type": "test", "name": "tests::parse", "event": "failed", "stdout": "\nthread 'tests::parse' panicked at src/lib.rs:12:9:\nboom\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n" }
+{ "type": "test", "event": "started", "name": "tests::adds" }
+{ "type": "test", "name": "tests::adds", "event": "ok" }
+{ "type": "test", "name": "tests::must_panic", "event": "failed", "message": "test did not panic as expected" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 2, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 0.001 }
+not json at all
+{ "type": "test", "name": "beta::integration$tests::parse", "event": "ok" }
+{ "type": "test", "name": "gamma::bin/tool$cli::args", "event": "failed", "stdout": "thread 'cli::args' panicked at src/main.rs:3:5:\nbad args" }
+"#;
+
+    /// nextest's `target/nextest/ci/junit.xml`
+    const JUNIT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="nextest-run" tests="5" failures="1" errors="1" uuid="45c5bb8f" timestamp="2024-05-01T10:00:00.000+00:00" time="0.031">
+    <testsuite name="alpha" tests="3" disabled="1" errors="0" failures="1">
+        <testcase name="tests::parse" classname="alpha" timestamp="2024-05-01T10:00:00.001+00:00" time="0.004">
+            <failure type="test failure with exit code 101">thread &apos;tests::parse&apos; panicked at src/lib.rs:12:9:
+assertion failed: x &lt; 2</failure>
+            <system-out></system-out>
+            <system-err><![CDATA[
+thread 'tests::parse' panicked at src/lib.rs:12:9:
+assertion failed: x < 2 && <y>
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+]]></system-err>
+        </testcase>
+        <testcase classname="alpha" name="tests::adds" time="0.001"/>
+        <testcase name="tests::slow" classname="alpha"><skipped/></testcase>
+    </testsuite>
+    <testsuite name="alpha::integration" tests="2" disabled="0" errors="1" failures="0">
+        <testcase name="flaky" classname="alpha::integration" time="0.010">
+            <flakyFailure type="test failure" message="first try">first try</flakyFailure>
+        </testcase>
+        <testcase name="crashes" classname="alpha::integration" time="0.010">
+            <error message="test aborted with signal 6" type="test abort"/>
+        </testcase>
+    </testsuite>
+</testsuites>
+"#;
+
+    fn passed(report: &TestReport) -> Vec<(Option<&str>, &str)> {
+        report
+            .passed
+            .iter()
+            .map(|p| (p.crate_name.as_deref(), p.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_cargo_test_transcript() {
+        let report = parse_libtest_output(CARGO_TEST);
+
+        assert_eq!(report.failures.len(), 2);
+        let parse = &report.failures[0];
+        assert_eq!(parse.crate_name.as_deref(), Some("alpha"));
+        assert_eq!(parse.name, "tests::parse");
+        assert_eq!(
+            parse.message.as_deref(),
+            Some("assertion `left == right` failed\n  left: 1\n right: 2")
+        );
+        assert_eq!(
+            (parse.file.as_deref(), parse.line),
+            (Some("src/lib.rs"), Some(12))
+        );
+        assert!(parse.output.starts_with("parsing \"1 + 1\""));
+        assert_eq!(parse.source, "libtest");
+        assert_eq!(parse.signature(), "test failed: alpha::tests::parse");
+
+        // A doctest, with the pre-1.73 panic format
+        let doctest = &report.failures[1];
+        assert_eq!(doctest.crate_name.as_deref(), Some("beta"));
+        assert_eq!(doctest.name, "src/lib.rs - render (line 5)");
+        assert_eq!(doctest.message.as_deref(), Some("not yet implemented"));
+        assert_eq!(
+            (doctest.file.as_deref(), doctest.line),
+            (Some("src/lib.rs"), Some(7))
+        );
+
+        assert_eq!(
+            passed(&report),
+            vec![
+                (Some("alpha"), "tests::adds"),
+                (Some("alpha"), "tests::splits a ... b"),
+                (Some("beta"), "tests::parse"),
+            ]
+        );
+        // beta's pass doesn't resolve alpha's failure of the same name
+        let resolvable: Vec<String> = report.resolvable().map(PassedTest::signature).collect();
+        assert_eq!(
+            resolvable,
+            vec![
+                "test failed: alpha::tests::adds",
+                "test failed: alpha::tests::splits a ... b",
+                "test failed: beta::tests::parse",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_libtest_and_nextest_json() {
+        let report = parse_libtest_json(LIBTEST_JSON);
+
+        let failures: Vec<_> = report
+            .failures
+            .iter()
+            .map(|f| {
+                (
+                    f.crate_name.as_deref(),
+                    f.name.as_str(),
+                    f.message.as_deref(),
+                    f.line,
+                    f.source.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (
+                    Some("alpha"),
+                    "tests::parse",
+                    Some("boom"),
+                    Some(12),
+                    "libtest"
+                ),
+                (
+                    Some("alpha"),
+                    "tests::must_panic",
+                    Some("test did not panic as expected"),
+                    None,
+                    "libtest"
+                ),
+                (
+                    Some("gamma"),
+                    "cli::args",
+                    Some("bad args"),
+                    Some(3),
+                    "nextest"
+                ),
+            ]
+        );
+        assert_eq!(
+            passed(&report),
+            vec![
+                (Some("alpha"), "tests::adds"),
+                (Some("beta"), "tests::parse")
+            ]
+        );
+
+        let mut named = LibtestJsonParser::new().with_crate_name("delta");
+        named.push_line(r#"{ "type": "test", "name": "t", "event": "ok" }"#);
+        assert_eq!(passed(&named.finish()), vec![(Some("delta"), "t")]);
+    }
+
+    #[test]
+    fn parses_nextest_junit() {
+        let report = parse_junit_xml(JUNIT);
+
+        assert_eq!(report.failures.len(), 2);
+        let parse = &report.failures[0];
+        assert_eq!(
+            (parse.crate_name.as_deref(), parse.name.as_str()),
+            (Some("alpha"), "tests::parse")
+        );
+        // From stderr (CDATA, kept verbatim) rather than the escaped failure text
+        assert_eq!(
+            parse.message.as_deref(),
+            Some("assertion failed: x < 2 && <y>")
+        );
+        assert_eq!(
+            (parse.file.as_deref(), parse.line),
+            (Some("src/lib.rs"), Some(12))
+        );
+        assert!(parse.output.ends_with(
+            "thread 'tests::parse' panicked at src/lib.rs:12:9:\nassertion failed: x < 2"
+        ));
+        assert_eq!(parse.source, "nextest");
+
+        let crashes = &report.failures[1];
+        assert_eq!(crashes.crate_name.as_deref(), Some("alpha"));
+        assert_eq!(crashes.name, "crashes");
+        assert_eq!(
+            crashes.message.as_deref(),
+            Some("test aborted with signal 6")
+        );
+
+        assert_eq!(
+            passed(&report),
+            vec![(Some("alpha"), "tests::adds"), (Some("alpha"), "flaky")]
+        );
+    }
+
+    #[test]
+    fn xml_helpers() {
+        let tag = r#"<testcase classname="alpha::it" name='a &amp; b &#x1F980; &#65;' time="1""#;
+        assert_eq!(xml_attr(tag, "name").as_deref(), Some("a & b 🦀 A"));
+        assert_eq!(xml_attr(tag, "classname").as_deref(), Some("alpha::it"));
+        assert_eq!(xml_attr(tag, "missing"), None);
+        assert_eq!(xml_unescape("&bogus; &amp"), "&bogus; &amp");
+        assert_eq!(
+            xml_element("<failures/><failure message=\"m\">x</failure>", "failure"),
+            Some(("<failure message=\"m\"", "x".to_string()))
+        );
+    }
+
+    #[test]
+    fn crate_from_running_lines() {
+        for (line, expected) in [
+            (
+                "Running unittests src/lib.rs (target/debug/deps/my_crate-1a2b3c4d5e6f7a8b)",
+                Some("my_crate"),
+            ),
+            (
+                "Running tests/it.rs (target/debug/deps/it-0f1e2d3c4b5a6978)",
+                Some("it"),
+            ),
+            (
+                "Running unittests src\\main.rs (target\\debug\\deps\\tool-0f1e2d3c.exe)",
+                Some("tool"),
+            ),
+            ("Doc-tests my_crate", Some("my_crate")),
+            ("Running `target/debug/tool`", None),
+            ("running 3 tests", None),
+        ] {
+            assert_eq!(
+                crate_from_running_line(line).as_deref(),
+                expected,
+                "{}",
+                line
+            );
+        }
+        assert_eq!(crate_from_binary_id("alpha::bin/tool"), "alpha");
+        assert_eq!(crate_from_binary_id("alpha"), "alpha");
+    }
+
+    #[test]
+    fn failed_then_passed_resolves_only_that_crate() {
+        let backend = Arc::new(MemoryBackend::new());
+        let kg = LocalKGBridge::with_backend(backend.clone()).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let first = parse_libtest_output(
+            "     Running unittests src/lib.rs (target/debug/deps/alpha-1111)
+test tests::parse ... FAILED
+     Running unittests src/lib.rs (target/debug/deps/beta-2222)
+test tests::parse ... FAILED
+",
+        );
+        let second = parse_libtest_output(
+            "     Running unittests src/lib.rs (target/debug/deps/beta-2222)
+test tests::parse ... ok
+",
+        );
+        let resolved = runtime.block_on(async {
+            kg.capture_test_failures(&first.failures).await.unwrap();
+            kg.resolve_passed_tests(&second).await.unwrap()
+        });
+        assert_eq!(resolved, vec!["test failed: beta::tests::parse"]);
+
+        let open: Vec<String> = backend
+            .search_patterns("test failed", 10)
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.resolved_at.is_none())
+            .map(|p| p.signature)
+            .collect();
+        assert_eq!(open, vec!["test failed: alpha::tests::parse"]);
+    }
+}
@@ -0,0 +1,395 @@
+//! `tracing_subscriber::Layer` that records `ERROR`/`WARN` events in the Local KG.
+//!
+//! Matching events are captured with their target, fields and span context.
+//! Captures are handed to a background writer thread, so logging never blocks
+//! on the database. Sampling, per-target filters and a dedup window keep a hot
+//! loop from flooding the store.
+//!
+//! ```ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! let kg = get_local_kg(None)?;
+//! tracing_subscriber::registry()
+//!     .with(tracing_subscriber::fmt::layer())
+//!     .with(KgLayer::new(&kg).with_target_filter("hyper", LevelFilter::ERROR))
+//!     .init();
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::backend::KgBackend;
+use super::{bridge_module_path, Category, ErrorCapture, LocalKGBridge, Severity};
+
+/// Identical events within this window are recorded once
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Captures waiting for the writer; further events are dropped while full
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Dedup entries kept before expired ones are pruned
+const DEDUP_PRUNE_THRESHOLD: usize = 4096;
+
+/// Layer that turns error/warning events into Local KG captures
+pub struct KgLayer {
+    sender: SyncSender<ErrorCapture>,
+    min_level: LevelFilter,
+    target_filters: Vec<(String, LevelFilter)>,
+    sample_rate: f64,
+    dedup_window: Duration,
+    state: Mutex<LayerState>,
+}
+
+struct LayerState {
+    /// signature -> (last recorded, occurrences suppressed since)
+    seen: HashMap<String, (Instant, u64)>,
+    rng: u64,
+}
+
+impl KgLayer {
+    /// Record `WARN` and above, unsampled, with the default dedup window
+    pub fn new(bridge: &LocalKGBridge) -> Self {
+        Self::with_backend(bridge.backend.clone())
+    }
+
+    fn with_backend(backend: Arc<dyn KgBackend>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<ErrorCapture>(QUEUE_CAPACITY);
+        let spawned = thread::Builder::new()
+            .name("local-kg-tracing-writer".to_string())
+            .spawn(move || {
+                for capture in receiver {
+                    if let Err(e) = backend.capture(&capture) {
+                        tracing::warn!("Failed to capture tracing event to Local KG: {}", e);
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            eprintln!("Local KG: tracing writer thread failed to start: {}", e);
+        }
+
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self {
+            sender,
+            min_level: LevelFilter::WARN,
+            target_filters: Vec::new(),
+            sample_rate: 1.0,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            state: Mutex::new(LayerState {
+                seen: HashMap::new(),
+                rng: seed | 1,
+            }),
+        }
+    }
+
+    /// Lowest level recorded for targets without a specific filter: `WARN`
+    /// (default) or `ERROR`. More verbose levels are never recorded.
+    pub fn with_min_level(mut self, level: LevelFilter) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Override the minimum level for targets starting with `prefix`.
+    /// The longest matching prefix wins; use `LevelFilter::OFF` to ignore a target.
+    pub fn with_target_filter(mut self, prefix: &str, level: LevelFilter) -> Self {
+        self.target_filters.push((prefix.to_string(), level));
+        self
+    }
+
+    /// Record only this fraction (0.0–1.0) of matching events. `ERROR`s are always kept.
+    pub fn with_sample_rate(mut self, rate: f64) -> Self {
+        self.sample_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Drop repeats of the same target+message within `window` (zero disables dedup)
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    fn enabled_for(&self, target: &str, level: &Level) -> bool {
+        // Never record the bridge's own diagnostics: a failing backend would loop
+        if target.starts_with(bridge_module_path()) {
+            return false;
+        }
+        let filter = self
+            .target_filters
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, filter)| *filter)
+            .unwrap_or(self.min_level);
+        filter >= *level && *level <= Level::WARN
+    }
+
+    /// Apply sampling and dedup. Returns how many earlier repeats were suppressed,
+    /// or `None` if this event should be dropped.
+    fn admit(&self, signature: &str, level: &Level) -> Option<u64> {
+        let mut state = self.state.lock().ok()?;
+
+        if *level != Level::ERROR && self.sample_rate < 1.0 {
+            // xorshift64: good enough for sampling, no extra dependency
+            state.rng ^= state.rng << 13;
+            state.rng ^= state.rng >> 7;
+            state.rng ^= state.rng << 17;
+            if (state.rng >> 11) as f64 / (1u64 << 53) as f64 >= self.sample_rate {
+                return None;
+            }
+        }
+
+        if self.dedup_window.is_zero() {
+            return Some(0);
+        }
+        let now = Instant::now();
+        if state.seen.len() > DEDUP_PRUNE_THRESHOLD {
+            let window = self.dedup_window;
+            state
+                .seen
+                .retain(|_, (last, _)| now.duration_since(*last) < window);
+        }
+        match state.seen.get_mut(signature) {
+            Some((last, suppressed)) if now.duration_since(*last) < self.dedup_window => {
+                *suppressed += 1;
+                None
+            }
+            Some((last, suppressed)) => {
+                let repeats = *suppressed;
+                *last = now;
+                *suppressed = 0;
+                Some(repeats)
+            }
+            None => {
+                state.seen.insert(signature.to_string(), (now, 0));
+                Some(0)
+            }
+        }
+    }
+}
+
+impl<S> Layer<S> for KgLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = JsonVisitor::default();
+            attrs.record(&mut fields);
+            span.extensions_mut().insert(SpanFields(fields.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+                let mut visitor = JsonVisitor {
+                    fields: std::mem::take(fields),
+                };
+                values.record(&mut visitor);
+                *fields = visitor.fields;
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = metadata.level();
+        if !self.enabled_for(metadata.target(), level) {
+            return;
+        }
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        let message = match visitor.fields.remove("message") {
+            Some(Value::String(message)) => message,
+            Some(other) => other.to_string(),
+            None => metadata.name().to_string(),
+        };
+        let signature = format!("{}: {}", metadata.target(), message);
+
+        let Some(suppressed) = self.admit(&signature, level) else {
+            return;
+        };
+
+        let spans: Vec<Value> = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| {
+                        let extensions = span.extensions();
+                        let fields = extensions
+                            .get::<SpanFields>()
+                            .map(|SpanFields(fields)| Value::Object(fields.clone()))
+                            .unwrap_or(Value::Null);
+                        json!({ "name": span.name(), "fields": fields })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let capture = ErrorCapture {
+            level: Some(level.as_str().to_string()),
+            file_path: metadata.file().map(str::to_string),
+            line_number: metadata.line(),
+            ..ErrorCapture::new(signature, Category::Runtime)
+        }
+        .with_severity(if *level == Level::ERROR {
+            Severity::High
+        } else {
+            Severity::Medium
+        })
+        .with_description(message)
+        .with_context(json!({
+            "target": metadata.target(),
+            "module_path": metadata.module_path(),
+            "fields": Value::Object(visitor.fields),
+            "spans": spans,
+            "suppressed_repeats": suppressed,
+        }));
+
+        if let Err(TrySendError::Disconnected(_)) = self.sender.try_send(capture) {
+            eprintln!("Local KG: tracing writer is not running; event dropped");
+        }
+    }
+}
+
+/// Recorded fields of a span, stored in its extensions
+struct SpanFields(Map<String, Value>);
+
+/// Collects event/span fields as JSON values
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.fields
+            .insert(field.name().to_string(), json!(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::prelude::*;
+
+    use super::super::memory::MemoryBackend;
+    use super::super::KgEvent;
+    use super::*;
+
+    /// Events recorded so far, once the one with `content` has been written.
+    /// The writer is FIFO, so everything queued before it is stored too.
+    fn wait_for_event(backend: &MemoryBackend, content: &str) -> Vec<KgEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let events = backend.recent_events(50).unwrap();
+            let written = events.iter().any(|e| e.content.as_deref() == Some(content));
+            if written || Instant::now() > deadline {
+                return events;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn records_errors_and_warnings_only() {
+        let backend = Arc::new(MemoryBackend::new());
+        let layer = KgLayer::with_backend(backend.clone());
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app::db", "connected");
+            tracing::debug!(target: "app::db", "query plan");
+            // Targets inside the bridge are what its own writer logs on failure
+            tracing::error!("backend unavailable");
+            let span = tracing::info_span!("request", id = 42);
+            let _guard = span.enter();
+            tracing::warn!(target: "app::db", retries = 3, "slow query");
+            tracing::error!(target: "app::db", "connection reset");
+        });
+
+        let events = wait_for_event(&backend, "connection reset");
+        assert_eq!(events.len(), 2);
+        let error = events
+            .iter()
+            .find(|e| e.content.as_deref() == Some("connection reset"))
+            .unwrap();
+        assert_eq!(error.severity(), Some(Severity::High));
+        assert_eq!(error.category(), Some(Category::Runtime));
+        assert_eq!(error.metadata["level"], "ERROR");
+        assert_eq!(error.metadata["signature"], "app::db: connection reset");
+        assert_eq!(error.metadata["file_path"], file!());
+        assert_eq!(error.metadata["context"]["spans"][0]["fields"]["id"], 42);
+
+        let warning = events
+            .iter()
+            .find(|e| e.content.as_deref() == Some("slow query"))
+            .unwrap();
+        assert_eq!(warning.severity(), Some(Severity::Medium));
+        assert_eq!(warning.metadata["context"]["fields"]["retries"], 3);
+    }
+
+    #[test]
+    fn filters_by_level_and_target_and_dedups() {
+        let backend = Arc::new(MemoryBackend::new());
+        let layer = KgLayer::with_backend(backend.clone())
+            .with_min_level(LevelFilter::ERROR)
+            .with_target_filter("noisy", LevelFilter::OFF);
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "app", "disk almost full");
+            tracing::error!(target: "noisy::pool", "pool exhausted");
+            for _ in 0..3 {
+                tracing::error!(target: "app", "disk full");
+            }
+            tracing::error!(target: "app", "write failed");
+        });
+
+        let events = wait_for_event(&backend, "write failed");
+        let mut messages: Vec<_> = events.iter().filter_map(|e| e.content.clone()).collect();
+        messages.sort();
+        assert_eq!(messages, ["disk full", "write failed"]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Persistent Python worker backend.
+//!
+//! Keeps one `python -m local_kg.kg_worker` process alive and talks to it with
+//! newline-delimited JSON-RPC 2.0 over stdin/stdout. Arguments travel as JSON,
+//! so nothing is templated into Python source. The worker is restarted on the
+//! next call if it crashes, and killed if a request exceeds the timeout.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::rpc::{RpcCall, RpcRequest, RpcResponse};
+use super::NebulaKgError;
+
+/// Default time a single request may take before the worker is killed
+pub const DEFAULT_WORKER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A running worker process
+struct WorkerProcess {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<String>,
+}
+
+impl WorkerProcess {
+    fn spawn(python_cmd: &str, db_path: &str) -> Result<Self, NebulaKgError> {
+        let mut child = Command::new(python_cmd)
+            .args(["-u", "-m", "local_kg.kg_worker", "--db", db_path])
+            .current_dir(".")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+
+        let (stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => {
+                let _ = child.kill();
+                return Err(NebulaKgError::BackendUnavailable(
+                    "Python worker pipes unavailable".to_string(),
+                ));
+            }
+        };
+
+        // Reader thread: lets the caller wait with a timeout. Ends when the worker exits.
+        let (tx, responses) = mpsc::channel();
+        thread::Builder::new()
+            .name("local-kg-worker-reader".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    match line {
+                        Ok(line) => {
+                            if tx.send(line).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            child,
+            stdin,
+            responses,
+        })
+    }
+
+    fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Backend that forwards every call to a long-lived Python worker
+pub struct PythonWorkerBackend {
+    db_path: String,
+    python_cmd: String,
+    timeout: Duration,
+    process: Mutex<Option<WorkerProcess>>,
+    next_id: Mutex<u64>,
+}
+
+impl PythonWorkerBackend {
+    /// Create the backend. The worker is started lazily on the first call.
+    pub fn new(db_path: &str, python_cmd: &str) -> Self {
+        Self {
+            db_path: db_path.to_string(),
+            python_cmd: python_cmd.to_string(),
+            timeout: DEFAULT_WORKER_TIMEOUT,
+            process: Mutex::new(None),
+            next_id: Mutex::new(1),
+        }
+    }
+
+    /// Override the per-request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Stop the worker process (a new one is started on the next call)
+    pub fn shutdown(&self) {
+        if let Ok(mut process) = self.process.lock() {
+            if let Some(worker) = process.take() {
+                worker.kill();
+            }
+        }
+    }
+}
+
+impl Drop for PythonWorkerBackend {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl RpcCall for PythonWorkerBackend {
+    /// Send one request over the running worker, restarting it if needed
+    fn request(&self, method: &str, params: Value) -> Result<RpcResponse, NebulaKgError> {
+        let id = {
+            let mut next_id = self.next_id.lock()?;
+            let id = *next_id;
+            *next_id += 1;
+            id
+        };
+        let line = serde_json::to_string(&RpcRequest {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        })?;
+
+        let mut process = self.process.lock()?;
+
+        // Restart a worker that exited since the last call
+        if let Some(worker) = process.as_mut() {
+            if worker.child.try_wait()?.is_some() {
+                tracing::warn!("Local KG Python worker exited; restarting");
+                if let Some(dead) = process.take() {
+                    dead.kill();
+                }
+            }
+        }
+
+        // If the request can't even be written the worker died before seeing it,
+        // so one retry on a fresh process is safe.
+        let mut attempts = 0;
+        loop {
+            if process.is_none() {
+                *process = Some(WorkerProcess::spawn(&self.python_cmd, &self.db_path)?);
+            }
+            let worker = process.as_mut().expect("worker just spawned");
+            let written = writeln!(worker.stdin, "{}", line).and_then(|_| worker.stdin.flush());
+            match written {
+                Ok(()) => break,
+                Err(e) if attempts == 0 => {
+                    tracing::warn!("Local KG Python worker unavailable ({}); restarting", e);
+                    if let Some(dead) = process.take() {
+                        dead.kill();
+                    }
+                    attempts += 1;
+                }
+                Err(e) => {
+                    return Err(NebulaKgError::BackendUnavailable(format!(
+                        "Python worker unavailable: {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let worker = process.as_mut().expect("worker is running");
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match worker.responses.recv_timeout(remaining) {
+                Ok(raw) => {
+                    let response: RpcResponse = serde_json::from_str(&raw)?;
+                    // Responses to requests that previously timed out are discarded
+                    if response.id != Some(id) {
+                        continue;
+                    }
+                    return Ok(response);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(stuck) = process.take() {
+                        stuck.kill();
+                    }
+                    return Err(NebulaKgError::BackendUnavailable(format!(
+                        "Python worker timed out after {:?} on '{}'",
+                        self.timeout, method
+                    )));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(dead) = process.take() {
+                        dead.kill();
+                    }
+                    return Err(NebulaKgError::BackendUnavailable(format!(
+                        "Python worker exited while handling '{}'",
+                        method
+                    )));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+/*!
+ * cargo-nebula: run cargo and record every failure in the Local KG
+ *
+ * Installed next to the Rust bridge as `src/bin/cargo-nebula.rs`; after
+ * `cargo install --path .` it is available as a cargo subcommand.
+ *
+ * Usage:
+ *   cargo nebula build|check|test|clippy [cargo args...]
+ *
+ * Output is streamed as cargo would print it. Compiler errors, clippy lints
+ * and test failures are captured, tests that pass again resolve their earlier
+ * failures, and when the command fails the best known solutions for the top
+ * errors (or for similar earlier errors) are printed from the KG.
+ */
+
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+// Pull in the bridge from src/ without requiring a library target
+#[path = ".."]
+#[allow(unused)]
+mod src {
+    pub mod local_kg_bridge;
+}
+
+use src::local_kg_bridge::{
+    Diagnostic, DiagnosticCollector, LibtestParser, LocalKGBridge, NebulaKgError, SimilaritySearch,
+    TestReport,
+};
+
+const SUBCOMMANDS: &[&str] = &["build", "check", "test", "clippy"];
+
+/// How many failures get a solution lookup after a failed run
+const TOP_ERRORS: usize = 5;
+
+/// Cargo's JSON output, with diagnostics rendered as cargo would print them
+const JSON_FORMAT: &str = "json-diagnostic-rendered-ansi";
+
+/// A line of cargo's output
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+fn main() -> ExitCode {
+    // `cargo nebula test` runs us as `cargo-nebula nebula test`
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("nebula") {
+        args.remove(0);
+    }
+    let Some(subcommand) = args.first().cloned() else {
+        return usage();
+    };
+    if !SUBCOMMANDS.contains(&subcommand.as_str()) {
+        return usage();
+    }
+
+    let (cargo_args, user_json) = with_json_format(&args[1..]);
+    let mut command = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command
+        .arg(&subcommand)
+        .args(&cargo_args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("❌ Failed to run cargo: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Cargo prints `Running <test binary>` on stderr before it starts the
+    // binary, so with both pipes read into one channel that line arrives
+    // ahead of the binary's output and the test parser learns the crate.
+    let (sender, lines) = mpsc::channel();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward(stdout, sender.clone(), Line::Stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward(stderr, sender.clone(), Line::Stderr));
+    }
+    drop(sender);
+
+    let mut diagnostics = DiagnosticCollector::new();
+    let mut tests = LibtestParser::new();
+    let mut out = io::stdout();
+    for line in lines {
+        let line = match line {
+            Line::Stderr(line) => {
+                eprintln!("{}", line);
+                tests.push_line(&line);
+                continue;
+            }
+            Line::Stdout(line) => line,
+        };
+        match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(message) if message.get("reason").is_some() => {
+                if user_json {
+                    let _ = writeln!(out, "{}", line);
+                } else if message["reason"] == "compiler-message" {
+                    // Show diagnostics the way cargo would, drop the rest
+                    if let Some(rendered) = message["message"]["rendered"].as_str() {
+                        eprint!("{}", rendered);
+                    }
+                }
+                diagnostics.push_line(&line);
+            }
+            _ => {
+                // Test harness output and anything else is passed through
+                let _ = writeln!(out, "{}", line);
+                tests.push_line(&line);
+            }
+        }
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("❌ Failed to wait for cargo: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let diagnostics = diagnostics.into_diagnostics();
+    let tests = tests.finish();
+    if let Err(e) = record(&diagnostics, &tests, status.success()) {
+        eprintln!("⚠️  Local KG unavailable: {}", e);
+    }
+
+    match status.code() {
+        Some(0) => ExitCode::SUCCESS,
+        Some(code) => ExitCode::from(u8::try_from(code).unwrap_or(1)),
+        None => ExitCode::FAILURE,
+    }
+}
+
+/// Send each line of `pipe` to `sender` from a new thread
+fn forward(
+    pipe: impl io::Read + Send + 'static,
+    sender: mpsc::Sender<Line>,
+    wrap: fn(String) -> Line,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if sender.send(wrap(line)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Cargo arguments with a JSON `--message-format`, and whether the user asked
+/// for JSON themselves (then it is passed through to stdout).
+///
+/// Diagnostics are only captured from JSON, so a `human` or `short` format is
+/// replaced by its JSON equivalent; the rendered text printed is the same.
+/// Arguments after `--` belong to the test binary and are left alone.
+fn with_json_format(args: &[String]) -> (Vec<String>, bool) {
+    let split = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let (cargo_args, binary_args) = args.split_at(split);
+
+    let mut kept = Vec::new();
+    let mut formats = Vec::new();
+    let mut iter = cargo_args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(value) = arg.strip_prefix("--message-format=") {
+            formats.extend(value.split(',').map(str::to_string));
+        } else if arg == "--message-format" {
+            if let Some(value) = iter.next() {
+                formats.extend(value.split(',').map(str::to_string));
+            }
+        } else {
+            kept.push(arg.clone());
+        }
+    }
+
+    let user_json = formats.iter().any(|f| f.starts_with("json"));
+    if user_json {
+        return (args.to_vec(), true);
+    }
+    let mut format = JSON_FORMAT.to_string();
+    if formats.iter().any(|f| f == "short") {
+        format.push_str(",json-diagnostic-short");
+    }
+    kept.push(format!("--message-format={}", format));
+    kept.extend(binary_args.iter().cloned());
+    (kept, false)
+}
+
+/// Capture everything, then (on failure) print known solutions for the top errors
+fn record(
+    diagnostics: &[Diagnostic],
+    tests: &TestReport,
+    success: bool,
+) -> Result<(), NebulaKgError> {
+    let failures = &tests.failures;
+    if diagnostics.is_empty() && failures.is_empty() && tests.passed.is_empty() {
+        return Ok(());
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .map_err(NebulaKgError::Io)?;
+    runtime.block_on(async {
+        let kg = LocalKGBridge::new(None)?;
+        kg.capture_diagnostics(diagnostics).await?;
+        kg.capture_test_failures(failures).await?;
+        let resolved = kg.resolve_passed_tests(tests).await?;
+        if !diagnostics.is_empty() || !failures.is_empty() {
+            eprintln!(
+                "📝 Recorded {} diagnostic(s) and {} test failure(s) in the Local KG",
+                diagnostics.len(),
+                failures.len()
+            );
+        }
+        for signature in &resolved {
+            eprintln!("✅ Resolved: {}", signature);
+        }
+
+        if success {
+            return Ok(());
+        }
+
+        let top: Vec<String> = diagnostics
+            .iter()
+            .filter(|d| d.is_error())
+            .map(|d| d.signature())
+            .chain(failures.iter().map(|f| f.signature()))
+            .take(TOP_ERRORS)
+            .collect();
+        let mut printed_header = false;
+        for signature in &top {
+            let search = SimilaritySearch::new(signature.as_str())
+                .with_require_solution(true)
+                .with_limit(1);
+            let Some(similar) = kg.find_similar(search).await?.into_iter().next() else {
+                continue;
+            };
+            if !printed_header {
+                eprintln!("\n💡 Known solutions from the Local KG:");
+                printed_header = true;
+            }
+            let pattern = similar.pattern;
+            if pattern.signature == *signature {
+                eprintln!("  {} (seen {}×)", signature, pattern.occurrence_count);
+            } else {
+                eprintln!(
+                    "  {} (similar to {}, {:.0}% match)",
+                    signature,
+                    pattern.signature,
+                    similar.score * 100.0
+                );
+            }
+            if let Some(solution) = pattern.solution {
+                eprintln!("    → {}", solution);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn usage() -> ExitCode {
+    eprintln!("Usage: cargo nebula <build|check|test|clippy> [cargo args...]");
+    ExitCode::FAILURE
+}
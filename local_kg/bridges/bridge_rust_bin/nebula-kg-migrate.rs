@@ -0,0 +1,104 @@
+/*!
+ * nebula-kg-migrate: inspect and migrate a Local KG database file
+ *
+ * Installed next to the Rust bridge as `src/bin/nebula-kg-migrate.rs`.
+ *
+ * Usage:
+ *   cargo run --bin nebula-kg-migrate -- [--db PATH] [status|up]
+ *
+ *   status  Show the detected schema version and pending migrations
+ *   up      Apply pending migrations (default)
+ */
+
+use std::env;
+use std::process::ExitCode;
+
+// Pull in the bridge from src/ without requiring a library target
+#[path = ".."]
+#[allow(unused)]
+mod src {
+    pub mod local_kg_bridge;
+}
+
+use src::local_kg_bridge::{self as kg, NebulaKgError};
+
+const DEFAULT_DB_PATH: &str = "local_kg/universal_memory.sqlite";
+
+fn main() -> ExitCode {
+    let mut db_path =
+        env::var("NEBULA_LOCAL_KG_DB").unwrap_or_else(|_| DEFAULT_DB_PATH.to_string());
+    let mut command = "up".to_string();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => match args.next() {
+                Some(path) => db_path = path,
+                None => return usage("--db requires a path"),
+            },
+            "status" | "up" => command = arg,
+            "-h" | "--help" => return usage(""),
+            other => return usage(&format!("unknown argument: {}", other)),
+        }
+    }
+
+    let result = match command.as_str() {
+        "status" => status(&db_path),
+        _ => up(&db_path),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn status(db_path: &str) -> Result<(), NebulaKgError> {
+    let conn =
+        rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version = kg::detect_schema_version(&conn)?;
+    println!("Database: {}", db_path);
+    println!(
+        "Schema version: {}",
+        version.as_deref().unwrap_or("(empty)")
+    );
+    println!("Bridge expects: {}", kg::SCHEMA_VERSION);
+
+    let pending = kg::pending_migrations(&conn)?;
+    if pending.is_empty() {
+        println!("✅ Up to date");
+    } else {
+        println!("Pending migrations:");
+        for migration in pending {
+            println!("  {}  {}", migration.version, migration.description);
+        }
+    }
+    Ok(())
+}
+
+fn up(db_path: &str) -> Result<(), NebulaKgError> {
+    let applied = kg::migrate_db(db_path)?;
+    if applied.is_empty() {
+        println!("✅ {} is already at schema {}", db_path, kg::SCHEMA_VERSION);
+    } else {
+        for version in &applied {
+            println!("Applied migration {}", version);
+        }
+        println!("✅ {} migrated to schema {}", db_path, kg::SCHEMA_VERSION);
+    }
+    Ok(())
+}
+
+fn usage(error: &str) -> ExitCode {
+    if !error.is_empty() {
+        eprintln!("❌ {}", error);
+    }
+    eprintln!("Usage: nebula-kg-migrate [--db PATH] [status|up]");
+    if error.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,315 @@
+"""
+Local KG Worker (JSON-RPC over stdin/stdout)
+
+Long-lived process used by language bridges that cannot open SQLite natively.
+Reads one JSON-RPC 2.0 request per line from stdin and writes one response per
+line to stdout. Arguments arrive as JSON, so no caller input is ever evaluated
+as code.
+
+Usage:
+    python -u -m local_kg.kg_worker --db local_kg/universal_memory.sqlite
+
+Request:
+    {"jsonrpc": "2.0", "id": 1, "method": "capture_error", "params": {...}}
+Response:
+    {"jsonrpc": "2.0", "id": 1, "result": "<event id>"}
+    {"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "..."}}
+"""
+import argparse
+import inspect
+import json
+import sys
+from typing import Any, Callable, Dict, List, Optional, Sequence
+
+from local_kg.local_kg import LocalKG, DB_PATH, InvalidValue
+
+# JSON-RPC 2.0 error codes
+PARSE_ERROR = -32700
+INVALID_REQUEST = -32600
+METHOD_NOT_FOUND = -32601
+INVALID_PARAMS = -32602
+INTERNAL_ERROR = -32603
+NOT_FOUND = -32004  # Application error: pattern/event does not exist
+INVALID_VALUE = -32005  # Application error: argument doesn't fit the call
+
+
+def capture_error(
+    kg: LocalKG,
+    signature: str,
+    category: str,
+    language: str,
+    severity: str,
+    description: Optional[str] = None,
+    context: Optional[Dict[str, Any]] = None,
+    level: Optional[str] = None,
+    phase: Optional[str] = None,
+    constellation: Optional[str] = None,
+    file_path: Optional[str] = None,
+    line_number: Optional[int] = None,
+    error_code: Optional[str] = None,
+    stack_trace: Optional[str] = None,
+    technologies: Optional[List[str]] = None
+) -> str:
+    return kg.capture_error(
+        error_signature=signature,
+        error_category=category,
+        description=description,
+        context=context,
+        severity=severity,
+        language=language,
+        phase=constellation or phase,
+        details={
+            "level": level,
+            "phase": phase,
+            "constellation": constellation,
+            "file_path": file_path,
+            "line_number": line_number,
+            "error_code": error_code,
+            "stack_trace": stack_trace,
+            "technologies": technologies or None,
+        }
+    )
+
+
+def _with_solutions(kg: LocalKG, pattern: Dict[str, Any]) -> Dict[str, Any]:
+    pattern["solutions"] = kg.get_solutions(pattern["signature"])
+    return pattern
+
+
+def search_patterns(kg: LocalKG, query: str, limit: int = 10):
+    return [_with_solutions(kg, p) for p in kg.search_patterns(query, limit)]
+
+
+def search(
+    kg: LocalKG,
+    match: str,
+    limit: int = 10,
+    category: Optional[str] = None,
+    include_resolved: bool = True,
+    highlight_open: str = "[",
+    highlight_close: str = "]",
+    column_weights: Sequence[float] = (10.0, 5.0, 2.0),
+    snippet_tokens: int = 16
+):
+    hits = kg.search_full_text(
+        match, limit, category, include_resolved, highlight_open, highlight_close,
+        column_weights, snippet_tokens
+    )
+    for hit in hits:
+        _with_solutions(kg, hit["pattern"])
+    return hits
+
+
+def add_solution(
+    kg: LocalKG,
+    pattern_id: str,
+    solution_text: str,
+    effectiveness: Any = 3,
+    title: Optional[str] = None,
+    code_snippet: Optional[str] = None,
+    resolution_steps: Optional[List[str]] = None,
+    time_to_resolve_minutes: Optional[int] = None,
+    was_successful: Optional[bool] = None
+) -> str:
+    row = kg.conn.execute(
+        "SELECT signature FROM patterns WHERE id = ? OR signature = ?",
+        (pattern_id, pattern_id)
+    ).fetchone()
+    if row is None:
+        raise LookupError(f"pattern {pattern_id}")
+    return kg.add_solution(
+        row['signature'],
+        solution_text,
+        effectiveness,
+        details={
+            "title": title,
+            "code_snippet": code_snippet,
+            "resolution_steps": resolution_steps or None,
+            "time_to_resolve_minutes": time_to_resolve_minutes,
+            "was_successful": was_successful,
+        }
+    )
+
+
+def record_feedback(
+    kg: LocalKG,
+    solution_id: str,
+    was_helpful: bool,
+    resolution_time_minutes: Optional[int] = None,
+    comment: Optional[str] = None
+) -> str:
+    return kg.record_feedback(solution_id, was_helpful, resolution_time_minutes, comment)
+
+
+def resolve_pattern(kg: LocalKG, signature: str, content: Optional[str] = None, context: Any = None) -> Optional[str]:
+    return kg.resolve_pattern(signature, content, context)
+
+
+def resolve_error(
+    kg: LocalKG,
+    error_event_id: str,
+    solution: Optional[Dict[str, Any]] = None,
+    solution_id: Optional[str] = None
+) -> Dict[str, Any]:
+    return kg.resolve_error(error_event_id, solution, solution_id)
+
+
+def open_errors(kg: LocalKG, limit: int = 10):
+    events = kg.open_errors(limit)
+    for event in events:
+        event['metadata'] = json.loads(event['metadata']) if event.get('metadata') else None
+    return events
+
+
+def resolution_stats(kg: LocalKG):
+    return kg.get_resolution_stats()
+
+
+def unsynced_patterns(kg: LocalKG, limit: int = 100):
+    return [_with_solutions(kg, p) for p in kg.unsynced_patterns(limit)]
+
+
+def unsynced_solutions(kg: LocalKG, limit: int = 100):
+    return kg.unsynced_solutions(limit)
+
+
+def record_sync(kg: LocalKG, records: List[Dict[str, Any]]) -> None:
+    kg.record_sync(records)
+
+
+def sync_history(kg: LocalKG, limit: int = 10):
+    return kg.get_sync_history(limit)
+
+
+def enqueue_sync(kg: LocalKG, items: List[Dict[str, Any]]) -> int:
+    return kg.enqueue_sync(items)
+
+
+def due_sync_items(kg: LocalKG, sync_type: str, limit: int = 100):
+    return kg.due_sync_items(sync_type, limit)
+
+
+def update_sync_items(kg: LocalKG, updates: List[Dict[str, Any]]) -> None:
+    kg.update_sync_items(updates)
+
+
+def sync_outbox(kg: LocalKG, status: Optional[str] = None, limit: int = 10):
+    return kg.get_sync_outbox(status, limit)
+
+
+def get_summary(kg: LocalKG):
+    summary = kg.get_pattern_summary()
+    for pattern in summary["top_patterns"]:
+        _with_solutions(kg, pattern)
+    return summary
+
+
+def record_event(kg: LocalKG, event_type: str, phase: str, content: str, metadata: Any = None) -> str:
+    return kg.record_event(event_type, phase, content, metadata)
+
+
+def recent_events(kg: LocalKG, limit: int = 10):
+    events = kg.get_recent_events(limit)
+    for event in events:
+        event['metadata'] = json.loads(event['metadata']) if event.get('metadata') else None
+    return events
+
+
+def schema_version(kg: LocalKG):
+    return kg.get_schema_version()
+
+
+def ping(kg: LocalKG) -> str:
+    return "pong"
+
+
+METHODS: Dict[str, Callable[..., Any]] = {
+    "capture_error": capture_error,
+    "search_patterns": search_patterns,
+    "search": search,
+    "add_solution": add_solution,
+    "record_feedback": record_feedback,
+    "resolve_pattern": resolve_pattern,
+    "resolve_error": resolve_error,
+    "open_errors": open_errors,
+    "resolution_stats": resolution_stats,
+    "get_summary": get_summary,
+    "record_event": record_event,
+    "recent_events": recent_events,
+    "unsynced_patterns": unsynced_patterns,
+    "unsynced_solutions": unsynced_solutions,
+    "record_sync": record_sync,
+    "sync_history": sync_history,
+    "enqueue_sync": enqueue_sync,
+    "due_sync_items": due_sync_items,
+    "update_sync_items": update_sync_items,
+    "sync_outbox": sync_outbox,
+    "schema_version": schema_version,
+    "ping": ping,
+}
+
+
+def handle(kg: LocalKG, line: str) -> Dict[str, Any]:
+    """Dispatch a single request line and build the response object."""
+    try:
+        request = json.loads(line)
+    except json.JSONDecodeError as e:
+        return {"jsonrpc": "2.0", "id": None, "error": {"code": PARSE_ERROR, "message": str(e)}}
+
+    if not isinstance(request, dict) or not isinstance(request.get("method"), str):
+        return {"jsonrpc": "2.0", "id": None, "error": {"code": INVALID_REQUEST, "message": "Invalid request"}}
+
+    request_id = request.get("id")
+    method = METHODS.get(request["method"])
+    if method is None:
+        return {
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "error": {"code": METHOD_NOT_FOUND, "message": f"Unknown method: {request['method']}"}
+        }
+
+    params = request.get("params") or {}
+    # Check the arguments up front, so a TypeError raised inside the method
+    # is reported as the internal error it is rather than as bad params
+    try:
+        if not isinstance(params, dict):
+            raise TypeError("params must be an object")
+        inspect.signature(method).bind(kg, **params)
+    except TypeError as e:
+        return {"jsonrpc": "2.0", "id": request_id, "error": {"code": INVALID_PARAMS, "message": str(e)}}
+
+    try:
+        result = method(kg, **params)
+    except LookupError as e:
+        return {"jsonrpc": "2.0", "id": request_id, "error": {"code": NOT_FOUND, "message": str(e.args[0] if e.args else e)}}
+    except InvalidValue as e:
+        return {"jsonrpc": "2.0", "id": request_id, "error": {"code": INVALID_VALUE, "message": str(e)}}
+    except Exception as e:
+        return {"jsonrpc": "2.0", "id": request_id, "error": {"code": INTERNAL_ERROR, "message": f"{type(e).__name__}: {e}"}}
+
+    return {"jsonrpc": "2.0", "id": request_id, "result": result}
+
+
+def serve(db_path: str):
+    # Keep stdout reserved for responses; stray prints go to stderr
+    out = sys.stdout
+    sys.stdout = sys.stderr
+
+    kg = LocalKG(db_path)
+    try:
+        for line in sys.stdin:
+            line = line.strip()
+            if not line:
+                continue
+            response = handle(kg, line)
+            out.write(json.dumps(response, default=str) + "\n")
+            out.flush()
+    finally:
+        kg.close()
+
+
+if __name__ == "__main__":
+    parser = argparse.ArgumentParser(description="Local KG JSON-RPC worker")
+    parser.add_argument("--db", default=DB_PATH, help="Path to the Universal Schema database")
+    args = parser.parse_args()
+    serve(args.db)
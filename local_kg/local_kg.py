@@ -1,6 +1,6 @@
 """
 Local Knowledge Graph Implementation (Universal Adapter)
-Captures errors, solutions, and context locally using the Universal Schema (v1.1).
+Captures errors, solutions, and context locally using the Universal Schema (v1.5).
 """
 import sqlite3
 import json
@@ -8,11 +8,28 @@ import hashlib
 import uuid
 from datetime import datetime
 from pathlib import Path
-from typing import Optional, List, Dict, Any
+from typing import Optional, List, Dict, Any, Sequence
 
 # Universal Schema Paths
 DB_PATH = "local_kg/universal_memory.sqlite"
 
+# Solution ranking: the author's 1-5 rating counts as this many pieces of
+# feedback, and solutions at or above this confidence are promoted
+RATING_WEIGHT = 2.0
+PROMOTE_CONFIDENCE = 0.75
+
+
+class InvalidValue(ValueError):
+    """An argument names something that exists but doesn't fit the call."""
+
+
+def _parse_effectiveness(value: Any) -> int:
+    """Effectiveness may arrive as a string ("5"); default to 3 (neutral)."""
+    try:
+        return int(str(value).strip())
+    except (TypeError, ValueError):
+        return 3
+
 class LocalKG:
     """
     Local Knowledge Graph for offline pattern/solution storage.
@@ -38,10 +55,15 @@ class LocalKG:
         error_category: Optional[str] = None,
         description: Optional[str] = None,
         context: Optional[Dict[str, Any]] = None,
-        severity: str = "medium"
+        severity: str = "medium",
+        language: Optional[str] = None,
+        phase: Optional[str] = None,
+        details: Optional[Dict[str, Any]] = None
     ) -> str:
         """
         Capture an error event and update pattern stats.
+        `details` (file_path, line_number, error_code, stack_trace, ...) is
+        merged into the event metadata; None values are skipped.
         """
         cursor = self.conn.cursor()
         
@@ -54,16 +76,18 @@ class LocalKG:
         meta = {
             "severity": severity,
             "category": error_category,
+            "language": language,
             "context": context,
             "signature": sig
         }
+        meta.update({k: v for k, v in (details or {}).items() if v is not None})
         
         cursor.execute(
             """INSERT INTO events (id, type, phase, content, metadata)
                VALUES (?, 'error', ?, ?, ?)""",
             (
                 event_id,
-                "UNKNOWN", # Phase tracking not native to this CLI tool yet
+                phase or "UNKNOWN",
                 description or f"Error: {error_category}",
                 json.dumps(meta, default=str) # Safe JSON dump handling dates/etc
             )
@@ -74,10 +98,12 @@ class LocalKG:
         existing = cursor.fetchone()
         
         if existing:
+            # A recurring error reopens a resolved pattern (v1.2+)
+            reopen = ", resolved_at = NULL" if self._has_column("patterns", "resolved_at") else ""
             cursor.execute(
-                """UPDATE patterns 
+                f"""UPDATE patterns 
                    SET occurrence_count = occurrence_count + 1,
-                       last_seen_at = CURRENT_TIMESTAMP
+                       last_seen_at = CURRENT_TIMESTAMP{reopen}
                    WHERE id = ?""",
                 (existing['id'],)
             )
@@ -96,19 +122,37 @@ class LocalKG:
         self,
         error_signature: str,
         solution_text: str,
-        effectiveness: int = 3
+        effectiveness: int = 3,
+        details: Optional[Dict[str, Any]] = None
     ) -> str:
         """
         Record a solution for a known error pattern.
+        `details` (title, code_snippet, resolution_steps, time_to_resolve_minutes,
+        was_successful) is merged into the event metadata; None values are skipped.
+        The pattern's promoted solution becomes its best-ranked one (see get_solutions).
         """
+        event_id = self._insert_solution(error_signature, solution_text, effectiveness, details)
+        self.conn.commit()
+        return event_id
+
+    def _insert_solution(
+        self,
+        error_signature: str,
+        solution_text: str,
+        effectiveness: int = 3,
+        details: Optional[Dict[str, Any]] = None
+    ) -> str:
+        """add_solution without committing."""
         cursor = self.conn.cursor()
         event_id = str(uuid.uuid4())
+        effectiveness = _parse_effectiveness(effectiveness)
         
         # 1. Log Solution Event
         meta = {
             "effectiveness": effectiveness,
             "target_signature": error_signature
         }
+        meta.update({k: v for k, v in (details or {}).items() if v is not None})
         
         cursor.execute(
             """INSERT INTO events (id, type, phase, content, metadata)
@@ -116,18 +160,610 @@ class LocalKG:
             (event_id, solution_text, json.dumps(meta, default=str))
         )
         
-        # 2. Update Pattern (if it works well)
-        if effectiveness >= 4:
+        # 2. Update Pattern (promote the best solution if it works well)
+        self._promote_best(error_signature)
+        return event_id
+
+    def record_feedback(
+        self,
+        solution_id: str,
+        was_helpful: bool,
+        resolution_time_minutes: Optional[int] = None,
+        comment: Optional[str] = None
+    ) -> str:
+        """
+        Record whether a solution (by its event id) helped when applied, like the
+        Central KG's solution_feedback. Re-ranks the pattern's solutions.
+        """
+        event_id = self._insert_feedback(solution_id, was_helpful, resolution_time_minutes, comment)
+        self.conn.commit()
+        return event_id
+
+    def _solution_signature(self, solution_id: str) -> str:
+        """Signature of the pattern a solution event belongs to (LookupError if none)."""
+        row = self.conn.execute(
+            """SELECT json_extract(metadata, '$.target_signature') AS signature FROM events
+               WHERE id = ? AND type = 'solution' AND json_valid(metadata)""",
+            (solution_id,)
+        ).fetchone()
+        if row is None or row['signature'] is None:
+            raise LookupError(f"solution {solution_id}")
+        return row['signature']
+
+    def _insert_feedback(
+        self,
+        solution_id: str,
+        was_helpful: bool,
+        resolution_time_minutes: Optional[int] = None,
+        comment: Optional[str] = None
+    ) -> str:
+        """record_feedback without committing."""
+        signature = self._solution_signature(solution_id)
+        cursor = self.conn.cursor()
+        event_id = str(uuid.uuid4())
+        meta = {
+            "solution_id": solution_id,
+            "target_signature": signature,
+            "was_helpful": bool(was_helpful),
+        }
+        if resolution_time_minutes is not None:
+            meta["resolution_time_minutes"] = resolution_time_minutes
+        if comment is not None:
+            meta["comment"] = comment
+        content = comment or ("Solution helped" if was_helpful else "Solution did not help")
+        cursor.execute(
+            """INSERT INTO events (id, type, phase, content, metadata)
+               VALUES (?, 'solution_feedback', 'UNKNOWN', ?, ?)""",
+            (event_id, content, json.dumps(meta, default=str))
+        )
+        self._promote_best(signature)
+        return event_id
+
+    def _promote_best(self, error_signature: str):
+        """Promote the best-ranked solution if its confidence is at least 0.75;
+        withdraw a promoted solution that no longer qualifies."""
+        ranked = self.get_solutions(error_signature)
+        row = self.conn.execute(
+            "SELECT solution FROM patterns WHERE signature = ?", (error_signature,)
+        ).fetchone()
+        if row is None:
+            return
+        current = row['solution']
+        if ranked and ranked[0]["confidence"] >= PROMOTE_CONFIDENCE:
+            promoted = ranked[0]["description"]
+        elif any(s["description"] == current for s in ranked):
+            promoted = None
+        else:
+            promoted = current
+        if promoted != current:
+            self.conn.execute(
+                "UPDATE patterns SET solution = ? WHERE signature = ?",
+                (promoted, error_signature)
+            )
+
+    def get_solutions(self, error_signature: str) -> List[Dict[str, Any]]:
+        """
+        Solutions recorded for a pattern with the fields of the Central KG
+        `solutions` table, best first. Fields older writers left out are derived:
+        the title from the first line, success from effectiveness >= 3.
+
+        success_rate is helpful / all feedback (0.0 without feedback); confidence
+        is that rate smoothed towards the author's rating (which counts as two
+        pieces of feedback), so a fresh solution rated 3 starts at 0.5. Ranked by
+        confidence, success rate, helpful feedback, then newest.
+        """
+        cursor = self.conn.cursor()
+        # SQLite before 3.45 cuts json_extract results at an escaped NUL, so
+        # such signatures scan every solution and are matched in Python below
+        cursor.execute(
+            """SELECT id, type, content, metadata, created_at FROM events
+               WHERE type IN ('solution', 'solution_feedback') AND json_valid(metadata)
+                 AND (json_extract(metadata, '$.target_signature') = ? OR instr(?, char(0)) > 0)
+               ORDER BY created_at, rowid""",
+            (error_signature, error_signature)
+        )
+        rows = [
+            row for row in cursor.fetchall()
+            if json.loads(row['metadata']).get("target_signature") == error_signature
+        ]
+        feedback: Dict[str, List[bool]] = {}
+        for row in rows:
+            meta = json.loads(row['metadata'])
+            if row['type'] == 'solution_feedback' and isinstance(meta.get("was_helpful"), bool):
+                feedback.setdefault(meta.get("solution_id"), []).append(meta["was_helpful"])
+
+        solutions = []
+        for row in rows:
+            if row['type'] != 'solution':
+                continue
+            meta = json.loads(row['metadata'])
+            description = row['content'] or ""
+            effectiveness = min(max(_parse_effectiveness(meta.get("effectiveness")), 1), 5)
+            steps = meta.get("resolution_steps") or []
+            if isinstance(steps, str):
+                # Legacy rows hold the steps as one block of text
+                steps = [line.strip() for line in steps.splitlines() if line.strip()]
+            title = meta.get("title")
+            if title is None:
+                title = next((line.strip() for line in description.splitlines() if line.strip()), "")
+                if len(title) > 80:
+                    title = title[:79] + "…"
+            was_successful = meta.get("was_successful")
+            solutions.append({
+                "id": row['id'],
+                "title": title,
+                "description": description,
+                "code_snippet": meta.get("code_snippet"),
+                "resolution_steps": [s for s in steps if isinstance(s, str)],
+                "time_to_resolve_minutes": meta.get("time_to_resolve_minutes"),
+                "was_successful": was_successful if isinstance(was_successful, bool) else effectiveness >= 3,
+                "effectiveness": effectiveness,
+                "created_at": row['created_at'],
+            })
+            votes = feedback.get(row['id'], [])
+            helpful = sum(1 for v in votes if v)
+            solutions[-1].update({
+                "helpful_count": helpful,
+                "unhelpful_count": len(votes) - helpful,
+                "success_rate": helpful / len(votes) if votes else 0.0,
+                "confidence": (helpful + RATING_WEIGHT * ((effectiveness - 1) / 4)) / (len(votes) + RATING_WEIGHT),
+            })
+
+        # Reversed first so that, of otherwise equal solutions, the later one wins
+        solutions.reverse()
+        solutions.sort(
+            key=lambda s: (s["confidence"], s["success_rate"], s["helpful_count"], s["created_at"] or ""),
+            reverse=True
+        )
+        return solutions
+
+    def resolve_pattern(
+        self,
+        error_signature: str,
+        content: Optional[str] = None,
+        context: Optional[Dict[str, Any]] = None
+    ) -> Optional[str]:
+        """
+        Mark an unresolved pattern resolved and log a 'resolution' event.
+        Returns the event id, or None if no unresolved pattern has this signature.
+        """
+        if not self._has_column("patterns", "resolved_at"):
+            return None
+        cursor = self.conn.cursor()
+        cursor.execute(
+            """UPDATE patterns SET resolved_at = CURRENT_TIMESTAMP
+               WHERE signature = ? AND resolved_at IS NULL""",
+            (error_signature,)
+        )
+        if cursor.rowcount == 0:
+            return None
+
+        event_id = str(uuid.uuid4())
+        meta = {
+            "target_signature": error_signature,
+            "context": context
+        }
+        cursor.execute(
+            """INSERT INTO events (id, type, phase, content, metadata)
+               VALUES (?, 'resolution', 'UNKNOWN', ?, ?)""",
+            (event_id, content, json.dumps(meta, default=str))
+        )
+        self.conn.commit()
+        return event_id
+
+    # An 'error' event no resolve_error resolution points at
+    _OPEN_ERROR_FILTER = """type = 'error' AND id NOT IN (
+        SELECT json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.error_event_id')
+        FROM events WHERE type = 'resolution'
+          AND json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.error_event_id') IS NOT NULL)"""
+
+    def resolve_error(
+        self,
+        error_event_id: str,
+        solution: Optional[Dict[str, Any]] = None,
+        solution_id: Optional[str] = None
+    ) -> Dict[str, Any]:
+        """
+        Link an error event (the id capture_error returned) to its fix: either a
+        new `solution` (fields as returned by get_solutions; the time to resolve is
+        filled in if missing) or an existing `solution_id`, which gets helpful
+        feedback. Logs a 'resolution' event with the minutes since capture and
+        marks the pattern resolved once none of its occurrences are open.
+        Resolving an occurrence twice returns the first resolution. An existing
+        solution must belong to the error's pattern (InvalidValue otherwise).
+        Everything is written in one transaction.
+        """
+        if (solution is None) == (solution_id is None):
+            raise InvalidValue("pass exactly one of solution or solution_id")
+        try:
+            resolution = self._resolve_error(error_event_id, solution, solution_id)
+        except BaseException:
+            self.conn.rollback()
+            raise
+        self.conn.commit()
+        return resolution
+
+    def _resolve_error(
+        self,
+        error_event_id: str,
+        solution: Optional[Dict[str, Any]],
+        solution_id: Optional[str]
+    ) -> Dict[str, Any]:
+        cursor = self.conn.cursor()
+        cursor.execute(
+            """SELECT id, content, metadata, created_at FROM events
+               WHERE type = 'resolution'
+                 AND json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.error_event_id') = ?""",
+            (error_event_id,)
+        )
+        row = cursor.fetchone()
+        if row is not None:
+            return self._error_resolution(row)
+
+        cursor.execute(
+            """SELECT json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.signature') AS signature,
+                      CAST(MAX(0, (julianday('now') - julianday(created_at)) * 1440) AS INTEGER) AS minutes
+               FROM events WHERE id = ? AND type = 'error'""",
+            (error_event_id,)
+        )
+        row = cursor.fetchone()
+        if row is None or row['signature'] is None:
+            raise LookupError(f"error event {error_event_id}")
+        signature, minutes = row['signature'], row['minutes']
+
+        if solution is not None:
+            details = {key: solution.get(key) for key in (
+                "title", "code_snippet", "resolution_steps", "time_to_resolve_minutes", "was_successful"
+            )}
+            details["resolution_steps"] = details["resolution_steps"] or None
+            if details["time_to_resolve_minutes"] is None:
+                details["time_to_resolve_minutes"] = minutes
+            solution_id = self._insert_solution(
+                signature, solution.get("description") or "", solution.get("effectiveness", 3), details
+            )
+        else:
+            solution_signature = self._solution_signature(solution_id)
+            if solution_signature != signature:
+                raise InvalidValue(
+                    f"solution {solution_id} is for '{solution_signature}', not '{signature}'"
+                )
+            self._insert_feedback(solution_id, True, resolution_time_minutes=minutes)
+
+        event_id = str(uuid.uuid4())
+        meta = {
+            "error_event_id": error_event_id,
+            "solution_id": solution_id,
+            "target_signature": signature,
+            "time_to_resolve_minutes": minutes,
+        }
+        cursor.execute(
+            """INSERT INTO events (id, type, phase, content, metadata)
+               VALUES (?, 'resolution', 'UNKNOWN', ?, ?)""",
+            (event_id, f"Resolved in {minutes} min", json.dumps(meta))
+        )
+
+        # The pattern is resolved once none of its occurrences are open
+        cursor.execute(
+            f"""SELECT COUNT(*) FROM events WHERE {self._OPEN_ERROR_FILTER}
+                AND json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.signature') = ?""",
+            (signature,)
+        )
+        if cursor.fetchone()[0] == 0 and self._has_column("patterns", "resolved_at"):
             cursor.execute(
-                """UPDATE patterns 
-                   SET solution = ? 
-                   WHERE signature = ?""",
-                (solution_text, error_signature)
+                """UPDATE patterns SET resolved_at = CURRENT_TIMESTAMP
+                   WHERE signature = ? AND resolved_at IS NULL""",
+                (signature,)
             )
-            
+
+        cursor.execute(
+            "SELECT id, content, metadata, created_at FROM events WHERE id = ?", (event_id,)
+        )
+        return self._error_resolution(cursor.fetchone())
+
+    @staticmethod
+    def _error_resolution(row: sqlite3.Row) -> Dict[str, Any]:
+        meta = json.loads(row['metadata'])
+        return {
+            "event_id": row['id'],
+            "error_event_id": meta["error_event_id"],
+            "signature": meta.get("target_signature") or "",
+            "solution_id": meta.get("solution_id") or "",
+            "time_to_resolve_minutes": meta.get("time_to_resolve_minutes") or 0,
+            "resolved_at": row['created_at'],
+        }
+
+    def open_errors(self, limit: int = 10) -> List[Dict[str, Any]]:
+        """Error events not yet linked to a fix by resolve_error, newest first."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT * FROM events WHERE {self._OPEN_ERROR_FILTER}
+                ORDER BY created_at DESC, rowid DESC LIMIT ?""",
+            (limit,)
+        )
+        return [dict(row) for row in cursor.fetchall()]
+
+    def get_resolution_stats(self) -> Dict[str, Any]:
+        """Open and resolved error counts and the mean time to fix in minutes."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT (SELECT COUNT(*) FROM events WHERE {self._OPEN_ERROR_FILTER}) AS open_errors,
+                       COUNT(*) AS resolved_errors,
+                       AVG(json_extract(CASE WHEN json_valid(metadata) THEN metadata END,
+                                        '$.time_to_resolve_minutes')) AS mean_time_to_fix_minutes
+                FROM events
+                WHERE type = 'resolution'
+                  AND json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.error_event_id') IS NOT NULL"""
+        )
+        return dict(cursor.fetchone())
+
+    # --- Central KG sync bookkeeping (sync_history v1.4, sync_outbox v1.5) ---
+    _SYNCED_IDS = (
+        "SELECT local_id FROM sync_history WHERE sync_type = ?1 AND sync_status = 'success' "
+        "UNION SELECT local_id FROM sync_outbox WHERE sync_type = ?1"
+    )
+    _OUTBOX_COLUMNS = (
+        "id, sync_type, local_id, idempotency_key, payload, status, attempts, "
+        "next_attempt_at, last_error, central_id, created_at"
+    )
+
+    def unsynced_patterns(self, limit: int = 100) -> List[Dict[str, Any]]:
+        """Patterns neither synced nor queued in sync_outbox, most frequent first."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT id, signature, category, description, solution,
+                      COALESCE(occurrence_count, 1) AS occurrence_count, last_seen_at, resolved_at
+               FROM patterns WHERE id NOT IN ({self._SYNCED_IDS})
+               ORDER BY occurrence_count DESC, rowid LIMIT ?""",
+            ("pattern", limit)
+        )
+        return [dict(row) for row in cursor.fetchall()]
+
+    def unsynced_solutions(self, limit: int = 100) -> List[Dict[str, Any]]:
+        """
+        Solutions neither synced nor queued in sync_outbox, oldest first:
+        the solution (as get_solutions returns it), its pattern's signature and
+        the pattern's central id once the pattern is synced.
+        """
+        target = "json_extract(CASE WHEN json_valid(e.metadata) THEN e.metadata END, '$.target_signature')"
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT e.id, {target} AS signature, (
+                    SELECT h.central_id FROM sync_history h JOIN patterns p ON p.id = h.local_id
+                    WHERE h.sync_type = 'pattern' AND h.sync_status = 'success'
+                      AND p.signature = {target}
+                    ORDER BY h.synced_at DESC, h.rowid DESC LIMIT 1) AS central_pattern_id
+               FROM events e
+               WHERE e.type = 'solution' AND {target} IS NOT NULL AND e.id NOT IN ({self._SYNCED_IDS})
+               ORDER BY e.created_at, e.rowid LIMIT ?""",
+            ("solution", limit)
+        )
+        rows = cursor.fetchall()
+        solutions: Dict[str, Dict[str, Any]] = {}
+        for signature in {row['signature'] for row in rows}:
+            solutions.update({s["id"]: s for s in self.get_solutions(signature)})
+        return [
+            {
+                "solution": solutions[row['id']],
+                "signature": row['signature'],
+                "central_pattern_id": row['central_pattern_id'],
+            }
+            for row in rows if row['id'] in solutions
+        ]
+
+    def record_sync(self, records: List[Dict[str, Any]]):
+        """Append sync_history rows (id, sync_type, local_id, central_id, status, error_message)."""
+        self.conn.executemany(
+            """INSERT INTO sync_history (id, sync_type, local_id, central_id, sync_status, error_message)
+               VALUES (?, ?, ?, ?, ?, ?)""",
+            [
+                (r["id"], r["sync_type"], r["local_id"], r.get("central_id"), r["status"], r.get("error_message"))
+                for r in records
+            ]
+        )
+        self.conn.commit()
+
+    def get_sync_history(self, limit: int = 10) -> List[Dict[str, Any]]:
+        """Most recent sync_history rows written for patterns and solutions, newest first."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            """SELECT id, sync_type, local_id, central_id, sync_status, error_message, synced_at
+               FROM sync_history ORDER BY synced_at DESC, rowid DESC LIMIT ?""",
+            (limit,)
+        )
+        history = []
+        for row in cursor.fetchall():
+            if row['sync_type'] not in ("pattern", "solution"):
+                continue
+            record = dict(row)
+            status = (record.pop("sync_status") or "").lower()
+            record["status"] = status if status in ("success", "pending") else "failed"
+            history.append(record)
+        return history
+
+    def enqueue_sync(self, items: List[Dict[str, Any]]) -> int:
+        """Queue outbox items, skipping ones already queued. Returns how many were added."""
+        cursor = self.conn.cursor()
+        added = 0
+        for item in items:
+            cursor.execute(
+                """INSERT OR IGNORE INTO sync_outbox
+                       (id, sync_type, local_id, idempotency_key, payload, status, attempts,
+                        last_error, central_id)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)""",
+                (
+                    item["id"], item["sync_type"], item["local_id"], item["idempotency_key"],
+                    json.dumps(item["payload"]), item.get("status", "pending"),
+                    item.get("attempts", 0), item.get("last_error"), item.get("central_id")
+                )
+            )
+            added += cursor.rowcount
+        self.conn.commit()
+        return added
+
+    def _outbox_item(self, row: sqlite3.Row) -> Dict[str, Any]:
+        item = dict(row)
+        try:
+            item["payload"] = json.loads(item["payload"])
+        except (TypeError, json.JSONDecodeError):
+            item["payload"] = None
+        return item
+
+    def due_sync_items(self, sync_type: str, limit: int = 100) -> List[Dict[str, Any]]:
+        """Pending outbox items of sync_type whose next attempt is due, oldest first."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT {self._OUTBOX_COLUMNS} FROM sync_outbox
+               WHERE sync_type = ? AND status = 'pending' AND next_attempt_at <= datetime('now')
+               ORDER BY created_at, rowid LIMIT ?""",
+            (sync_type, limit)
+        )
+        return [self._outbox_item(row) for row in cursor.fetchall()]
+
+    def update_sync_items(self, updates: List[Dict[str, Any]]):
+        """Record one attempt per item: status, central id, error and delay before the next one."""
+        self.conn.executemany(
+            """UPDATE sync_outbox SET status = ?, attempts = attempts + 1,
+                   central_id = COALESCE(?, central_id), last_error = ?,
+                   next_attempt_at = datetime('now', '+' || ? || ' seconds')
+               WHERE id = ?""",
+            [
+                (u["status"], u.get("central_id"), u.get("error"), int(u.get("retry_in_secs", 0)), u["id"])
+                for u in updates
+            ]
+        )
+        self.conn.commit()
+
+    def get_sync_outbox(self, status: Optional[str] = None, limit: int = 10) -> List[Dict[str, Any]]:
+        """Outbox items, optionally only those with status, newest first."""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT {self._OUTBOX_COLUMNS} FROM sync_outbox
+               WHERE ? IS NULL OR status = ?
+               ORDER BY created_at DESC, rowid DESC LIMIT ?""",
+            (status, status, limit)
+        )
+        return [
+            item for item in map(self._outbox_item, cursor.fetchall())
+            if item["sync_type"] in ("pattern", "solution")
+        ]
+
+    def record_event(
+        self,
+        event_type: str,
+        phase: str = "UNKNOWN",
+        content: Optional[str] = None,
+        metadata: Optional[Dict[str, Any]] = None
+    ) -> str:
+        """Append a raw event (milestone, decision, ...) to the event stream."""
+        event_id = str(uuid.uuid4())
+        self.conn.execute(
+            """INSERT INTO events (id, type, phase, content, metadata)
+               VALUES (?, ?, ?, ?, ?)""",
+            (event_id, event_type, phase, content, json.dumps(metadata, default=str))
+        )
         self.conn.commit()
         return event_id
 
+    def search_patterns(self, query: str, limit: int = 10) -> List[Dict[str, Any]]:
+        """Search patterns by signature, category or description (Universal Schema columns)."""
+        # Match the query literally: escape LIKE's wildcards and the escape char
+        escaped = query.replace("\\", "\\\\").replace("%", "\\%").replace("_", "\\_")
+        like = f"%{escaped}%"
+        resolved = ", resolved_at" if self._has_column("patterns", "resolved_at") else ""
+        cursor = self.conn.cursor()
+        cursor.execute(
+            f"""SELECT id, signature, category, description, solution,
+                      COALESCE(occurrence_count, 1) AS occurrence_count, last_seen_at{resolved}
+               FROM patterns
+               WHERE signature LIKE ? ESCAPE '\\' OR category LIKE ? ESCAPE '\\'
+                  OR description LIKE ? ESCAPE '\\'
+               ORDER BY occurrence_count DESC
+               LIMIT ?""",
+            (like, like, like, limit)
+        )
+        return [dict(row) for row in cursor.fetchall()]
+
+    def search_full_text(
+        self,
+        match: str,
+        limit: int = 10,
+        category: Optional[str] = None,
+        include_resolved: bool = True,
+        highlight_open: str = "[",
+        highlight_close: str = "]",
+        column_weights: Sequence[float] = (10.0, 5.0, 2.0),
+        snippet_tokens: int = 16
+    ) -> List[Dict[str, Any]]:
+        """
+        Rank patterns against an FTS5 MATCH expression using the v1.3+ `patterns_fts`
+        index (signature, description, solution text). Returns dicts with the
+        `pattern`, its BM25 `score` (higher is better) and a highlighted `snippet`.
+        `column_weights` (signature, description, solution) and `snippet_tokens`
+        default to the Rust bridge's values, which its worker requests pass explicitly.
+        """
+        signature_weight, description_weight, solution_weight = (float(w) for w in column_weights)
+        cursor = self.conn.cursor()
+        cursor.execute(
+            """SELECT p.id, p.signature, p.category, p.description, p.solution,
+                      COALESCE(p.occurrence_count, 1) AS occurrence_count, p.last_seen_at, p.resolved_at,
+                      -bm25(patterns_fts, 0.0, ?, ?, ?) AS score,
+                      snippet(patterns_fts, -1, ?, ?, '…', ?) AS snippet
+               FROM patterns_fts JOIN patterns p ON p.id = patterns_fts.pattern_id
+               WHERE patterns_fts MATCH ?
+                 AND (? IS NULL OR p.category = ?)
+                 AND (? OR p.resolved_at IS NULL)
+               ORDER BY score DESC
+               LIMIT ?""",
+            (signature_weight, description_weight, solution_weight,
+             highlight_open, highlight_close, int(snippet_tokens),
+             match, category, category, include_resolved, limit)
+        )
+        hits = []
+        for row in cursor.fetchall():
+            pattern = dict(row)
+            score = pattern.pop("score")
+            snippet = pattern.pop("snippet")
+            hits.append({"pattern": pattern, "score": score, "snippet": snippet})
+        return hits
+
+    def get_pattern_summary(self) -> Dict[str, Any]:
+        """Aggregate pattern/event counts and the most frequent patterns."""
+        cursor = self.conn.cursor()
+
+        cursor.execute("SELECT COUNT(*), COUNT(solution) FROM patterns")
+        total_patterns, patterns_with_solution = cursor.fetchone()
+
+        cursor.execute("SELECT COUNT(*) FROM events WHERE type = 'error'")
+        total_errors = cursor.fetchone()[0]
+
+        cursor.execute("SELECT COUNT(*) FROM events WHERE type = 'solution'")
+        total_solutions = cursor.fetchone()[0]
+
+        return {
+            "total_patterns": total_patterns,
+            "total_errors": total_errors,
+            "total_solutions": total_solutions,
+            "patterns_with_solution": patterns_with_solution,
+            "top_patterns": self.search_patterns("", 10)
+        }
+
+    def _has_column(self, table: str, column: str) -> bool:
+        return any(row['name'] == column for row in self.conn.execute(f"PRAGMA table_info({table})"))
+
+    def get_schema_version(self) -> Optional[str]:
+        """
+        Universal Schema version: the latest migration recorded in
+        schema_migrations, else project_info.schema_version (None if unversioned).
+        """
+        if self._has_column("schema_migrations", "version"):
+            versions = [row['version'] for row in self.conn.execute("SELECT version FROM schema_migrations")]
+            if versions:
+                return max(versions, key=lambda v: tuple(int(part) for part in v.split(".")))
+        if not self._has_column("project_info", "schema_version"):
+            return None
+        row = self.conn.execute("SELECT schema_version FROM project_info LIMIT 1").fetchone()
+        return row['schema_version'] if row else None
+
     def generate_context_summary(self) -> str:
         """
         Analyzes recent events to generate a high-level summary for LLM injection.
@@ -148,7 +784,7 @@ class LocalKG:
             f"Project Status: ACTIVE. "
             f"Recent Errors (24h): {recent_errors}. "
             f"Last Milestone: {last_milestone_text}. "
-            f"Database: Universal Schema v1.1 (SQLite)."
+            f"Database: Universal Schema v1.5 (SQLite)."
         )
         
         # Update DB
@@ -166,7 +802,7 @@ class LocalKG:
         """Retrieve recent events for context."""
         cursor = self.conn.cursor()
         cursor.execute(
-            "SELECT * FROM events ORDER BY created_at DESC LIMIT ?", 
+            "SELECT * FROM events ORDER BY created_at DESC, rowid DESC LIMIT ?", 
             (limit,)
         )
         return [dict(row) for row in cursor.fetchall()]